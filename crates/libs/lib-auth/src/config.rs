@@ -1,5 +1,7 @@
 use std::sync::OnceLock;
 
+use sha2::{Digest, Sha256};
+
 pub fn auth_config() -> &'static AuthConfig {
     static AUTH_CONFIG: OnceLock<AuthConfig> = OnceLock::new();
     AUTH_CONFIG.get_or_init(|| {
@@ -8,18 +10,47 @@ pub fn auth_config() -> &'static AuthConfig {
     })
 }
 
+const DEFAULT_JWT_ISSUER: &str = "social-api";
+const DEFAULT_JWT_AUDIENCE: &str = "social";
+
 pub struct AuthConfig {
     jwt_access_secret: String,
     jwt_refresh_secret: String,
     jwt_reset_password_secret: String,
+    jwt_two_factor_secret: String,
+    jwt_keys_dir: Option<String>,
+    jwt_active_kid: Option<String>,
+    jwt_issuer: String,
+    jwt_audience: String,
+    totp_encryption_key: [u8; 32],
 }
 
 impl AuthConfig {
     fn load_from_env() -> lib_utils::env::Result<Self> {
+        let jwt_two_factor_secret = lib_utils::env::get_env("JWT_TWO_FACTOR_SECRET")?;
+        let totp_encryption_key = match lib_utils::env::get_env("TOTP_ENCRYPTION_KEY") {
+            Ok(key) => hex::decode(key.trim())
+                .ok()
+                .and_then(|key| key.try_into().ok())
+                .ok_or(lib_utils::env::Error::Invalid)?,
+            Err(lib_utils::env::Error::NotFound) => {
+                Sha256::digest(format!("totp-secret:{jwt_two_factor_secret}")).into()
+            }
+            Err(e) => return Err(e),
+        };
+
         Ok(Self {
             jwt_access_secret: lib_utils::env::get_env("JWT_ACCESS_SECRET")?,
             jwt_refresh_secret: lib_utils::env::get_env("JWT_REFRESH_SECRET")?,
             jwt_reset_password_secret: lib_utils::env::get_env("JWT_RESET_PASSWORD_SECRET")?,
+            jwt_two_factor_secret,
+            jwt_keys_dir: lib_utils::env::get_env("JWT_KEYS_DIR").ok(),
+            jwt_active_kid: lib_utils::env::get_env("JWT_ACTIVE_KID").ok(),
+            jwt_issuer: lib_utils::env::get_env("JWT_ISSUER")
+                .unwrap_or_else(|_| DEFAULT_JWT_ISSUER.to_string()),
+            jwt_audience: lib_utils::env::get_env("JWT_AUDIENCE")
+                .unwrap_or_else(|_| DEFAULT_JWT_AUDIENCE.to_string()),
+            totp_encryption_key,
         })
     }
 
@@ -34,4 +65,147 @@ impl AuthConfig {
     pub fn jwt_reset_password_secret(&self) -> &str {
         &self.jwt_reset_password_secret
     }
+
+    pub fn jwt_two_factor_secret(&self) -> &str {
+        &self.jwt_two_factor_secret
+    }
+
+    /// Directory with `<kid>.pub.pem`/`<kid>.pem` files. When set, access tokens are signed
+    /// with the `JWT_ACTIVE_KID` key instead of `JWT_ACCESS_SECRET`.
+    pub fn jwt_keys_dir(&self) -> Option<&str> {
+        self.jwt_keys_dir.as_deref()
+    }
+
+    pub fn jwt_active_kid(&self) -> Option<&str> {
+        self.jwt_active_kid.as_deref()
+    }
+
+    /// `iss` claim put into and required from every token.
+    pub fn jwt_issuer(&self) -> &str {
+        &self.jwt_issuer
+    }
+
+    /// `aud` claim put into and required from every token.
+    pub fn jwt_audience(&self) -> &str {
+        &self.jwt_audience
+    }
+
+    /// AES-256 key for TOTP secrets at rest: `TOTP_ENCRYPTION_KEY` as 64 hex chars,
+    /// derived from `JWT_TWO_FACTOR_SECRET` when unset.
+    pub fn totp_encryption_key(&self) -> &[u8; 32] {
+        &self.totp_encryption_key
+    }
+}
+
+pub fn pwd_config() -> &'static PwdConfig {
+    static PWD_CONFIG: OnceLock<PwdConfig> = OnceLock::new();
+    PWD_CONFIG.get_or_init(|| {
+        PwdConfig::load_from_env()
+            .unwrap_or_else(|err| panic!("PANIC WHILE LOADING PWD CONFIG: {}", err))
+    })
+}
+
+/// Argon2id cost parameters from `PWD_ARGON2_*`. Unset variables fall back to the
+/// `argon2::Config::default()` values (OWASP: 19 MiB, 2 passes, 1 lane).
+/// Password policy settings come from `PWD_MIN_LENGTH` and `PWD_BREACHED_FILE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PwdConfig {
+    pub mem_cost: u32,
+    pub time_cost: u32,
+    pub lanes: u32,
+    pub min_length: usize,
+    pub breached_file: Option<String>,
+}
+
+impl Default for PwdConfig {
+    fn default() -> Self {
+        let config = argon2::Config::default();
+        Self {
+            mem_cost: config.mem_cost,
+            time_cost: config.time_cost,
+            lanes: config.lanes,
+            min_length: 8,
+            breached_file: None,
+        }
+    }
+}
+
+impl PwdConfig {
+    fn load_from_env() -> lib_utils::env::Result<Self> {
+        let default = Self::default();
+
+        Ok(Self {
+            mem_cost: env_or("PWD_ARGON2_MEM_COST", default.mem_cost)?,
+            time_cost: env_or("PWD_ARGON2_TIME_COST", default.time_cost)?,
+            lanes: env_or("PWD_ARGON2_LANES", default.lanes)?,
+            min_length: env_or("PWD_MIN_LENGTH", default.min_length)?,
+            breached_file: lib_utils::env::get_env("PWD_BREACHED_FILE").ok(),
+        })
+    }
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> lib_utils::env::Result<T> {
+    match lib_utils::env::get_parsed_env(key) {
+        Err(lib_utils::env::Error::NotFound) => Ok(default),
+        result => result,
+    }
+}
+
+pub fn oidc_config() -> &'static OidcConfig {
+    static OIDC_CONFIG: OnceLock<OidcConfig> = OnceLock::new();
+    OIDC_CONFIG.get_or_init(|| {
+        OidcConfig::load_from_env()
+            .unwrap_or_else(|err| panic!("PANIC WHILE LOADING OIDC CONFIG: {}", err))
+    })
+}
+
+/// Login providers listed in `OIDC_PROVIDERS`, each configured by `OIDC_<NAME>_*` variables.
+pub struct OidcConfig {
+    providers: Vec<OidcProviderConfig>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OidcProviderConfig {
+    pub name: String,
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_uri: String,
+    pub scopes: Vec<String>,
+}
+
+impl OidcConfig {
+    fn load_from_env() -> lib_utils::env::Result<Self> {
+        let names = lib_utils::env::get_env("OIDC_PROVIDERS").unwrap_or_default();
+
+        let providers = names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                let var = |key: &str| format!("OIDC_{}_{}", name.to_uppercase(), key);
+                let scopes = lib_utils::env::get_env(&var("SCOPES"))
+                    .unwrap_or_else(|_| "openid email profile".to_string());
+
+                Ok(OidcProviderConfig {
+                    name: name.to_lowercase(),
+                    issuer: lib_utils::env::get_env(&var("ISSUER"))?,
+                    client_id: lib_utils::env::get_env(&var("CLIENT_ID"))?,
+                    client_secret: lib_utils::env::get_env(&var("CLIENT_SECRET"))?,
+                    redirect_uri: lib_utils::env::get_env(&var("REDIRECT_URI"))?,
+                    scopes: scopes.split_whitespace().map(str::to_string).collect(),
+                })
+            })
+            .collect::<lib_utils::env::Result<Vec<_>>>()?;
+
+        Ok(Self { providers })
+    }
+
+    pub fn provider(&self, name: &str) -> Option<&OidcProviderConfig> {
+        self.providers.iter().find(|provider| provider.name == name)
+    }
+
+    pub fn provider_names(&self) -> impl Iterator<Item = &str> {
+        self.providers.iter().map(|provider| provider.name.as_str())
+    }
 }
@@ -1,5 +1,7 @@
 #![allow(warnings)]
 
 mod config;
+pub mod oidc;
 pub mod pwd;
 pub mod token;
+pub mod totp;
@@ -0,0 +1,28 @@
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("OIDC provider `{0}` is not configured")]
+    UnknownProvider(String),
+
+    #[error("OIDC request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("OIDC provider responded with an error: {0}")]
+    Provider(String),
+
+    #[error("OIDC provider metadata does not match the configured issuer")]
+    IssuerMismatch,
+
+    #[error("OIDC provider metadata has an invalid `{0}`")]
+    InvalidMetadata(&'static str),
+
+    #[error("Signing key `{0}` is not published by the provider")]
+    UnknownKey(String),
+
+    #[error("ID token is invalid")]
+    InvalidIdToken,
+
+    #[error("ID token nonce does not match")]
+    NonceMismatch,
+}
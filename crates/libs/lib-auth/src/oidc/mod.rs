@@ -0,0 +1,481 @@
+pub mod error;
+
+pub use self::error::{Error, Result};
+pub use crate::config::{oidc_config, OidcProviderConfig};
+
+use data_encoding::BASE64URL_NOPAD;
+use jsonwebtoken::jwk::{AlgorithmParameters, JwkSet};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use rand_core::{OsRng, RngCore};
+use reqwest::Url;
+use serde::{Deserialize, Deserializer};
+use sha2::{Digest, Sha256};
+use std::sync::RwLock;
+
+/// Endpoints published by the provider at `/.well-known/openid-configuration`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+/// PKCE pair (RFC 7636). The verifier stays on our side, the challenge goes to the provider.
+pub struct Pkce {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+impl Pkce {
+    pub fn generate() -> Self {
+        let verifier = random_token();
+        let challenge = pkce_challenge(&verifier);
+        Self {
+            verifier,
+            challenge,
+        }
+    }
+}
+
+pub fn pkce_challenge(verifier: &str) -> String {
+    BASE64URL_NOPAD.encode(&Sha256::digest(verifier.as_bytes()))
+}
+
+/// URL-safe random value used for `state`, `nonce` and PKCE verifiers.
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    BASE64URL_NOPAD.encode(&bytes)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenResponse {
+    pub id_token: String,
+    pub access_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    pub email: Option<String>,
+    #[serde(default, deserialize_with = "bool_or_string")]
+    pub email_verified: bool,
+    pub preferred_username: Option<String>,
+    pub name: Option<String>,
+    pub nonce: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+/// Client for one provider. Discovery runs once, so it is meant to be kept and reused
+/// between logins; signing keys are refetched only when an unknown `kid` shows up.
+pub struct OidcClient {
+    config: OidcProviderConfig,
+    issuer: String,
+    authorization_endpoint: Url,
+    token_endpoint: Url,
+    jwks_uri: Url,
+    jwks: RwLock<Option<JwkSet>>,
+    http: reqwest::Client,
+}
+
+impl OidcClient {
+    pub async fn discover(config: OidcProviderConfig) -> Result<Self> {
+        let http = reqwest::Client::new();
+
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            config.issuer.trim_end_matches('/')
+        );
+        let metadata: ProviderMetadata = http
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if metadata.issuer.trim_end_matches('/') != config.issuer.trim_end_matches('/') {
+            return Err(Error::IssuerMismatch);
+        }
+
+        Ok(Self {
+            config,
+            authorization_endpoint: endpoint(
+                &metadata.authorization_endpoint,
+                "authorization_endpoint",
+            )?,
+            token_endpoint: endpoint(&metadata.token_endpoint, "token_endpoint")?,
+            jwks_uri: endpoint(&metadata.jwks_uri, "jwks_uri")?,
+            issuer: metadata.issuer,
+            jwks: RwLock::new(None),
+            http,
+        })
+    }
+
+    pub fn provider(&self) -> &str {
+        &self.config.name
+    }
+
+    pub fn authorization_url(&self, state: &str, nonce: &str, pkce_challenge: &str) -> String {
+        let scope = self.config.scopes.join(" ");
+        let mut url = self.authorization_endpoint.clone();
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &self.config.redirect_uri)
+            .append_pair("scope", &scope)
+            .append_pair("state", state)
+            .append_pair("nonce", nonce)
+            .append_pair("code_challenge", pkce_challenge)
+            .append_pair("code_challenge_method", "S256");
+        url.to_string()
+    }
+
+    pub async fn exchange_code(&self, code: &str, pkce_verifier: &str) -> Result<TokenResponse> {
+        let response = self
+            .http
+            .post(self.token_endpoint.clone())
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", &self.config.redirect_uri),
+                ("client_id", &self.config.client_id),
+                ("client_secret", &self.config.client_secret),
+                ("code_verifier", pkce_verifier),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let message = match response.json::<ErrorResponse>().await {
+                Ok(err) => err.error_description.unwrap_or(err.error),
+                Err(_) => "token request was rejected".to_string(),
+            };
+            return Err(Error::Provider(message));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    pub async fn verify_id_token(&self, id_token: &str, nonce: &str) -> Result<IdTokenClaims> {
+        let header = decode_header(id_token).map_err(|_| Error::InvalidIdToken)?;
+        if header.alg != Algorithm::RS256 {
+            return Err(Error::InvalidIdToken);
+        }
+
+        let kid = header.kid.unwrap_or_default();
+        let key = match self.cached_key(&kid)? {
+            Some(key) => key,
+            // The provider may have rotated its keys: refetch the set once
+            None => {
+                self.refresh_jwks().await?;
+                self.cached_key(&kid)?
+                    .ok_or_else(|| Error::UnknownKey(kid.clone()))?
+            }
+        };
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+
+        let claims = decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map_err(|_| Error::InvalidIdToken)?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(Error::NonceMismatch);
+        }
+
+        Ok(claims)
+    }
+
+    fn cached_key(&self, kid: &str) -> Result<Option<DecodingKey>> {
+        let jwks = self.jwks.read().unwrap_or_else(|e| e.into_inner());
+        let Some(jwk) = jwks.as_ref().and_then(|jwks| {
+            jwks.keys
+                .iter()
+                .find(|jwk| jwk.common.key_id.as_deref() == Some(kid))
+        }) else {
+            return Ok(None);
+        };
+
+        match &jwk.algorithm {
+            AlgorithmParameters::RSA(rsa) => DecodingKey::from_rsa_components(&rsa.n, &rsa.e)
+                .map(Some)
+                .map_err(|_| Error::InvalidIdToken),
+            _ => Err(Error::UnknownKey(kid.to_string())),
+        }
+    }
+
+    async fn refresh_jwks(&self) -> Result<()> {
+        let jwks: JwkSet = self
+            .http
+            .get(self.jwks_uri.clone())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        *self.jwks.write().unwrap_or_else(|e| e.into_inner()) = Some(jwks);
+        Ok(())
+    }
+
+    /// Finishes the authorization-code flow: exchanges the code and returns verified claims.
+    pub async fn authenticate(
+        &self,
+        code: &str,
+        pkce_verifier: &str,
+        nonce: &str,
+    ) -> Result<IdTokenClaims> {
+        let tokens = self.exchange_code(code, pkce_verifier).await?;
+        self.verify_id_token(&tokens.id_token, nonce).await
+    }
+}
+
+fn endpoint(url: &str, field: &'static str) -> Result<Url> {
+    Url::parse(url)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "https" | "http"))
+        .ok_or(Error::InvalidMetadata(field))
+}
+
+// Some providers send `email_verified` as a string
+fn bool_or_string<'de, D>(deserializer: D) -> core::result::Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BoolOrString {
+        Bool(bool),
+        String(String),
+    }
+
+    Ok(match BoolOrString::deserialize(deserializer)? {
+        BoolOrString::Bool(value) => value,
+        BoolOrString::String(value) => value.eq_ignore_ascii_case("true"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{Context, Result};
+    use axum::{
+        extract::State,
+        routing::{get, post},
+        Form, Json, Router,
+    };
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde::Serialize;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const MOCK_KEY: &str = include_str!("testdata/mock_idp_key.pem");
+    const MOCK_JWKS: &str = include_str!("testdata/mock_idp_jwks.json");
+    const CLIENT_ID: &str = "test-client";
+    const NONCE: &str = "test-nonce";
+
+    struct MockIdp {
+        issuer: String,
+        code_challenge: String,
+        authorization_endpoint: String,
+        jwks_requests: AtomicUsize,
+    }
+
+    #[derive(Serialize)]
+    struct MockIdTokenClaims<'a> {
+        iss: &'a str,
+        aud: &'a str,
+        sub: &'a str,
+        email: &'a str,
+        email_verified: &'a str,
+        nonce: &'a str,
+        iat: usize,
+        exp: usize,
+    }
+
+    async fn discovery(State(idp): State<Arc<MockIdp>>) -> Json<serde_json::Value> {
+        Json(serde_json::json!({
+            "issuer": idp.issuer,
+            "authorization_endpoint": idp.authorization_endpoint,
+            "token_endpoint": format!("{}/token", idp.issuer),
+            "jwks_uri": format!("{}/jwks", idp.issuer),
+        }))
+    }
+
+    async fn jwks(State(idp): State<Arc<MockIdp>>) -> &'static str {
+        idp.jwks_requests.fetch_add(1, Ordering::SeqCst);
+        MOCK_JWKS
+    }
+
+    async fn token(
+        State(idp): State<Arc<MockIdp>>,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> (axum::http::StatusCode, Json<serde_json::Value>) {
+        let verifier = form.get("code_verifier").cloned().unwrap_or_default();
+        if form.get("code").map(String::as_str) != Some("mock-code")
+            || pkce_challenge(&verifier) != idp.code_challenge
+        {
+            return (
+                axum::http::StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "invalid_grant" })),
+            );
+        }
+
+        let now = lib_utils::time::utc_now_plus_sec_usize(0);
+        let claims = MockIdTokenClaims {
+            iss: &idp.issuer,
+            aud: CLIENT_ID,
+            sub: "mock-subject",
+            email: "user@example.com",
+            email_verified: "true",
+            nonce: NONCE,
+            iat: now,
+            exp: now + 300,
+        };
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some("mock-key".to_string());
+        let id_token = encode(
+            &header,
+            &claims,
+            &EncodingKey::from_rsa_pem(MOCK_KEY.as_bytes()).unwrap(),
+        )
+        .unwrap();
+
+        (
+            axum::http::StatusCode::OK,
+            Json(serde_json::json!({ "id_token": id_token, "access_token": "mock-access" })),
+        )
+    }
+
+    async fn start_mock_idp(pkce: &Pkce) -> Result<OidcProviderConfig> {
+        Ok(start_mock_idp_with(pkce, None).await?.0)
+    }
+
+    /// `authorization_endpoint` replaces the one published in the discovery document
+    async fn start_mock_idp_with(
+        pkce: &Pkce,
+        authorization_endpoint: Option<&str>,
+    ) -> Result<(OidcProviderConfig, Arc<MockIdp>)> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let issuer = format!("http://{}", listener.local_addr()?);
+
+        let idp = Arc::new(MockIdp {
+            authorization_endpoint: authorization_endpoint
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("{}/authorize", issuer)),
+            issuer: issuer.clone(),
+            code_challenge: pkce.challenge.clone(),
+            jwks_requests: AtomicUsize::new(0),
+        });
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/jwks", get(jwks))
+            .route("/token", post(token))
+            .with_state(idp.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let config = OidcProviderConfig {
+            name: "mock".to_string(),
+            issuer,
+            client_id: CLIENT_ID.to_string(),
+            client_secret: "test-secret".to_string(),
+            redirect_uri: "http://localhost:5173/oidc/mock/callback".to_string(),
+            scopes: vec!["openid".to_string(), "email".to_string()],
+        };
+        Ok((config, idp))
+    }
+
+    #[tokio::test]
+    async fn test_authorization_code_flow_with_pkce() -> Result<()> {
+        let pkce = Pkce::generate();
+        let client = OidcClient::discover(start_mock_idp(&pkce).await?)
+            .await
+            .context("Failed to discover mock provider")?;
+
+        let url = client.authorization_url("test-state", NONCE, &pkce.challenge);
+        assert!(url.contains("code_challenge_method=S256"));
+        assert!(url.contains(&format!("code_challenge={}", pkce.challenge)));
+
+        let claims = client
+            .authenticate("mock-code", &pkce.verifier, NONCE)
+            .await
+            .context("Failed to authenticate against mock provider")?;
+
+        assert_eq!(claims.sub, "mock-subject");
+        assert_eq!(claims.email.as_deref(), Some("user@example.com"));
+        assert!(
+            claims.email_verified,
+            "String `email_verified` should parse"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wrong_pkce_verifier_is_rejected() -> Result<()> {
+        let pkce = Pkce::generate();
+        let client = OidcClient::discover(start_mock_idp(&pkce).await?).await?;
+
+        let result = client.exchange_code("mock-code", "wrong-verifier").await;
+
+        assert!(matches!(result, Err(Error::Provider(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wrong_nonce_is_rejected() -> Result<()> {
+        let pkce = Pkce::generate();
+        let client = OidcClient::discover(start_mock_idp(&pkce).await?).await?;
+
+        let result = client
+            .authenticate("mock-code", &pkce.verifier, "other-nonce")
+            .await;
+
+        assert!(matches!(result, Err(Error::NonceMismatch)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_metadata_is_rejected_on_discovery() -> Result<()> {
+        let pkce = Pkce::generate();
+        let (config, _) = start_mock_idp_with(&pkce, Some("not a url")).await?;
+
+        let result = OidcClient::discover(config).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::InvalidMetadata("authorization_endpoint"))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_signing_keys_are_cached() -> Result<()> {
+        let pkce = Pkce::generate();
+        let (config, idp) = start_mock_idp_with(&pkce, None).await?;
+        let client = OidcClient::discover(config).await?;
+
+        for _ in 0..2 {
+            client
+                .authenticate("mock-code", &pkce.verifier, NONCE)
+                .await?;
+        }
+
+        assert_eq!(idp.jwks_requests.load(Ordering::SeqCst), 1);
+
+        Ok(())
+    }
+}
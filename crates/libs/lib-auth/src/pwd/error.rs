@@ -7,4 +7,22 @@ pub enum Error {
 
     #[error("Password validation error")]
     Validate,
+
+    #[error("Password must be at least {0} characters long")]
+    TooShort(usize),
+
+    #[error("Password must be at most {0} characters long")]
+    TooLong(usize),
+
+    #[error("Password is too easy to guess, use a longer or more varied one")]
+    TooWeak,
+
+    #[error("Password must not contain your nickname or email")]
+    ContainsPersonalInfo,
+
+    #[error("Password has appeared in a data breach, choose a different one")]
+    Breached,
+
+    #[error("Breached password list error: {0}")]
+    BreachedList(String),
 }
@@ -1,10 +1,28 @@
 pub mod error;
+mod policy;
 
-use argon2::Config;
+pub use self::policy::{check_password_policy, init_breached_list, BreachedList};
+pub use crate::config::{pwd_config, PwdConfig};
+
+use argon2::{Config, Variant, Version};
 use rand_core::{OsRng, RngCore};
 
+const HASH_LENGTH: u32 = 32;
+
 pub fn hash_password(password: &str) -> error::Result<String> {
-    let config = Config::default();
+    hash_password_with(password, pwd_config())
+}
+
+fn hash_password_with(password: &str, pwd_config: &PwdConfig) -> error::Result<String> {
+    let config = Config {
+        variant: Variant::Argon2id,
+        version: Version::Version13,
+        mem_cost: pwd_config.mem_cost,
+        time_cost: pwd_config.time_cost,
+        lanes: pwd_config.lanes,
+        hash_length: HASH_LENGTH,
+        ..Config::default()
+    };
     let mut salt = [0u8; 16];
     OsRng.fill_bytes(&mut salt);
     argon2::hash_encoded(password.as_bytes(), &salt, &config).map_err(|_| error::Error::Hash)
@@ -14,6 +32,78 @@ pub fn validate_password(password: &str, hashed: &str) -> error::Result<bool> {
     argon2::verify_encoded(hashed, password.as_bytes()).map_err(|_| error::Error::Validate)
 }
 
+/// Whether a stored hash was produced with weaker parameters than the configured ones
+/// (or another variant/version) and should be replaced after the next successful login.
+pub fn needs_rehash(hashed: &str) -> bool {
+    needs_rehash_with(hashed, pwd_config())
+}
+
+fn needs_rehash_with(hashed: &str, pwd_config: &PwdConfig) -> bool {
+    let Some(params) = HashParams::parse(hashed) else {
+        return true;
+    };
+
+    params.variant != Variant::Argon2id.as_lowercase_str()
+        || params.version != Version::Version13.as_u32()
+        || params.mem_cost < pwd_config.mem_cost
+        || params.time_cost < pwd_config.time_cost
+        || params.lanes < pwd_config.lanes
+        || params.hash_length < HASH_LENGTH
+}
+
+/// Parameters of a PHC-encoded hash: `$argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>`.
+struct HashParams<'a> {
+    variant: &'a str,
+    version: u32,
+    mem_cost: u32,
+    time_cost: u32,
+    lanes: u32,
+    hash_length: u32,
+}
+
+impl<'a> HashParams<'a> {
+    fn parse(hashed: &'a str) -> Option<Self> {
+        let mut parts = hashed.strip_prefix('$')?.split('$');
+        let variant = parts.next()?;
+
+        let mut next = parts.next()?;
+        // Hashes from before version 1.3 have no `v=` part
+        let version = match next.strip_prefix("v=") {
+            Some(version) => {
+                let version = version.parse().ok()?;
+                next = parts.next()?;
+                version
+            }
+            None => Version::Version10.as_u32(),
+        };
+
+        let (mut mem_cost, mut time_cost, mut lanes) = (None, None, None);
+        for option in next.split(',') {
+            let (name, value) = option.split_once('=')?;
+            let value = value.parse().ok()?;
+            match name {
+                "m" => mem_cost = Some(value),
+                "t" => time_cost = Some(value),
+                "p" => lanes = Some(value),
+                _ => return None,
+            }
+        }
+
+        let _salt = parts.next()?;
+        let hash = parts.next()?;
+
+        Some(Self {
+            variant,
+            version,
+            mem_cost: mem_cost?,
+            time_cost: time_cost?,
+            lanes: lanes?,
+            // Unpadded base64: every 4 characters carry 3 bytes
+            hash_length: (hash.len() * 3 / 4) as u32,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -63,6 +153,52 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_needs_rehash_on_weaker_parameters() -> Result<()> {
+        let weak = PwdConfig {
+            mem_cost: 4096,
+            time_cost: 1,
+            lanes: 1,
+            ..PwdConfig::default()
+        };
+        let strong = PwdConfig {
+            mem_cost: 8192,
+            time_cost: 2,
+            lanes: 1,
+            ..PwdConfig::default()
+        };
+
+        let weak_hash = hash_password_with("secure_password", &weak)?;
+        let strong_hash = hash_password_with("secure_password", &strong)?;
+
+        assert!(needs_rehash_with(&weak_hash, &strong));
+        assert!(
+            !needs_rehash_with(&strong_hash, &strong),
+            "A hash with the configured parameters should be kept"
+        );
+        assert!(
+            !needs_rehash_with(&strong_hash, &weak),
+            "A stronger hash should not be downgraded"
+        );
+        assert!(validate_password("secure_password", &weak_hash)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_needs_rehash_on_other_variant() -> Result<()> {
+        let config = Config {
+            variant: Variant::Argon2i,
+            ..Config::default()
+        };
+        let argon2i_hash = argon2::hash_encoded(b"secure_password", b"somesaltsomesalt", &config)?;
+
+        assert!(needs_rehash_with(&argon2i_hash, &PwdConfig::default()));
+        assert!(needs_rehash_with("invalid_hash", &PwdConfig::default()));
+
+        Ok(())
+    }
+
     #[test]
     fn test_validate_password_invalid_hash() -> Result<()> {
         let password = "secure_password";
@@ -0,0 +1,304 @@
+use super::error::{Error, Result};
+use super::{pwd_config, PwdConfig};
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use sha1::{Digest, Sha1};
+
+const MAX_LENGTH: usize = 128;
+const MIN_ENTROPY_BITS: f64 = 40.0;
+/// Personal values shorter than this are too common to reject passwords over.
+const MIN_PERSONAL_LENGTH: usize = 3;
+/// Length of the SHA-1 prefix used as the bucket key, as in the k-anonymity range API.
+const PREFIX_LENGTH: usize = 5;
+
+/// Checks a new password against the configured policy. `personal` holds values the password
+/// must not contain, such as the nickname and email of the account.
+pub fn check_password_policy(password: &str, personal: &[&str]) -> Result<()> {
+    check_password_policy_with(password, personal, pwd_config(), breached_list())
+}
+
+fn check_password_policy_with(
+    password: &str,
+    personal: &[&str],
+    config: &PwdConfig,
+    breached: Option<&BreachedList>,
+) -> Result<()> {
+    let length = password.chars().count();
+    if length < config.min_length {
+        return Err(Error::TooShort(config.min_length));
+    }
+    if length > MAX_LENGTH {
+        return Err(Error::TooLong(MAX_LENGTH));
+    }
+
+    if contains_personal_info(password, personal) {
+        return Err(Error::ContainsPersonalInfo);
+    }
+
+    if estimate_entropy(password) < MIN_ENTROPY_BITS {
+        return Err(Error::TooWeak);
+    }
+
+    if breached.is_some_and(|breached| breached.contains(password)) {
+        return Err(Error::Breached);
+    }
+
+    Ok(())
+}
+
+fn contains_personal_info(password: &str, personal: &[&str]) -> bool {
+    let password = password.to_lowercase();
+
+    personal
+        .iter()
+        .flat_map(|value| {
+            // For emails the local part is what people tend to reuse
+            let local_part = value.split_once('@').map(|(local, _)| local);
+            std::iter::once(*value).chain(local_part)
+        })
+        .map(str::to_lowercase)
+        .filter(|value| value.chars().count() >= MIN_PERSONAL_LENGTH)
+        .any(|value| password.contains(&value))
+}
+
+/// Rough brute-force entropy: character pool size by the classes used, counted only over
+/// characters that do not merely repeat or continue a sequence (`aaaa`, `1234`, `abcd`).
+fn estimate_entropy(password: &str) -> f64 {
+    let chars: Vec<char> = password.chars().collect();
+
+    let mut pool = 0;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if chars.iter().any(|c| c.is_ascii_punctuation() || *c == ' ') {
+        pool += 33;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        pool += 100;
+    }
+    if pool == 0 {
+        return 0.0;
+    }
+
+    let effective_length = chars
+        .iter()
+        .enumerate()
+        .filter(|(i, c)| {
+            let Some(prev) = i.checked_sub(1).map(|i| chars[i]) else {
+                return true;
+            };
+            let step = **c as i64 - prev as i64;
+            !(-1..=1).contains(&step)
+        })
+        .count();
+
+    effective_length as f64 * (pool as f64).log2()
+}
+
+/// Known-breached passwords as SHA-1 hashes, bucketed by their 5-character prefix.
+///
+/// Two layouts are accepted, both optionally followed by `:<count>` on each line:
+/// - a single file with one full 40-character hex hash per line, meant for a top-N list;
+/// - k-anonymity range files, named after their prefix (`21BD1` or `21BD1.txt`), holding
+///   35-character suffixes. `path` may point at one such file or a directory of them.
+pub struct BreachedList {
+    buckets: HashMap<String, Vec<String>>,
+}
+
+impl BreachedList {
+    pub fn load(path: &str) -> Result<Self> {
+        let read_err = |err: std::io::Error| Error::BreachedList(format!("{}: {}", path, err));
+
+        let mut buckets: HashMap<String, Vec<String>> = HashMap::new();
+        if Path::new(path).is_dir() {
+            for entry in fs::read_dir(path).map_err(read_err)? {
+                let file = entry.map_err(read_err)?.path();
+                if file.is_file() {
+                    let content = fs::read_to_string(&file).map_err(read_err)?;
+                    parse_into(&mut buckets, &content, range_prefix(&file));
+                }
+            }
+        } else {
+            let content = fs::read_to_string(path).map_err(read_err)?;
+            parse_into(&mut buckets, &content, range_prefix(Path::new(path)));
+        }
+
+        if buckets.is_empty() {
+            return Err(Error::BreachedList(format!("{}: no hashes found", path)));
+        }
+        for suffixes in buckets.values_mut() {
+            suffixes.sort_unstable();
+            suffixes.dedup();
+        }
+
+        Ok(Self { buckets })
+    }
+
+    pub fn contains(&self, password: &str) -> bool {
+        let hash = hex::encode_upper(Sha1::digest(password.as_bytes()));
+        let (prefix, suffix) = hash.split_at(PREFIX_LENGTH);
+
+        self.buckets
+            .get(prefix)
+            .is_some_and(|suffixes| suffixes.binary_search(&suffix.to_string()).is_ok())
+    }
+}
+
+/// Prefix of a range file, taken from its name without the extension.
+fn range_prefix(file: &Path) -> Option<String> {
+    let stem = file.file_stem()?.to_str()?;
+    is_hex(stem, PREFIX_LENGTH).then(|| stem.to_uppercase())
+}
+
+fn parse_into(buckets: &mut HashMap<String, Vec<String>>, content: &str, prefix: Option<String>) {
+    for line in content.lines() {
+        let hash = line.split(':').next().unwrap_or_default().trim();
+
+        let (prefix, suffix) = if is_hex(hash, 40) {
+            let hash = hash.to_uppercase();
+            let (prefix, suffix) = hash.split_at(PREFIX_LENGTH);
+            (prefix.to_string(), suffix.to_string())
+        } else if let Some(prefix) = prefix.as_ref().filter(|_| is_hex(hash, 40 - PREFIX_LENGTH)) {
+            (prefix.clone(), hash.to_uppercase())
+        } else {
+            continue;
+        };
+
+        buckets.entry(prefix).or_default().push(suffix);
+    }
+}
+
+fn is_hex(value: &str, len: usize) -> bool {
+    value.len() == len && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+static BREACHED_LIST: OnceLock<Option<BreachedList>> = OnceLock::new();
+
+/// Loads the configured breached list at startup, so a missing or malformed file stops the
+/// server instead of the first password change.
+pub fn init_breached_list() -> Result<()> {
+    if BREACHED_LIST.get().is_none() {
+        let list = pwd_config()
+            .breached_file
+            .as_deref()
+            .map(BreachedList::load)
+            .transpose()?;
+        let _ = BREACHED_LIST.set(list);
+    }
+
+    Ok(())
+}
+
+/// The list loaded by [`init_breached_list`]; without it only the local rules apply.
+fn breached_list() -> Option<&'static BreachedList> {
+    BREACHED_LIST.get().and_then(Option::as_ref)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn check(password: &str) -> super::Result<()> {
+        check_password_policy_with(
+            password,
+            &["alice", "alice.smith@example.com"],
+            &PwdConfig::default(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_policy_accepts_strong_password() -> Result<()> {
+        check("correct horse battery staple")?;
+        check("Tr0ub4dor&3x!")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_rejects_weak_passwords() -> Result<()> {
+        assert!(matches!(check("short1"), Err(Error::TooShort(8))));
+        assert!(matches!(check(&"a1".repeat(65)), Err(Error::TooLong(_))));
+        assert!(matches!(check("aaaaaaaaaaaa"), Err(Error::TooWeak)));
+        assert!(matches!(check("abcdefgh12345678"), Err(Error::TooWeak)));
+        assert!(matches!(check("password"), Err(Error::TooWeak)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_rejects_personal_info() -> Result<()> {
+        assert!(matches!(
+            check("MyNameIsAlice!2024"),
+            Err(Error::ContainsPersonalInfo)
+        ));
+        assert!(matches!(
+            check("x_alice.smith_x9"),
+            Err(Error::ContainsPersonalInfo)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_rejects_breached_password() -> Result<()> {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/pwd/testdata/breached.txt");
+        let breached = BreachedList::load(path)?;
+
+        assert!(breached.contains("P@ssw0rd2024!"));
+        assert!(!breached.contains("correct horse battery staple"));
+        assert!(matches!(
+            check_password_policy_with(
+                "P@ssw0rd2024!",
+                &[],
+                &PwdConfig::default(),
+                Some(&breached)
+            ),
+            Err(Error::Breached)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_breached_list_loads_range_files() -> Result<()> {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/pwd/testdata/range");
+        let file = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/pwd/testdata/range/25713.txt"
+        );
+
+        for path in [dir, file] {
+            let breached = BreachedList::load(path)?;
+            assert!(breached.contains("Qwerty!Dragon77"));
+            assert!(!breached.contains("P@ssw0rd2024!"));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_breached_list_rejects_empty_file() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/pwd/testdata/empty.txt");
+
+        assert!(matches!(
+            BreachedList::load(path),
+            Err(Error::BreachedList(_))
+        ));
+        assert!(matches!(
+            BreachedList::load("/nonexistent/breached.txt"),
+            Err(Error::BreachedList(_))
+        ));
+    }
+}
@@ -14,6 +14,12 @@ pub enum Error {
     #[error("Token expired")]
     Expired,
 
+    #[error("Token has a different type")]
+    WrongType,
+
     #[error("Invalid token format")]
     InvalidFormat,
+
+    #[error("Signing key error: {0}")]
+    Key(String),
 }
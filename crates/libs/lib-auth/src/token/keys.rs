@@ -0,0 +1,401 @@
+use super::{auth_config, Error, Result, TokenClaims};
+
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use data_encoding::{BASE64, BASE64URL_NOPAD};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, TokenData,
+    Validation,
+};
+
+const OID_RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+const OID_ED25519: &[u8] = &[0x2b, 0x65, 0x70];
+
+static SIGNING_KEYS: OnceLock<Option<SigningKeys>> = OnceLock::new();
+
+/// Asymmetric keys for access tokens, loaded from `JWT_KEYS_DIR` when it is set.
+pub fn signing_keys() -> Option<&'static SigningKeys> {
+    SIGNING_KEYS
+        .get_or_init(|| {
+            load_configured().unwrap_or_else(|err| panic!("PANIC WHILE LOADING JWT KEYS: {}", err))
+        })
+        .as_ref()
+}
+
+/// Loads and checks the configured keys at startup, so a broken key directory stops the
+/// server instead of the first request that issues a token.
+pub fn init_signing_keys() -> Result<()> {
+    if SIGNING_KEYS.get().is_none() {
+        let _ = SIGNING_KEYS.set(load_configured()?);
+    }
+
+    Ok(())
+}
+
+fn load_configured() -> Result<Option<SigningKeys>> {
+    let Some(dir) = auth_config().jwt_keys_dir() else {
+        return Ok(None);
+    };
+    let active_kid = auth_config()
+        .jwt_active_kid()
+        .ok_or_else(|| Error::Key("JWT_ACTIVE_KID is not set".to_string()))?;
+
+    SigningKeys::load(Path::new(dir), active_kid).map(Some)
+}
+
+/// JWK Set for `/.well-known/jwks.json`, empty while tokens are signed with a shared secret.
+pub fn jwks() -> JwkSet {
+    signing_keys()
+        .map(SigningKeys::jwks)
+        .unwrap_or(JwkSet { keys: Vec::new() })
+}
+
+pub struct SigningKey {
+    kid: String,
+    algorithm: Algorithm,
+    encoding_key: Option<EncodingKey>,
+    decoding_key: DecodingKey,
+    jwk: Jwk,
+}
+
+/// Key ring of a key directory. Every `<kid>.pub.pem` is trusted for verification, so retired
+/// keys stay valid until their tokens expire; only the active key needs its `<kid>.pem`.
+pub struct SigningKeys {
+    active_kid: String,
+    keys: Vec<SigningKey>,
+}
+
+impl SigningKeys {
+    pub fn load(dir: &Path, active_kid: &str) -> Result<Self> {
+        let entries = fs::read_dir(dir).map_err(|e| key_error(dir, e))?;
+
+        let mut keys = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|e| key_error(dir, e))?.path();
+            let Some(kid) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".pub.pem"))
+            else {
+                continue;
+            };
+
+            keys.push(SigningKey::load(dir, kid)?);
+        }
+        keys.sort_by(|a, b| a.kid.cmp(&b.kid));
+
+        let active = keys
+            .iter()
+            .find(|key| key.kid == active_kid)
+            .ok_or_else(|| Error::Key(format!("active key '{active_kid}' not found")))?;
+        if active.encoding_key.is_none() {
+            return Err(Error::Key(format!(
+                "private key for active key '{active_kid}' not found"
+            )));
+        }
+
+        Ok(Self {
+            active_kid: active_kid.to_string(),
+            keys,
+        })
+    }
+
+    pub fn active_kid(&self) -> &str {
+        &self.active_kid
+    }
+
+    fn find(&self, kid: &str) -> Option<&SigningKey> {
+        self.keys.iter().find(|key| key.kid == kid)
+    }
+
+    pub fn encode(&self, claims: &TokenClaims) -> Result<String> {
+        let key = self.find(&self.active_kid).ok_or(Error::Generation)?;
+        let encoding_key = key.encoding_key.as_ref().ok_or(Error::Generation)?;
+
+        let mut header = Header::new(key.algorithm);
+        header.kid = Some(key.kid.clone());
+
+        encode(&header, claims, encoding_key).map_err(|_| Error::Generation)
+    }
+
+    pub fn decode(&self, token: &str, validation: &Validation) -> Result<TokenData<TokenClaims>> {
+        let header = decode_header(token).map_err(|_| Error::InvalidFormat)?;
+        let key = header
+            .kid
+            .as_deref()
+            .and_then(|kid| self.find(kid))
+            .filter(|key| key.algorithm == header.alg)
+            .ok_or(Error::Verification)?;
+
+        let mut validation = validation.clone();
+        validation.algorithms = vec![key.algorithm];
+
+        decode::<TokenClaims>(token, &key.decoding_key, &validation)
+            .map_err(super::map_decode_error)
+    }
+
+    /// Public half of every key, as served on `/.well-known/jwks.json`.
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self.keys.iter().map(|key| key.jwk.clone()).collect(),
+        }
+    }
+}
+
+impl SigningKey {
+    fn load(dir: &Path, kid: &str) -> Result<Self> {
+        let public_path = dir.join(format!("{kid}.pub.pem"));
+        let public_pem =
+            fs::read_to_string(&public_path).map_err(|e| key_error(&public_path, e))?;
+
+        let private_path = dir.join(format!("{kid}.pem"));
+        let private_pem = match fs::read_to_string(&private_path) {
+            Ok(pem) => Some(pem),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(key_error(&private_path, e)),
+        };
+
+        let der = pem_to_der(&public_pem).ok_or_else(|| invalid_key(kid))?;
+        let (algorithm, params) = parse_public_key(&der).ok_or_else(|| invalid_key(kid))?;
+
+        let (decoding_key, encoding_key) = match algorithm {
+            Algorithm::RS256 => (
+                DecodingKey::from_rsa_pem(public_pem.as_bytes()),
+                private_pem.map(|pem| EncodingKey::from_rsa_pem(pem.as_bytes())),
+            ),
+            _ => (
+                DecodingKey::from_ed_pem(public_pem.as_bytes()),
+                private_pem.map(|pem| EncodingKey::from_ed_pem(pem.as_bytes())),
+            ),
+        };
+        let decoding_key = decoding_key.map_err(|_| invalid_key(kid))?;
+        let encoding_key = encoding_key.transpose().map_err(|_| invalid_key(kid))?;
+
+        let jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(match algorithm {
+                    Algorithm::RS256 => KeyAlgorithm::RS256,
+                    _ => KeyAlgorithm::EdDSA,
+                }),
+                key_id: Some(kid.to_string()),
+                ..Default::default()
+            },
+            algorithm: params,
+        };
+
+        Ok(Self {
+            kid: kid.to_string(),
+            algorithm,
+            encoding_key,
+            decoding_key,
+            jwk,
+        })
+    }
+}
+
+fn key_error(path: &Path, err: std::io::Error) -> Error {
+    Error::Key(format!("{}: {err}", path.display()))
+}
+
+fn invalid_key(kid: &str) -> Error {
+    Error::Key(format!("'{kid}' is not an RSA or Ed25519 key"))
+}
+
+fn pem_to_der(pem: &str) -> Option<Vec<u8>> {
+    let body: String = pem
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .map(str::trim)
+        .collect();
+
+    BASE64.decode(body.as_bytes()).ok()
+}
+
+/// Reads a DER SubjectPublicKeyInfo, returning the signing algorithm and JWK parameters.
+fn parse_public_key(der: &[u8]) -> Option<(Algorithm, AlgorithmParameters)> {
+    let (spki, _) = read_der(der, 0x30)?;
+    let (algorithm_id, rest) = read_der(spki, 0x30)?;
+    let (oid, _) = read_der(algorithm_id, 0x06)?;
+    let (bits, _) = read_der(rest, 0x03)?;
+    // The first byte of a BIT STRING counts unused bits, always zero for keys.
+    let key = bits.strip_prefix(&[0])?;
+
+    if oid == OID_RSA_ENCRYPTION {
+        let (rsa_key, _) = read_der(key, 0x30)?;
+        let (n, rest) = read_der(rsa_key, 0x02)?;
+        let (e, _) = read_der(rest, 0x02)?;
+
+        let params = RSAKeyParameters {
+            key_type: RSAKeyType::RSA,
+            n: BASE64URL_NOPAD.encode(strip_leading_zeros(n)),
+            e: BASE64URL_NOPAD.encode(strip_leading_zeros(e)),
+        };
+        Some((Algorithm::RS256, AlgorithmParameters::RSA(params)))
+    } else if oid == OID_ED25519 && key.len() == 32 {
+        let params = OctetKeyPairParameters {
+            key_type: OctetKeyPairType::OctetKeyPair,
+            curve: EllipticCurve::Ed25519,
+            x: BASE64URL_NOPAD.encode(key),
+        };
+        Some((Algorithm::EdDSA, AlgorithmParameters::OctetKeyPair(params)))
+    } else {
+        None
+    }
+}
+
+/// Splits one DER element with the expected tag off the input: `(contents, rest)`.
+fn read_der(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (&actual_tag, input) = input.split_first()?;
+    if actual_tag != tag {
+        return None;
+    }
+
+    let (&first, mut input) = input.split_first()?;
+    let len = if first < 0x80 {
+        first as usize
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || input.len() < count {
+            return None;
+        }
+        let (len_bytes, rest) = input.split_at(count);
+        input = rest;
+        len_bytes
+            .iter()
+            .fold(0usize, |len, &byte| (len << 8) | byte as usize)
+    };
+
+    if input.len() < len {
+        return None;
+    }
+    Some(input.split_at(len))
+}
+
+fn strip_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|&byte| byte != 0)
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{Context, Result};
+    use std::path::PathBuf;
+
+    fn testdata() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("src/token/testdata")
+    }
+
+    fn claims() -> TokenClaims {
+        TokenClaims {
+            sub: "test_user".to_string(),
+            typ: "access".to_string(),
+            iss: "test_issuer".to_string(),
+            aud: "test_audience".to_string(),
+            jti: "test_jti".to_string(),
+            iat: 0,
+            exp: (chrono::Utc::now() + chrono::Duration::minutes(30)).timestamp() as usize,
+            role: None,
+            act: None,
+            sid: None,
+            generation: None,
+        }
+    }
+
+    fn validation() -> Validation {
+        let mut validation = Validation::default();
+        validation.set_audience(&["test_audience"]);
+        validation
+    }
+
+    #[test]
+    fn test_sign_and_verify_with_each_algorithm() -> Result<()> {
+        for (kid, alg) in [
+            ("rsa-2025", Algorithm::RS256),
+            ("ed-2026", Algorithm::EdDSA),
+        ] {
+            let keys = SigningKeys::load(&testdata(), kid).context("Failed to load keys")?;
+
+            let token = keys.encode(&claims()).context("Failed to sign token")?;
+            let header = decode_header(&token)?;
+            assert_eq!(header.kid.as_deref(), Some(kid));
+            assert_eq!(header.alg, alg);
+
+            let decoded = keys
+                .decode(&token, &validation())
+                .context("Failed to verify token")?;
+            assert_eq!(decoded.claims.sub, "test_user");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rotated_key_still_verifies() -> Result<()> {
+        let old_keys = SigningKeys::load(&testdata(), "rsa-2025")?;
+        let new_keys = SigningKeys::load(&testdata(), "ed-2026")?;
+
+        let token = old_keys.encode(&claims())?;
+
+        assert!(
+            new_keys.decode(&token, &validation()).is_ok(),
+            "Tokens signed by a retired key should verify until they expire"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_unknown_kid_and_missing_active_key() -> Result<()> {
+        let keys = SigningKeys::load(&testdata(), "rsa-2025")?;
+
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("rsa-2025".to_string());
+        let forged = encode(&header, &claims(), &EncodingKey::from_secret(b"secret"))?;
+
+        assert!(
+            keys.decode(&forged, &validation()).is_err(),
+            "A token whose alg does not match the key should be rejected"
+        );
+        assert!(SigningKeys::load(&testdata(), "missing").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_jwks_lists_public_keys() -> Result<()> {
+        let keys = SigningKeys::load(&testdata(), "rsa-2025")?;
+        let jwks = keys.jwks();
+
+        let kids: Vec<_> = jwks
+            .keys
+            .iter()
+            .filter_map(|jwk| jwk.common.key_id.as_deref())
+            .collect();
+        assert_eq!(kids, ["ed-2026", "rsa-2025"]);
+
+        let jwk = jwks
+            .find("rsa-2025")
+            .context("Active key missing from JWKS")?;
+        let token = keys.encode(&claims())?;
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&["test_audience"]);
+        let decoded = decode::<TokenClaims>(&token, &DecodingKey::from_jwk(jwk)?, &validation);
+        assert!(
+            decoded.is_ok(),
+            "Other services should verify tokens with the JWKS alone"
+        );
+
+        Ok(())
+    }
+}
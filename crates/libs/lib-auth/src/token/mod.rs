@@ -1,22 +1,47 @@
 pub mod error;
+mod keys;
 
 pub use self::error::{Error, Result};
+pub use self::keys::{init_signing_keys, jwks, signing_keys, SigningKeys};
 pub use crate::config::auth_config;
-use lib_utils::time::{utc_now_plus_days_usize, utc_now_plus_min_usize};
+use crate::totp::constant_time_eq;
+use lib_utils::time::{utc_now_plus_days_usize, utc_now_plus_min_usize, utc_now_plus_sec_usize};
 
 use std::str::FromStr;
 
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, TokenData, Validation};
+use rand_core::{OsRng, RngCore};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum TokenType {
     Access,
     Refresh,
     ResetPassword,
+    TwoFactor,
 }
 
+impl TokenType {
+    /// Value of the `typ` claim, so a token of one type is never accepted as another
+    /// even if the secrets are misconfigured to be the same.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenType::Access => "access",
+            TokenType::Refresh => "refresh",
+            TokenType::ResetPassword => "reset_password",
+            TokenType::TwoFactor => "two_factor",
+        }
+    }
+}
+
+pub const ACCESS_TOKEN_TTL_MIN: i64 = 30;
+pub const IMPERSONATION_TOKEN_TTL_MIN: i64 = 15;
+
 pub struct Token {
     pub ident: String,
+    pub jti: String,
+    pub iat: usize,
     pub exp: usize,
     pub sign: String,
 }
@@ -24,13 +49,19 @@ pub struct Token {
 impl Token {
     pub fn new(ident: &str, token_type: TokenType) -> Result<Self> {
         let exp = match token_type {
-            TokenType::Access => utc_now_plus_min_usize(30),
+            TokenType::Access => utc_now_plus_min_usize(ACCESS_TOKEN_TTL_MIN),
             TokenType::Refresh => utc_now_plus_days_usize(30),
             TokenType::ResetPassword => utc_now_plus_min_usize(60),
+            TokenType::TwoFactor => utc_now_plus_min_usize(5),
         };
 
+        let mut jti = [0u8; 16];
+        OsRng.fill_bytes(&mut jti);
+
         let token = Self {
             ident: ident.to_string(),
+            jti: hex::encode(jti),
+            iat: utc_now_plus_sec_usize(0),
             exp,
             sign: secret_by_type(token_type)?,
         };
@@ -42,7 +73,36 @@ impl Token {
 #[derive(Serialize, Deserialize)]
 pub struct TokenClaims {
     pub sub: String,
+    pub typ: String,
+    pub iss: String,
+    pub aud: String,
+    pub jti: String,
+    pub iat: usize,
     pub exp: usize,
+    /// Role of the user at the time an access token was issued.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// Admin acting as `sub` in an impersonation access token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<String>,
+    /// Session (token family) a refresh token belongs to, or the impersonation session
+    /// of an access token with `act`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    /// Rotation counter of a refresh token within its session.
+    #[serde(default, rename = "gen", skip_serializing_if = "Option::is_none")]
+    pub generation: Option<i32>,
+}
+
+/// Outcome of comparing a presented refresh token with the state stored for its session.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Rotation {
+    /// The token is the latest one issued for the session and may be rotated.
+    Current,
+    /// The token was already rotated away: it is being replayed and the session must be revoked.
+    Reused,
+    /// The token is not bound to a session generation we know about.
+    Unknown,
 }
 
 fn secret_by_type(token_type: TokenType) -> Result<String> {
@@ -50,6 +110,7 @@ fn secret_by_type(token_type: TokenType) -> Result<String> {
         TokenType::Access => Ok(&auth_config().jwt_access_secret()),
         TokenType::Refresh => Ok(&auth_config().jwt_refresh_secret()),
         TokenType::ResetPassword => Ok(&auth_config().jwt_reset_password_secret()),
+        TokenType::TwoFactor => Ok(&auth_config().jwt_two_factor_secret()),
         _ => Err(Error::Secret),
     };
     let secret = secret?;
@@ -57,45 +118,168 @@ fn secret_by_type(token_type: TokenType) -> Result<String> {
     Ok(secret.to_string())
 }
 
+impl TokenClaims {
+    fn new(token: Token, token_type: TokenType) -> Self {
+        Self {
+            sub: token.ident,
+            typ: token_type.as_str().to_string(),
+            iss: auth_config().jwt_issuer().to_string(),
+            aud: auth_config().jwt_audience().to_string(),
+            jti: token.jti,
+            iat: token.iat,
+            exp: token.exp,
+            role: None,
+            act: None,
+            sid: None,
+            generation: None,
+        }
+    }
+}
+
 pub fn generate_token(user: &str, token_type: TokenType) -> Result<String> {
     let token = Token::new(user, token_type)?;
+    let sign = token.sign.clone();
+
+    encode_by_type(&TokenClaims::new(token, token_type), token_type, &sign)
+}
+
+/// Access token that also carries the user role, so it does not have to be looked up
+/// on every request.
+pub fn generate_access_token(user: &str, role: &str) -> Result<String> {
+    let token = Token::new(user, TokenType::Access)?;
+    let sign = token.sign.clone();
 
     let token_claims = TokenClaims {
-        sub: token.ident,
-        exp: token.exp,
+        role: Some(role.to_string()),
+        ..TokenClaims::new(token, TokenType::Access)
     };
 
-    let token = encode(
+    encode_by_type(&token_claims, TokenType::Access, &sign)
+}
+
+/// Access token for an admin (`actor`) to see the site as `user`. It expires sooner than
+/// a regular one and has no refresh token.
+pub fn generate_impersonation_token(
+    user: &str,
+    role: &str,
+    actor: &str,
+    impersonation_id: &str,
+) -> Result<String> {
+    let token = Token::new(user, TokenType::Access)?;
+    let sign = token.sign.clone();
+
+    let token_claims = TokenClaims {
+        exp: utc_now_plus_min_usize(IMPERSONATION_TOKEN_TTL_MIN),
+        role: Some(role.to_string()),
+        act: Some(actor.to_string()),
+        sid: Some(impersonation_id.to_string()),
+        ..TokenClaims::new(token, TokenType::Access)
+    };
+
+    encode_by_type(&token_claims, TokenType::Access, &sign)
+}
+
+pub fn generate_refresh_token(user: &str, session_id: &str, generation: i32) -> Result<String> {
+    let token = Token::new(user, TokenType::Refresh)?;
+    let sign = token.sign.clone();
+
+    let token_claims = TokenClaims {
+        sid: Some(session_id.to_string()),
+        generation: Some(generation),
+        ..TokenClaims::new(token, TokenType::Refresh)
+    };
+
+    encode_claims(&token_claims, &sign)
+}
+
+fn encode_by_type(claims: &TokenClaims, token_type: TokenType, sign: &str) -> Result<String> {
+    match signing_keys() {
+        Some(keys) if token_type == TokenType::Access => keys.encode(claims),
+        _ => encode_claims(claims, sign),
+    }
+}
+
+fn encode_claims(claims: &TokenClaims, sign: &str) -> Result<String> {
+    encode(
         &Header::default(),
-        &token_claims,
-        &EncodingKey::from_secret(token.sign.as_ref()),
+        claims,
+        &EncodingKey::from_secret(sign.as_ref()),
     )
-    .map_err(|_| Error::Generation)?;
+    .map_err(|_| Error::Generation)
+}
 
-    Ok(token)
+pub fn check_rotation(claims: &TokenClaims, current_generation: i32) -> Rotation {
+    match claims.generation {
+        Some(generation) if generation == current_generation => Rotation::Current,
+        Some(generation) if generation < current_generation => Rotation::Reused,
+        _ => Rotation::Unknown,
+    }
 }
 
 pub fn verify_token(token: &str, token_type: TokenType) -> Result<TokenData<TokenClaims>> {
-    let secret = secret_by_type(token_type)?;
-
-    let decoding_key = DecodingKey::from_secret(secret.as_ref());
-
     let mut validation = Validation::default();
     validation.validate_exp = true;
+    validation.set_issuer(&[auth_config().jwt_issuer()]);
+    validation.set_audience(&[auth_config().jwt_audience()]);
+    validation.set_required_spec_claims(&["exp", "sub", "iss", "aud"]);
+
+    let token_data = match signing_keys().filter(|_| token_type == TokenType::Access) {
+        Some(keys) => keys.decode(token, &validation)?,
+        None => {
+            let secret = secret_by_type(token_type)?;
+            let decoding_key = DecodingKey::from_secret(secret.as_ref());
 
-    let token_data = decode::<TokenClaims>(token, &decoding_key, &validation).map_err(|e| {
-        if e.kind()
-            .eq(&jsonwebtoken::errors::ErrorKind::ExpiredSignature)
-        {
-            Error::Expired
-        } else {
-            Error::Verification
+            decode::<TokenClaims>(token, &decoding_key, &validation).map_err(map_decode_error)?
         }
-    })?;
+    };
+
+    if token_data.claims.typ != token_type.as_str() {
+        return Err(Error::WrongType);
+    }
 
     Ok(token_data)
 }
 
+fn map_decode_error(e: jsonwebtoken::errors::Error) -> Error {
+    if e.kind()
+        .eq(&jsonwebtoken::errors::ErrorKind::ExpiredSignature)
+    {
+        Error::Expired
+    } else {
+        Error::Verification
+    }
+}
+
+/// Random single-use token for links sent by mail. Carries no claims, so it must be
+/// looked up in storage to be trusted.
+pub fn generate_opaque_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Token for the double-submit CSRF check: sent in a cookie the frontend can read and
+/// echoed back in a header, which a cross-site form cannot do.
+pub fn generate_csrf_token() -> String {
+    generate_opaque_token()
+}
+
+pub fn verify_csrf_token(cookie: &str, header: &str) -> bool {
+    !cookie.is_empty() && constant_time_eq(cookie.as_bytes(), header.as_bytes())
+}
+
+/// Prefix that tells personal access tokens apart from JWTs in the `Authorization` header.
+pub const API_TOKEN_PREFIX: &str = "pat_";
+
+/// Long-lived personal access token. Shown to the user once; only its hash is stored.
+pub fn generate_api_token() -> String {
+    format!("{}{}", API_TOKEN_PREFIX, generate_opaque_token())
+}
+
+pub fn hash_api_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,6 +304,176 @@ mod tests {
             "The 'sub' claim should match"
         );
         assert_eq!(decoded_data.claims.exp, exp, "The 'exp' claim should match");
+        assert_eq!(
+            decoded_data.claims.jti.len(),
+            32,
+            "The 'jti' claim should be 16 hex-encoded bytes"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_opaque_token() -> Result<()> {
+        let first = generate_opaque_token();
+        let second = generate_opaque_token();
+
+        assert_eq!(
+            first.len(),
+            64,
+            "Opaque token should be 32 hex-encoded bytes"
+        );
+        assert_ne!(first, second, "Opaque tokens should not repeat");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_csrf_token() -> Result<()> {
+        let token = generate_csrf_token();
+
+        assert!(verify_csrf_token(&token, &token));
+        assert!(!verify_csrf_token(&token, &generate_csrf_token()));
+        assert!(!verify_csrf_token(&token, ""));
+        assert!(!verify_csrf_token("", ""), "A missing cookie never matches");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_and_hash_api_token() -> Result<()> {
+        let token = generate_api_token();
+
+        assert!(token.starts_with(API_TOKEN_PREFIX));
+        assert_eq!(hash_api_token(&token), hash_api_token(&token));
+        assert_ne!(
+            hash_api_token(&token),
+            hash_api_token(&generate_api_token()),
+            "Different tokens should have different hashes"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_and_verify_refresh_token() -> Result<()> {
+        dotenvy::from_path(std::path::Path::new("../../.env")).ok();
+
+        let first = generate_refresh_token("test_user", "test_session", 0)
+            .context("Failed to generate token")?;
+        let second = generate_refresh_token("test_user", "test_session", 1)
+            .context("Failed to generate token")?;
+
+        assert_ne!(first, second, "Rotated refresh tokens should differ");
+
+        let decoded_data =
+            verify_token(&second, TokenType::Refresh).context("Failed to verify token")?;
+
+        assert_eq!(decoded_data.claims.sid.as_deref(), Some("test_session"));
+        assert_eq!(decoded_data.claims.generation, Some(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_rotation_detects_replay() -> Result<()> {
+        let claims = |generation| TokenClaims {
+            sub: "test_user".to_string(),
+            typ: TokenType::Refresh.as_str().to_string(),
+            iss: "test_issuer".to_string(),
+            aud: "test_audience".to_string(),
+            jti: "test_jti".to_string(),
+            iat: 0,
+            exp: 0,
+            role: None,
+            act: None,
+            sid: Some("test_session".to_string()),
+            generation,
+        };
+
+        assert_eq!(check_rotation(&claims(Some(2)), 2), Rotation::Current);
+        assert_eq!(
+            check_rotation(&claims(Some(1)), 2),
+            Rotation::Reused,
+            "A token from an earlier generation is a replay"
+        );
+        assert_eq!(check_rotation(&claims(Some(3)), 2), Rotation::Unknown);
+        assert_eq!(check_rotation(&claims(None), 2), Rotation::Unknown);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_access_token_carries_typed_claims() -> Result<()> {
+        dotenvy::from_path(std::path::Path::new("../../.env")).ok();
+        let access_token = generate_access_token("test_user", "moderator")?;
+
+        let claims = verify_token(&access_token, TokenType::Access)?.claims;
+        assert_eq!(claims.typ, "access");
+        assert_eq!(claims.role.as_deref(), Some("moderator"));
+        assert_eq!(claims.iss, auth_config().jwt_issuer());
+        assert_eq!(claims.aud, auth_config().jwt_audience());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_impersonation_token_carries_actor() -> Result<()> {
+        dotenvy::from_path(std::path::Path::new("../../.env")).ok();
+        let earliest_exp = utc_now_plus_min_usize(IMPERSONATION_TOKEN_TTL_MIN);
+        let token =
+            generate_impersonation_token("test_user", "user", "test_admin", "test_impersonation")?;
+        let latest_exp = utc_now_plus_min_usize(IMPERSONATION_TOKEN_TTL_MIN);
+
+        let claims = verify_token(&token, TokenType::Access)?.claims;
+        assert_eq!(claims.sub, "test_user");
+        assert_eq!(claims.act.as_deref(), Some("test_admin"));
+        assert_eq!(claims.sid.as_deref(), Some("test_impersonation"));
+        assert!(
+            (earliest_exp..=latest_exp).contains(&claims.exp),
+            "Impersonation tokens should expire sooner than regular access tokens"
+        );
+
+        let regular = verify_token(
+            &generate_access_token("test_user", "user")?,
+            TokenType::Access,
+        )?;
+        assert!(regular.claims.act.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_wrong_type_and_audience() -> Result<()> {
+        dotenvy::from_path(std::path::Path::new("../../.env")).ok();
+        let token = Token::new("test_user", TokenType::Access)?;
+        let sign = token.sign.clone();
+        let claims = TokenClaims::new(token, TokenType::Access);
+
+        let refresh_claims = TokenClaims {
+            typ: TokenType::Refresh.as_str().to_string(),
+            ..TokenClaims::new(
+                Token::new("test_user", TokenType::Access)?,
+                TokenType::Access,
+            )
+        };
+        let as_refresh = encode_claims(&refresh_claims, &sign)?;
+        assert!(
+            matches!(
+                verify_token(&as_refresh, TokenType::Access),
+                Err(Error::WrongType)
+            ),
+            "A token typed as refresh must not pass as access, even with the same secret"
+        );
+
+        let foreign = encode_claims(
+            &TokenClaims {
+                aud: "another_service".to_string(),
+                ..claims
+            },
+            &sign,
+        )?;
+        assert!(verify_token(&foreign, TokenType::Access).is_err());
 
         Ok(())
     }
@@ -0,0 +1,13 @@
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Invalid TOTP secret")]
+    InvalidSecret,
+
+    #[error("Failed to encrypt TOTP secret")]
+    Seal,
+
+    #[error("Stored TOTP secret cannot be decrypted")]
+    Unseal,
+}
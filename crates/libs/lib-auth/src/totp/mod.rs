@@ -0,0 +1,269 @@
+pub mod error;
+
+pub use self::error::{Error, Result};
+
+use data_encoding::{BASE32_NOPAD, BASE64};
+use hmac::{Hmac, Mac};
+use rand_core::{OsRng, RngCore};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::config::auth_config;
+
+/// RFC 6238 defaults understood by every authenticator app.
+pub const STEP_SEC: u64 = 30;
+pub const DIGITS: u32 = 6;
+/// How many steps around the current one are accepted to tolerate clock drift.
+pub const SKEW_STEPS: u64 = 1;
+
+const SECRET_LEN: usize = 20;
+const RECOVERY_CODE_LEN: usize = 10;
+/// Marks a secret encrypted by `seal_secret`. Secrets stored before encryption at rest
+/// are plain base32 and never contain `:`.
+const SEALED_PREFIX: &str = "v1:";
+
+pub fn generate_secret() -> String {
+    let mut secret = [0u8; SECRET_LEN];
+    OsRng.fill_bytes(&mut secret);
+    BASE32_NOPAD.encode(&secret)
+}
+
+pub fn otpauth_uri(secret: &str, account: &str, issuer: &str) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={DIGITS}&period={STEP_SEC}",
+        issuer = percent_encode(issuer),
+        account = percent_encode(account),
+    )
+}
+
+pub fn generate_code(secret: &str, step: u64) -> Result<String> {
+    let key = decode_secret(secret)?;
+    Ok(hotp(&key, step))
+}
+
+/// Checks `code` against the steps around `unix_time` and returns the matched step,
+/// so the caller can refuse a code from a step that was already used.
+pub fn verify_code(secret: &str, code: &str, unix_time: u64) -> Result<Option<u64>> {
+    let key = decode_secret(secret)?;
+    let current = unix_time / STEP_SEC;
+
+    let matched = (current.saturating_sub(SKEW_STEPS)..=current + SKEW_STEPS)
+        .find(|step| constant_time_eq(hotp(&key, *step).as_bytes(), code.as_bytes()));
+
+    Ok(matched)
+}
+
+pub fn generate_recovery_codes(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|_| {
+            let mut bytes = [0u8; RECOVERY_CODE_LEN / 2];
+            OsRng.fill_bytes(&mut bytes);
+            let code = hex::encode(bytes);
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
+
+/// Encrypts a secret for storage. `owner` (the user id) is bound as associated data,
+/// so a sealed secret copied to another account does not open.
+pub fn seal_secret(secret: &str, owner: &[u8]) -> Result<String> {
+    seal_with(auth_config().totp_encryption_key(), secret, owner)
+}
+
+/// Reverse of `seal_secret`. A legacy plaintext secret is returned as is.
+pub fn open_secret(stored: &str, owner: &[u8]) -> Result<String> {
+    open_with(auth_config().totp_encryption_key(), stored, owner)
+}
+
+pub fn is_sealed(stored: &str) -> bool {
+    stored.starts_with(SEALED_PREFIX)
+}
+
+fn seal_with(key: &[u8; 32], secret: &str, owner: &[u8]) -> Result<String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut sealed = secret.as_bytes().to_vec();
+    aead_key(key)?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(owner),
+            &mut sealed,
+        )
+        .map_err(|_| Error::Seal)?;
+
+    Ok(format!(
+        "{SEALED_PREFIX}{}",
+        BASE64.encode(&[nonce.as_slice(), &sealed].concat())
+    ))
+}
+
+fn open_with(key: &[u8; 32], stored: &str, owner: &[u8]) -> Result<String> {
+    let Some(encoded) = stored.strip_prefix(SEALED_PREFIX) else {
+        return Ok(stored.to_string());
+    };
+
+    let data = BASE64
+        .decode(encoded.as_bytes())
+        .map_err(|_| Error::Unseal)?;
+    if data.len() < NONCE_LEN {
+        return Err(Error::Unseal);
+    }
+    let (nonce, sealed) = data.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| Error::Unseal)?;
+
+    let mut sealed = sealed.to_vec();
+    let secret = aead_key(key)?
+        .open_in_place(nonce, Aad::from(owner), &mut sealed)
+        .map_err(|_| Error::Unseal)?;
+
+    String::from_utf8(secret.to_vec()).map_err(|_| Error::Unseal)
+}
+
+fn aead_key(key: &[u8; 32]) -> Result<LessSafeKey> {
+    let key = UnboundKey::new(&AES_256_GCM, key).map_err(|_| Error::Seal)?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Recovery codes are high-entropy, so a plain digest is enough to store them.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized = code.trim().to_lowercase().replace('-', "");
+    hex::encode(Sha256::digest(normalized.as_bytes()))
+}
+
+fn decode_secret(secret: &str) -> Result<Vec<u8>> {
+    BASE32_NOPAD
+        .decode(secret.trim_end_matches('=').to_uppercase().as_bytes())
+        .map_err(|_| Error::InvalidSecret)
+}
+
+fn hotp(key: &[u8], counter: u64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(DIGITS),
+        width = DIGITS as usize
+    )
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{Context, Result};
+
+    // Secret from RFC 6238 appendix B, base32-encoded
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn test_generate_code_rfc_vectors() -> Result<()> {
+        let vectors = [
+            (59, "287082"),
+            (1111111109, "081804"),
+            (1111111111, "050471"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+        ];
+
+        for (time, expected) in vectors {
+            let code =
+                generate_code(RFC_SECRET, time / STEP_SEC).context("Failed to generate code")?;
+            assert_eq!(code, expected, "Code for time {} should match RFC", time);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_code_window() -> Result<()> {
+        let time = 1111111109;
+        let step = time / STEP_SEC;
+
+        let previous = generate_code(RFC_SECRET, step - 1)?;
+        let far = generate_code(RFC_SECRET, step - 3)?;
+
+        assert_eq!(verify_code(RFC_SECRET, &previous, time)?, Some(step - 1));
+        assert_eq!(
+            verify_code(RFC_SECRET, &far, time)?,
+            None,
+            "Codes outside the skew window should be rejected"
+        );
+        assert_eq!(verify_code(RFC_SECRET, "12345", time)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generated_secret_roundtrip() -> Result<()> {
+        let secret = generate_secret();
+        let code = generate_code(&secret, 1)?;
+
+        assert_eq!(verify_code(&secret, &code, STEP_SEC)?, Some(1));
+        assert!(otpauth_uri(&secret, "user@example.com", "My App")
+            .starts_with("otpauth://totp/My%20App:user%40example.com?secret="));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recovery_code_hash_is_normalized() -> Result<()> {
+        let codes = generate_recovery_codes(8);
+        assert_eq!(codes.len(), 8);
+
+        let code = &codes[0];
+        assert_eq!(
+            hash_recovery_code(code),
+            hash_recovery_code(&code.to_uppercase().replace('-', ""))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sealed_secret_roundtrip() -> Result<()> {
+        let key = [7u8; 32];
+        let secret = generate_secret();
+
+        let sealed = seal_with(&key, &secret, b"owner")?;
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.contains(&secret));
+        assert_ne!(sealed, seal_with(&key, &secret, b"owner")?);
+        assert_eq!(open_with(&key, &sealed, b"owner")?, secret);
+
+        assert!(open_with(&key, &sealed, b"another owner").is_err());
+        assert!(open_with(&[8u8; 32], &sealed, b"owner").is_err());
+
+        // Secrets stored before encryption at rest still open
+        assert!(!is_sealed(RFC_SECRET));
+        assert_eq!(open_with(&key, RFC_SECRET, b"owner")?, RFC_SECRET);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tracing::warn;
+use uuid::Uuid;
+
+use super::AccessDenied;
+use crate::db::Db;
+use crate::model::access_denial::{AccessDenialForCreate, AccessDenialRepo};
+
+/// Denials waiting to be stored. When the queue is full new ones are dropped rather than
+/// slowing down access checks or growing memory during a burst.
+const QUEUE_SIZE: usize = 1024;
+/// Denials stored with one INSERT.
+const BATCH_SIZE: usize = 100;
+
+static DENIALS: OnceLock<mpsc::Sender<AccessDenialForCreate>> = OnceLock::new();
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// Writes denied decisions into `access_denials`. Access checks stay synchronous: they only
+/// queue the denial and this task stores it. Until it is started denials are only logged.
+pub fn record_denials(db: Db) {
+    let (sender, receiver) = mpsc::channel(QUEUE_SIZE);
+    if DENIALS.set(sender).is_err() {
+        warn!("Access denials are already being recorded");
+        return;
+    }
+
+    tokio::spawn(store_denials(db, receiver));
+}
+
+/// Number of denials dropped so far because the queue was full.
+pub fn dropped_denials() -> u64 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+async fn store_denials(db: Db, mut receiver: mpsc::Receiver<AccessDenialForCreate>) {
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    while receiver.recv_many(&mut batch, BATCH_SIZE).await > 0 {
+        if let Err(err) = AccessDenialRepo::create_many(&db, &batch).await {
+            warn!("{} access denials not recorded: {}", batch.len(), err);
+        }
+        batch.clear();
+    }
+}
+
+pub(super) fn record(denied: &AccessDenied, user_id: Option<Uuid>) {
+    if let (Some(sender), Some(denial)) = (DENIALS.get(), denial(denied, user_id)) {
+        enqueue(sender, denial);
+    }
+}
+
+/// Anonymous requests are not recorded, there is nobody to hold responsible.
+fn denial(denied: &AccessDenied, user_id: Option<Uuid>) -> Option<AccessDenialForCreate> {
+    Some(AccessDenialForCreate {
+        user_id: user_id?,
+        role: format!("{:?}", denied.role).to_lowercase(),
+        resource: denied.resource.kind().to_string(),
+        resource_id: denied.resource.id(),
+        action: denied.action.as_str().to_string(),
+    })
+}
+
+fn enqueue(sender: &mpsc::Sender<AccessDenialForCreate>, denial: AccessDenialForCreate) {
+    match sender.try_send(denial) {
+        Ok(()) => {}
+        Err(TrySendError::Full(_)) => {
+            let dropped = DROPPED.fetch_add(1, Ordering::Relaxed) + 1;
+            // Logged on 1, 2, 4, 8... drops so a flood does not flood the log as well
+            if dropped.is_power_of_two() {
+                warn!("Access denial queue is full, {} denials dropped", dropped);
+            }
+        }
+        Err(TrySendError::Closed(_)) => {
+            warn!("Access denial not recorded: audit task has stopped");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::acs::{Action, Resource, Role};
+    use crate::db::new_db_pool;
+    use crate::model::user::{UserForCreate, UserRepo};
+    use anyhow::Result;
+
+    fn denied() -> AccessDenied {
+        AccessDenied {
+            role: Role::User,
+            resource: Resource::User(Uuid::new_v4()),
+            action: Action::Ban,
+        }
+    }
+
+    #[test]
+    fn test_anonymous_denials_are_not_recorded() {
+        assert!(denial(&denied(), None).is_none());
+
+        let user_id = Uuid::new_v4();
+        let denial = denial(&denied(), Some(user_id)).unwrap();
+        assert_eq!(denial.user_id, user_id);
+        assert_eq!(denial.role, "user");
+        assert_eq!(denial.action, "ban");
+    }
+
+    #[test]
+    fn test_full_queue_drops_denials() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let dropped = dropped_denials();
+
+        enqueue(&sender, denial(&denied(), Some(Uuid::new_v4())).unwrap());
+        enqueue(&sender, denial(&denied(), Some(Uuid::new_v4())).unwrap());
+
+        assert!(receiver.try_recv().is_ok());
+        assert!(receiver.try_recv().is_err());
+        assert!(dropped_denials() > dropped);
+    }
+
+    #[tokio::test]
+    async fn test_denials_are_stored() -> Result<()> {
+        let db = new_db_pool().await?;
+
+        let nickname = format!("test_{}", &Uuid::new_v4().simple().to_string()[..12]);
+        let user = UserRepo::create(
+            &db,
+            UserForCreate {
+                email: format!("{nickname}@example.com"),
+                nickname,
+                hashed_password: "not-a-hash".to_string(),
+            },
+        )
+        .await?;
+
+        let (sender, receiver) = mpsc::channel(QUEUE_SIZE);
+        for _ in 0..3 {
+            enqueue(&sender, denial(&denied(), Some(user.id)).unwrap());
+        }
+        drop(sender);
+        store_denials(db.clone(), receiver).await;
+
+        let stored = AccessDenialRepo::find_latest(&db, Some(user.id), 10).await?;
+        assert_eq!(stored.len(), 3);
+
+        UserRepo::delete(&db, &user.id).await?;
+        Ok(())
+    }
+}
@@ -1,8 +1,19 @@
 // mod rbac (role-based access control)
+mod audit;
+mod policy;
+
 use std::fmt::Display;
 
 use uuid::Uuid;
 
+use crate::ctx::Ctx;
+
+pub use self::audit::{dropped_denials, record_denials};
+pub use self::policy::{
+    init_policy, policy, reload_policy, watch_policy, Condition, Explanation, Grant, Pattern,
+    Policy, ResourceKind, Rule, DEFAULT_POLICY,
+};
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Action {
     Create,
@@ -13,6 +24,57 @@ pub enum Action {
     Unfollow,
     Like,
     Unlike,
+    Ban,
+    Pin,
+    ManageModerators,
+    /// Read posts and comments of a private community.
+    ReadPrivate,
+    /// Approve join requests, invite and remove members.
+    ManageMembers,
+}
+
+impl std::str::FromStr for Action {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create" => Ok(Action::Create),
+            "read" => Ok(Action::Read),
+            "update" => Ok(Action::Update),
+            "delete" => Ok(Action::Delete),
+            "follow" => Ok(Action::Follow),
+            "unfollow" => Ok(Action::Unfollow),
+            "like" => Ok(Action::Like),
+            "unlike" => Ok(Action::Unlike),
+            "ban" => Ok(Action::Ban),
+            "pin" => Ok(Action::Pin),
+            "manage_moderators" => Ok(Action::ManageModerators),
+            "read_private" => Ok(Action::ReadPrivate),
+            "manage_members" => Ok(Action::ManageMembers),
+            _ => Err(Error::UnknownAction(s.to_string())),
+        }
+    }
+}
+
+impl Action {
+    /// Name used in the policy file.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Create => "create",
+            Action::Read => "read",
+            Action::Update => "update",
+            Action::Delete => "delete",
+            Action::Follow => "follow",
+            Action::Unfollow => "unfollow",
+            Action::Like => "like",
+            Action::Unlike => "unlike",
+            Action::Ban => "ban",
+            Action::Pin => "pin",
+            Action::ManageModerators => "manage_moderators",
+            Action::ReadPrivate => "read_private",
+            Action::ManageMembers => "manage_members",
+        }
+    }
 }
 
 impl Display for Action {
@@ -21,7 +83,7 @@ impl Display for Action {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Role {
     Admin,
     Moderator,
@@ -43,78 +105,315 @@ impl std::str::FromStr for Role {
     }
 }
 
+/// Role of a user inside one community, on top of their site-wide `Role`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommunityRole {
+    Owner,
+    Moderator,
+    Member,
+}
+
+impl std::str::FromStr for CommunityRole {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "owner" => Ok(CommunityRole::Owner),
+            "moderator" => Ok(CommunityRole::Moderator),
+            "member" => Ok(CommunityRole::Member),
+            _ => Err(Error::UnknownRole(s.to_string())),
+        }
+    }
+}
+
+/// Area of the API a personal access token can be granted, e.g. `posts:write`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeResource {
+    Users,
+    Communities,
+    Posts,
+    Comments,
+    Likes,
+    Chats,
+    Reports,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeAccess {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scope {
+    pub resource: ScopeResource,
+    pub access: ScopeAccess,
+}
+
+impl Scope {
+    pub fn new(resource: ScopeResource, access: ScopeAccess) -> Self {
+        Self { resource, access }
+    }
+
+    /// `write` also grants `read` on the same resource.
+    fn permits(&self, required: &Scope) -> bool {
+        self.resource == required.resource
+            && (self.access == ScopeAccess::Write || required.access == ScopeAccess::Read)
+    }
+}
+
+impl std::str::FromStr for Scope {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (resource, access) = s
+            .split_once(':')
+            .ok_or_else(|| Error::UnknownScope(s.to_string()))?;
+
+        let resource = match resource {
+            "users" => ScopeResource::Users,
+            "communities" => ScopeResource::Communities,
+            "posts" => ScopeResource::Posts,
+            "comments" => ScopeResource::Comments,
+            "likes" => ScopeResource::Likes,
+            "chats" => ScopeResource::Chats,
+            "reports" => ScopeResource::Reports,
+            _ => return Err(Error::UnknownScope(s.to_string())),
+        };
+        let access = match access {
+            "read" => ScopeAccess::Read,
+            "write" => ScopeAccess::Write,
+            _ => return Err(Error::UnknownScope(s.to_string())),
+        };
+
+        Ok(Self { resource, access })
+    }
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let resource = format!("{:?}", self.resource).to_lowercase();
+        let access = format!("{:?}", self.access).to_lowercase();
+        write!(f, "{resource}:{access}")
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Resource {
     User(Uuid),
-    Community { id: Uuid, owner_id: Uuid },
-    Post { id: Uuid, author_id: Uuid },
-    Comment { id: Uuid, author_id: Uuid },
+    Community {
+        id: Uuid,
+        owner_id: Uuid,
+    },
+    Post {
+        id: Uuid,
+        author_id: Uuid,
+    },
+    Comment {
+        id: Uuid,
+        author_id: Uuid,
+    },
+    /// `is_member` tells whether the current user is in the chat. Private chats have no owner.
+    Chat {
+        id: Uuid,
+        owner_id: Option<Uuid>,
+        is_member: bool,
+    },
+    /// `is_member` refers to the chat the message was sent to.
+    Message {
+        id: Uuid,
+        sender_id: Uuid,
+        is_member: bool,
+    },
+    Report {
+        id: Uuid,
+        reporter_id: Uuid,
+    },
 }
 
-pub struct AccessControl;
+impl Resource {
+    pub fn kind(&self) -> ResourceKind {
+        match self {
+            Resource::User(_) => ResourceKind::User,
+            Resource::Community { .. } => ResourceKind::Community,
+            Resource::Post { .. } => ResourceKind::Post,
+            Resource::Comment { .. } => ResourceKind::Comment,
+            Resource::Chat { .. } => ResourceKind::Chat,
+            Resource::Message { .. } => ResourceKind::Message,
+            Resource::Report { .. } => ResourceKind::Report,
+        }
+    }
 
-impl AccessControl {
-    fn can(role: Role, resource: &Resource, action: Action, current_user_id: Option<Uuid>) -> bool {
-        match role {
-            Role::Admin => {
-                // Admin наследует все от Moderator и User
-                true // либо вызови: Self::can(Role::Moderator, resource, action, current_user_id)
-            }
-
-            Role::Moderator => {
-                // Moderator наследует от User
-                match (resource, action) {
-                    (Resource::Post { .. }, Action::Delete) => true,
-                    (Resource::Comment { .. }, Action::Delete) => true,
-                    _ => Self::can(Role::User, resource, action, current_user_id),
-                }
-            }
-
-            Role::User => match (resource, action) {
-                (Resource::Post { .. }, Action::Create) => current_user_id.is_some(),
-                (Resource::Post { .. }, Action::Like | Action::Unlike) => current_user_id.is_some(),
-                (Resource::Comment { .. }, Action::Create) => current_user_id.is_some(),
-                (Resource::Community { .. }, Action::Create) => current_user_id.is_some(),
-                (Resource::Community { .. }, Action::Follow | Action::Unfollow) => {
-                    current_user_id.is_some() && !Self::is_owner(resource, current_user_id)
-                }
-                (_, Action::Update | Action::Delete) => Self::is_owner(resource, current_user_id),
-                (_, Action::Read) => true,
-                _ => false,
-            },
-
-            Role::Guest => match action {
-                Action::Read => true,
-                _ => false,
-            },
+    pub fn id(&self) -> Uuid {
+        match self {
+            Resource::User(id)
+            | Resource::Community { id, .. }
+            | Resource::Post { id, .. }
+            | Resource::Comment { id, .. }
+            | Resource::Chat { id, .. }
+            | Resource::Message { id, .. }
+            | Resource::Report { id, .. } => *id,
         }
     }
 
-    fn is_owner(resource: &Resource, user_id: Option<Uuid>) -> bool {
-        match resource {
+    pub fn is_owned_by(&self, user_id: Option<Uuid>) -> bool {
+        match self {
             Resource::Post { author_id, .. } => user_id == Some(*author_id),
             Resource::Comment { author_id, .. } => user_id == Some(*author_id),
             Resource::User(resource_user_id) => user_id == Some(*resource_user_id),
             Resource::Community { owner_id, .. } => user_id == Some(*owner_id),
+            Resource::Chat { owner_id, .. } => owner_id.is_some() && user_id == *owner_id,
+            Resource::Message { sender_id, .. } => user_id == Some(*sender_id),
+            Resource::Report { reporter_id, .. } => user_id == Some(*reporter_id),
+        }
+    }
+
+    pub fn is_chat_member(&self) -> bool {
+        match self {
+            Resource::Chat { is_member, .. } | Resource::Message { is_member, .. } => *is_member,
+            _ => false,
         }
     }
+}
+
+pub struct AccessControl;
+
+impl AccessControl {
+    /// Rules come from the access policy, see `policy::DEFAULT_POLICY`
+    fn can(
+        role: Role,
+        community_role: Option<CommunityRole>,
+        resource: &Resource,
+        action: Action,
+        current_user_id: Option<Uuid>,
+    ) -> bool {
+        policy().allows(&role, resource, action, current_user_id, community_role)
+    }
 
     pub fn check_access(
+        ctx: &Ctx,
         role: Role,
         resource: Resource,
         action: Action,
-        current_user_id: Option<Uuid>,
     ) -> Result<(), Error> {
-        if Self::can(role.clone(), &resource, action, current_user_id) {
+        Self::check_community_access(ctx, role, None, resource, action)
+    }
+
+    /// Same as `check_access` for resources inside a community. `community_role` is the role
+    /// of the current user in the community the resource belongs to.
+    ///
+    /// This is an enforcement point: denials are recorded to `access_denials`. Code that only
+    /// filters what to show should ask `can_access` instead.
+    pub fn check_community_access(
+        ctx: &Ctx,
+        role: Role,
+        community_role: Option<CommunityRole>,
+        resource: Resource,
+        action: Action,
+    ) -> Result<(), Error> {
+        let result = Self::decide(ctx, role, community_role, resource, action);
+        if let Err(Error::AccessDenied(denied)) = &result {
+            audit::record(denied, ctx.user_id);
+        }
+
+        result
+    }
+
+    /// Denial decided by a rule outside the policy, such as comparing the roles of two users.
+    /// Recorded to `access_denials` like the ones `check_community_access` returns.
+    pub fn deny(ctx: &Ctx, role: Role, resource: Resource, action: Action) -> Error {
+        let denied = AccessDenied {
+            role,
+            resource,
+            action,
+        };
+        audit::record(&denied, ctx.user_id);
+        Error::AccessDenied(denied)
+    }
+
+    /// Decision `check_community_access` would make, without recording a denial. For
+    /// filtering lists and probing whether something may be shown.
+    pub fn can_access(
+        ctx: &Ctx,
+        role: Role,
+        community_role: Option<CommunityRole>,
+        resource: Resource,
+        action: Action,
+    ) -> bool {
+        Self::decide(ctx, role, community_role, resource, action).is_ok()
+    }
+
+    fn decide(
+        ctx: &Ctx,
+        role: Role,
+        community_role: Option<CommunityRole>,
+        resource: Resource,
+        action: Action,
+    ) -> Result<(), Error> {
+        if let Some(required) = Self::required_scope(&resource, action) {
+            Self::check_scope(ctx, Some(required))?;
+        }
+
+        if Self::can(role.clone(), community_role, &resource, action, ctx.user_id) {
             Ok(())
         } else {
             Err(Error::AccessDenied(AccessDenied {
                 role,
-                resource: resource.clone(),
+                resource,
                 action,
             }))
         }
     }
+
+    /// Decision `check_community_access` would make, with the policy rules behind it.
+    /// Nothing is recorded, so it is safe to ask on behalf of another user.
+    pub fn explain(
+        role: Role,
+        community_role: Option<CommunityRole>,
+        resource: &Resource,
+        action: Action,
+        current_user_id: Option<Uuid>,
+    ) -> Explanation {
+        policy().explain(&role, resource, action, current_user_id, community_role)
+    }
+}
+
+impl AccessControl {
+    /// Scope check for requests made with a personal access token, other requests are not
+    /// limited by scopes. `required` is `None` for parts of the API that tokens can never reach.
+    pub fn check_scope(ctx: &Ctx, required: Option<Scope>) -> Result<(), Error> {
+        let Some(granted) = &ctx.scopes else {
+            return Ok(());
+        };
+
+        match required {
+            Some(required) if granted.iter().any(|scope| scope.permits(&required)) => Ok(()),
+            Some(required) => Err(Error::ScopeDenied(required.to_string())),
+            None => Err(Error::ScopeDenied("this endpoint".to_string())),
+        }
+    }
+
+    /// Scope a token needs for `action` on `resource`. Likes have their own scope whatever
+    /// is being liked. `ReadPrivate` needs none: it only narrows reads of posts and comments,
+    /// which are checked against their own scopes.
+    fn required_scope(resource: &Resource, action: Action) -> Option<Scope> {
+        let resource = match (resource, action) {
+            (_, Action::ReadPrivate) => return None,
+            (_, Action::Like | Action::Unlike) => ScopeResource::Likes,
+            (Resource::User(_), _) => ScopeResource::Users,
+            (Resource::Community { .. }, _) => ScopeResource::Communities,
+            (Resource::Post { .. }, _) => ScopeResource::Posts,
+            (Resource::Comment { .. }, _) => ScopeResource::Comments,
+            (Resource::Chat { .. } | Resource::Message { .. }, _) => ScopeResource::Chats,
+            (Resource::Report { .. }, _) => ScopeResource::Reports,
+        };
+        let access = match action {
+            Action::Read => ScopeAccess::Read,
+            _ => ScopeAccess::Write,
+        };
+
+        Some(Scope::new(resource, access))
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -129,6 +428,71 @@ pub struct AccessDenied {
 pub enum Error {
     #[error("Unkown role: {0}")]
     UnknownRole(String),
+    #[error("Unknown scope: {0}")]
+    UnknownScope(String),
+    #[error("Unknown action: {0}")]
+    UnknownAction(String),
+    #[error("Unknown resource: {0}")]
+    UnknownResource(String),
+    #[error("Invalid access policy: {0}")]
+    InvalidPolicy(String),
+    #[error("Token scopes do not allow {0}")]
+    ScopeDenied(String),
     #[error(transparent)]
     AccessDenied(#[from] AccessDenied),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(author_id: Uuid) -> Resource {
+        Resource::Post {
+            id: Uuid::new_v4(),
+            author_id,
+        }
+    }
+
+    #[test]
+    fn test_token_cannot_exceed_its_scopes() {
+        let user_id = Uuid::new_v4();
+        let read_posts = Ctx::with_scopes(user_id, vec!["posts:read".parse().unwrap()]);
+
+        let read =
+            AccessControl::check_access(&read_posts, Role::User, post(user_id), Action::Read);
+        assert!(read.is_ok());
+
+        // The policy lets authors update their posts, the token does not
+        let update =
+            AccessControl::check_access(&read_posts, Role::User, post(user_id), Action::Update);
+        assert!(matches!(update, Err(Error::ScopeDenied(_))));
+
+        let session = Ctx::new(user_id);
+        let update =
+            AccessControl::check_access(&session, Role::User, post(user_id), Action::Update);
+        assert!(update.is_ok());
+    }
+
+    #[test]
+    fn test_likes_have_their_own_scope() {
+        let user_id = Uuid::new_v4();
+        let write_posts = Ctx::with_scopes(user_id, vec!["posts:write".parse().unwrap()]);
+        let write_likes = Ctx::with_scopes(user_id, vec!["likes:write".parse().unwrap()]);
+
+        let like = |ctx: &Ctx| {
+            AccessControl::check_access(ctx, Role::User, post(Uuid::new_v4()), Action::Like)
+        };
+        assert!(matches!(like(&write_posts), Err(Error::ScopeDenied(_))));
+        assert!(like(&write_likes).is_ok());
+    }
+
+    #[test]
+    fn test_tokens_never_reach_unscoped_endpoints() {
+        let user_id = Uuid::new_v4();
+        let token = Ctx::with_scopes(user_id, vec!["users:write".parse().unwrap()]);
+
+        assert!(AccessControl::check_scope(&token, None).is_err());
+        assert!(AccessControl::check_scope(&Ctx::new(user_id), None).is_ok());
+        assert!(AccessControl::check_scope(&token.unscoped(), None).is_ok());
+    }
+}
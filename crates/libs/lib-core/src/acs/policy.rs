@@ -0,0 +1,858 @@
+use super::{Action, CommunityRole, Error, Resource, Role};
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::fs;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Policy used when `ACS_POLICY_PATH` is not set. Reproduces the rules that used to be
+/// hardcoded in `AccessControl`.
+pub const DEFAULT_POLICY: &str = include_str!("default_policy.json");
+
+const ROLES: [Role; 4] = [Role::Admin, Role::Moderator, Role::User, Role::Guest];
+
+fn policy_cell() -> &'static RwLock<Arc<Policy>> {
+    static POLICY: OnceLock<RwLock<Arc<Policy>>> = OnceLock::new();
+    POLICY.get_or_init(|| {
+        let policy = Policy::load_configured()
+            .unwrap_or_else(|err| panic!("PANIC WHILE LOADING ACS POLICY: {}", err));
+        RwLock::new(Arc::new(policy))
+    })
+}
+
+/// Policy currently in force.
+pub fn policy() -> Arc<Policy> {
+    policy_cell()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+/// Loads and validates the policy at startup, so a broken file stops the server instead of
+/// the first request that needs it.
+pub fn init_policy() -> Result<(), Error> {
+    let policy = Policy::load_configured()?;
+    *policy_cell()
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(policy);
+
+    Ok(())
+}
+
+/// Re-reads the policy file. An invalid file is rejected and the previous policy stays in force.
+pub fn reload_policy() -> Result<(), Error> {
+    init_policy()
+}
+
+/// Reloads the policy whenever `ACS_POLICY_PATH` is modified. Does nothing for the
+/// built-in policy.
+pub fn watch_policy(interval: Duration) {
+    let Some(path) = policy_path() else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        let modified = |path: &str| fs::metadata(path).and_then(|m| m.modified()).ok();
+        let mut last_modified: Option<SystemTime> = modified(&path);
+
+        loop {
+            tokio::time::sleep(interval).await;
+
+            let current = modified(&path);
+            if current == last_modified {
+                continue;
+            }
+            last_modified = current;
+
+            match reload_policy() {
+                Ok(()) => info!("Access policy reloaded from {}", path),
+                Err(err) => warn!(
+                    "Access policy not reloaded, keeping the previous one: {}",
+                    err
+                ),
+            }
+        }
+    });
+}
+
+/// Read directly instead of through `core_config`, which also requires database settings
+fn policy_path() -> Option<String> {
+    lib_utils::env::get_env("ACS_POLICY_PATH").ok()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceKind {
+    User,
+    Community,
+    Post,
+    Comment,
+    Chat,
+    Message,
+    Report,
+}
+
+impl FromStr for ResourceKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(ResourceKind::User),
+            "community" => Ok(ResourceKind::Community),
+            "post" => Ok(ResourceKind::Post),
+            "comment" => Ok(ResourceKind::Comment),
+            "chat" => Ok(ResourceKind::Chat),
+            "message" => Ok(ResourceKind::Message),
+            "report" => Ok(ResourceKind::Report),
+            _ => Err(Error::UnknownResource(s.to_string())),
+        }
+    }
+}
+
+impl Display for ResourceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format!("{self:?}").to_lowercase())
+    }
+}
+
+/// Extra requirement of a grant on top of the role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    /// The request is made by a signed in user.
+    Authenticated,
+    /// The user owns the resource: authored it, owns the community or is the user.
+    Owner,
+    NotOwner,
+    /// The user moderates or owns the community the resource belongs to.
+    CommunityModerator,
+    CommunityOwner,
+    /// The user has any role in the community, including a plain member.
+    CommunityMember,
+    /// The user is in the chat, or in the chat of the message.
+    ChatMember,
+}
+
+/// Either `"*"` or a single value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern<T> {
+    Any,
+    One(T),
+}
+
+impl<'de, T> Deserialize<'de> for Pattern<T>
+where
+    T: FromStr<Err = Error>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match String::deserialize(deserializer)?.as_str() {
+            "*" => Ok(Pattern::Any),
+            value => value
+                .parse()
+                .map(Pattern::One)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+impl<T: PartialEq> Pattern<T> {
+    fn matches(&self, value: &T) -> bool {
+        match self {
+            Pattern::Any => true,
+            Pattern::One(expected) => expected == value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Grant {
+    pub resource: Pattern<ResourceKind>,
+    pub actions: Vec<Pattern<Action>>,
+    /// All conditions must hold.
+    #[serde(default)]
+    pub when: Vec<Condition>,
+}
+
+impl Grant {
+    fn allows(
+        &self,
+        resource: &Resource,
+        action: Action,
+        user_id: Option<Uuid>,
+        community_role: Option<CommunityRole>,
+    ) -> bool {
+        self.covers(resource, action)
+            && self
+                .when
+                .iter()
+                .all(|condition| condition.holds(resource, user_id, community_role))
+    }
+
+    /// The grant is about this kind of resource and this action, whatever its conditions.
+    fn covers(&self, resource: &Resource, action: Action) -> bool {
+        self.resource.matches(&resource.kind())
+            && self.actions.iter().any(|pattern| pattern.matches(&action))
+    }
+}
+
+impl Condition {
+    fn holds(
+        &self,
+        resource: &Resource,
+        user_id: Option<Uuid>,
+        community_role: Option<CommunityRole>,
+    ) -> bool {
+        match self {
+            Condition::Authenticated => user_id.is_some(),
+            Condition::Owner => resource.is_owned_by(user_id),
+            Condition::NotOwner => !resource.is_owned_by(user_id),
+            Condition::CommunityModerator => matches!(
+                community_role,
+                Some(CommunityRole::Moderator | CommunityRole::Owner)
+            ),
+            Condition::CommunityOwner => community_role == Some(CommunityRole::Owner),
+            Condition::CommunityMember => community_role.is_some(),
+            Condition::ChatMember => resource.is_chat_member(),
+        }
+    }
+}
+
+/// Grant together with where it is written in the policy, so a decision can point at it.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    /// Role whose section defines the grant, may be a role the checked one inherits from.
+    pub role: Role,
+    /// Position of the grant in that section.
+    pub index: usize,
+    pub grant: Grant,
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "roles.{}.grants[{}]",
+            format!("{:?}", self.role).to_lowercase(),
+            self.index
+        )
+    }
+}
+
+/// Outcome of `Policy::explain`.
+#[derive(Debug)]
+pub struct Explanation {
+    pub allowed: bool,
+    /// First rule that allows the action.
+    pub matched: Option<Rule>,
+    /// Rules about the resource and action that did not apply, with the conditions that failed.
+    pub rejected: Vec<(Rule, Vec<Condition>)>,
+}
+
+impl Explanation {
+    pub fn reason(&self) -> String {
+        if let Some(rule) = &self.matched {
+            return format!("allowed by {rule}");
+        }
+        if self.rejected.is_empty() {
+            return "denied: no rule grants this action on this resource".to_string();
+        }
+
+        let rejected = self
+            .rejected
+            .iter()
+            .map(|(rule, failed)| format!("{rule} requires {failed:?}"))
+            .collect::<Vec<_>>()
+            .join("; ");
+        format!("denied: {rejected}")
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    roles: BTreeMap<String, RoleSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RoleSpec {
+    #[serde(default)]
+    inherits: Vec<String>,
+    #[serde(default)]
+    grants: Vec<Grant>,
+}
+
+/// Access rules per role with inheritance already resolved. Anything not granted is denied.
+#[derive(Debug)]
+pub struct Policy {
+    rules: HashMap<Role, Vec<Rule>>,
+}
+
+impl Policy {
+    /// Parses and validates a policy: every role is defined exactly once, inherited roles
+    /// exist and inheritance has no cycles.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let file: PolicyFile =
+            serde_json::from_str(json).map_err(|e| Error::InvalidPolicy(e.to_string()))?;
+
+        let mut specs = HashMap::new();
+        for (name, spec) in file.roles {
+            let role = Role::from_str(&name).map_err(|e| Error::InvalidPolicy(e.to_string()))?;
+            if specs.insert(role.clone(), spec).is_some() {
+                return Err(Error::InvalidPolicy(format!(
+                    "role '{name}' is defined twice"
+                )));
+            }
+        }
+
+        let mut rules = HashMap::new();
+        for role in ROLES {
+            let resolved = resolve_rules(&role, &specs, &mut Vec::new())?;
+            rules.insert(role, resolved);
+        }
+
+        Ok(Self { rules })
+    }
+
+    pub fn load(path: &str) -> Result<Self, Error> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| Error::InvalidPolicy(format!("cannot read '{path}': {e}")))?;
+
+        Self::from_json(&json).map_err(|e| match e {
+            Error::InvalidPolicy(reason) => Error::InvalidPolicy(format!("{path}: {reason}")),
+            e => e,
+        })
+    }
+
+    fn load_configured() -> Result<Self, Error> {
+        match policy_path() {
+            Some(path) => Self::load(&path),
+            None => Self::from_json(DEFAULT_POLICY),
+        }
+    }
+
+    pub fn allows(
+        &self,
+        role: &Role,
+        resource: &Resource,
+        action: Action,
+        user_id: Option<Uuid>,
+        community_role: Option<CommunityRole>,
+    ) -> bool {
+        self.rules.get(role).is_some_and(|rules| {
+            rules
+                .iter()
+                .any(|rule| rule.grant.allows(resource, action, user_id, community_role))
+        })
+    }
+
+    /// Same decision as `allows`, along with the rules that led to it.
+    pub fn explain(
+        &self,
+        role: &Role,
+        resource: &Resource,
+        action: Action,
+        user_id: Option<Uuid>,
+        community_role: Option<CommunityRole>,
+    ) -> Explanation {
+        let mut matched = None;
+        let mut rejected = Vec::new();
+
+        let rules = self.rules.get(role).map(Vec::as_slice).unwrap_or_default();
+        for rule in rules
+            .iter()
+            .filter(|rule| rule.grant.covers(resource, action))
+        {
+            let failed: Vec<Condition> = rule
+                .grant
+                .when
+                .iter()
+                .filter(|condition| !condition.holds(resource, user_id, community_role))
+                .copied()
+                .collect();
+
+            if failed.is_empty() {
+                matched = Some(rule.clone());
+                break;
+            }
+            rejected.push((rule.clone(), failed));
+        }
+
+        Explanation {
+            allowed: matched.is_some(),
+            matched,
+            rejected,
+        }
+    }
+}
+
+fn resolve_rules(
+    role: &Role,
+    specs: &HashMap<Role, RoleSpec>,
+    visiting: &mut Vec<Role>,
+) -> Result<Vec<Rule>, Error> {
+    if visiting.contains(role) {
+        return Err(Error::InvalidPolicy(format!(
+            "role inheritance cycle through {role:?}"
+        )));
+    }
+    let spec = specs
+        .get(role)
+        .ok_or_else(|| Error::InvalidPolicy(format!("role {role:?} is not defined")))?;
+
+    visiting.push(role.clone());
+    let mut rules: Vec<Rule> = spec
+        .grants
+        .iter()
+        .enumerate()
+        .map(|(index, grant)| Rule {
+            role: role.clone(),
+            index,
+            grant: grant.clone(),
+        })
+        .collect();
+    for parent in &spec.inherits {
+        let parent = Role::from_str(parent).map_err(|e| Error::InvalidPolicy(e.to_string()))?;
+        rules.extend(resolve_rules(&parent, specs, visiting)?);
+    }
+    visiting.pop();
+
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    const ACTIONS: [Action; 9] = [
+        Action::Create,
+        Action::Read,
+        Action::Update,
+        Action::Delete,
+        Action::Follow,
+        Action::Unfollow,
+        Action::Like,
+        Action::Unlike,
+        Action::Ban,
+    ];
+
+    /// The rules `AccessControl::can` had before they moved into the policy file
+    fn hardcoded_can(
+        role: Role,
+        resource: &Resource,
+        action: Action,
+        user_id: Option<Uuid>,
+    ) -> bool {
+        match role {
+            Role::Admin => true,
+            Role::Moderator => match (resource, action) {
+                (Resource::Post { .. }, Action::Delete) => true,
+                (Resource::Comment { .. }, Action::Delete) => true,
+                (Resource::User(_), Action::Ban) => true,
+                _ => hardcoded_can(Role::User, resource, action, user_id),
+            },
+            Role::User => match (resource, action) {
+                (Resource::Post { .. }, Action::Create) => user_id.is_some(),
+                (Resource::Post { .. }, Action::Like | Action::Unlike) => user_id.is_some(),
+                (Resource::Comment { .. }, Action::Create) => user_id.is_some(),
+                (Resource::Community { .. }, Action::Create) => user_id.is_some(),
+                (Resource::Community { .. }, Action::Follow | Action::Unfollow) => {
+                    user_id.is_some() && !resource.is_owned_by(user_id)
+                }
+                (_, Action::Update | Action::Delete) => resource.is_owned_by(user_id),
+                (_, Action::Read) => true,
+                _ => false,
+            },
+            Role::Guest => matches!(action, Action::Read),
+        }
+    }
+
+    fn resources(owner_id: Uuid) -> Vec<Resource> {
+        vec![
+            Resource::User(owner_id),
+            Resource::Community {
+                id: Uuid::new_v4(),
+                owner_id,
+            },
+            Resource::Post {
+                id: Uuid::new_v4(),
+                author_id: owner_id,
+            },
+            Resource::Comment {
+                id: Uuid::new_v4(),
+                author_id: owner_id,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_default_policy_reproduces_hardcoded_rules() -> Result<()> {
+        let policy = Policy::from_json(DEFAULT_POLICY)?;
+        let owner_id = Uuid::new_v4();
+        let users = [None, Some(owner_id), Some(Uuid::new_v4())];
+
+        for role in ROLES {
+            for resource in resources(owner_id) {
+                for action in ACTIONS {
+                    for user_id in users {
+                        assert_eq!(
+                            policy.allows(&role, &resource, action, user_id, None),
+                            hardcoded_can(role.clone(), &resource, action, user_id),
+                            "{role:?} {action:?} {resource:?} as {user_id:?}"
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_policy_table() -> Result<()> {
+        let policy = Policy::from_json(DEFAULT_POLICY)?;
+        let owner_id = Uuid::new_v4();
+        let other_id = Uuid::new_v4();
+        let post = Resource::Post {
+            id: Uuid::new_v4(),
+            author_id: owner_id,
+        };
+        let comment = Resource::Comment {
+            id: Uuid::new_v4(),
+            author_id: owner_id,
+        };
+        let community = Resource::Community {
+            id: Uuid::new_v4(),
+            owner_id,
+        };
+        let user = Resource::User(owner_id);
+
+        #[rustfmt::skip]
+        let table = [
+            (Role::Guest, &post, Action::Read, None, true),
+            (Role::Guest, &post, Action::Create, None, false),
+            (Role::Guest, &community, Action::Follow, Some(other_id), false),
+            (Role::User, &post, Action::Create, Some(other_id), true),
+            (Role::User, &post, Action::Create, None, false),
+            (Role::User, &post, Action::Like, Some(other_id), true),
+            (Role::User, &post, Action::Update, Some(owner_id), true),
+            (Role::User, &post, Action::Update, Some(other_id), false),
+            (Role::User, &comment, Action::Delete, Some(owner_id), true),
+            (Role::User, &comment, Action::Delete, Some(other_id), false),
+            (Role::User, &community, Action::Follow, Some(other_id), true),
+            (Role::User, &community, Action::Follow, Some(owner_id), false),
+            (Role::User, &community, Action::Like, Some(other_id), false),
+            (Role::User, &user, Action::Update, Some(owner_id), true),
+            (Role::User, &user, Action::Ban, Some(other_id), false),
+            (Role::Moderator, &post, Action::Delete, Some(other_id), true),
+            (Role::Moderator, &comment, Action::Delete, Some(other_id), true),
+            (Role::Moderator, &community, Action::Delete, Some(other_id), false),
+            (Role::Moderator, &user, Action::Ban, Some(other_id), true),
+            (Role::Moderator, &post, Action::Update, Some(other_id), false),
+            (Role::Admin, &community, Action::Delete, Some(other_id), true),
+            (Role::Admin, &user, Action::Ban, Some(other_id), true),
+        ];
+
+        for (role, resource, action, user_id, expected) in table {
+            assert_eq!(
+                policy.allows(&role, resource, action, user_id, None),
+                expected,
+                "{role:?} {action:?} {resource:?} as {user_id:?}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_policy_community_roles() -> Result<()> {
+        let policy = Policy::from_json(DEFAULT_POLICY)?;
+        let user_id = Some(Uuid::new_v4());
+        let post = Resource::Post {
+            id: Uuid::new_v4(),
+            author_id: Uuid::new_v4(),
+        };
+        let comment = Resource::Comment {
+            id: Uuid::new_v4(),
+            author_id: Uuid::new_v4(),
+        };
+        let community = Resource::Community {
+            id: Uuid::new_v4(),
+            owner_id: Uuid::new_v4(),
+        };
+        let owner = Some(CommunityRole::Owner);
+        let moderator = Some(CommunityRole::Moderator);
+        let member = Some(CommunityRole::Member);
+
+        #[rustfmt::skip]
+        let table = [
+            (&post, Action::Delete, moderator, true),
+            (&post, Action::Delete, owner, true),
+            (&post, Action::Delete, member, false),
+            (&post, Action::Delete, None, false),
+            (&post, Action::Pin, moderator, true),
+            (&post, Action::Pin, None, false),
+            (&post, Action::Update, moderator, false),
+            (&comment, Action::Delete, moderator, true),
+            (&comment, Action::Delete, member, false),
+            (&community, Action::Update, moderator, true),
+            (&community, Action::Update, member, false),
+            (&community, Action::Delete, moderator, false),
+            (&community, Action::ManageModerators, owner, true),
+            (&community, Action::ManageModerators, moderator, false),
+            (&community, Action::ReadPrivate, member, true),
+            (&community, Action::ReadPrivate, None, false),
+            (&community, Action::ManageMembers, moderator, true),
+            (&community, Action::ManageMembers, member, false),
+        ];
+
+        for (resource, action, community_role, expected) in table {
+            assert_eq!(
+                policy.allows(&Role::User, resource, action, user_id, community_role),
+                expected,
+                "{action:?} {resource:?} as {community_role:?}"
+            );
+        }
+
+        assert!(
+            !policy.allows(&Role::Moderator, &post, Action::Pin, user_id, None),
+            "Site moderators do not pin posts in communities they do not moderate"
+        );
+        assert!(
+            policy.allows(
+                &Role::Moderator,
+                &community,
+                Action::ReadPrivate,
+                user_id,
+                None
+            ),
+            "Site moderators see private communities to moderate them"
+        );
+        assert!(!policy.allows(&Role::Guest, &community, Action::ReadPrivate, None, None));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_policy_chats_and_reports() -> Result<()> {
+        let policy = Policy::from_json(DEFAULT_POLICY)?;
+        let owner_id = Uuid::new_v4();
+        let other_id = Uuid::new_v4();
+        let chat = |is_member| Resource::Chat {
+            id: Uuid::new_v4(),
+            owner_id: Some(owner_id),
+            is_member,
+        };
+        let private_chat = Resource::Chat {
+            id: Uuid::new_v4(),
+            owner_id: None,
+            is_member: true,
+        };
+        let message = |is_member| Resource::Message {
+            id: Uuid::new_v4(),
+            sender_id: owner_id,
+            is_member,
+        };
+        let report = Resource::Report {
+            id: Uuid::new_v4(),
+            reporter_id: owner_id,
+        };
+
+        #[rustfmt::skip]
+        let table = [
+            (Role::Guest, chat(false), Action::Read, None, false),
+            (Role::User, chat(true), Action::Read, Some(other_id), true),
+            (Role::User, chat(false), Action::Read, Some(other_id), false),
+            (Role::User, chat(false), Action::Create, Some(other_id), true),
+            (Role::User, chat(true), Action::Update, Some(owner_id), true),
+            (Role::User, chat(true), Action::Update, Some(other_id), false),
+            (Role::User, chat(true), Action::ManageMembers, Some(owner_id), true),
+            (Role::User, chat(true), Action::ManageMembers, Some(other_id), false),
+            (Role::User, private_chat.clone(), Action::Delete, Some(other_id), false),
+            (Role::Moderator, chat(false), Action::Read, Some(other_id), false),
+            (Role::Admin, chat(false), Action::Read, Some(other_id), false),
+            (Role::Admin, chat(false), Action::Update, Some(other_id), false),
+            (Role::Admin, chat(false), Action::Delete, Some(other_id), false),
+            (Role::Admin, chat(true), Action::Read, Some(other_id), true),
+            (Role::User, message(true), Action::Read, Some(other_id), true),
+            (Role::User, message(false), Action::Read, Some(other_id), false),
+            (Role::User, message(true), Action::Create, Some(other_id), true),
+            (Role::User, message(false), Action::Create, Some(owner_id), false),
+            (Role::User, message(true), Action::Update, Some(owner_id), true),
+            (Role::User, message(false), Action::Update, Some(owner_id), false),
+            (Role::User, message(true), Action::Delete, Some(other_id), false),
+            (Role::Admin, message(false), Action::Read, Some(other_id), false),
+            (Role::Admin, message(false), Action::Create, Some(other_id), false),
+            (Role::Admin, message(true), Action::Delete, Some(other_id), false),
+            (Role::Guest, report.clone(), Action::Read, None, false),
+            (Role::User, report.clone(), Action::Create, Some(other_id), true),
+            (Role::User, report.clone(), Action::Read, Some(owner_id), true),
+            (Role::User, report.clone(), Action::Read, Some(other_id), false),
+            (Role::User, report.clone(), Action::Update, Some(owner_id), false),
+            (Role::Moderator, report.clone(), Action::Read, Some(other_id), true),
+            (Role::Moderator, report.clone(), Action::Update, Some(other_id), true),
+            (Role::Moderator, report.clone(), Action::Delete, Some(other_id), true),
+            (Role::Admin, report.clone(), Action::Update, Some(other_id), true),
+        ];
+
+        for (role, resource, action, user_id, expected) in table {
+            assert_eq!(
+                policy.allows(&role, &resource, action, user_id, None),
+                expected,
+                "{role:?} {action:?} {resource:?} as {user_id:?}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_invalid_policies() -> Result<()> {
+        let invalid = [
+            (
+                r#"{"roles": {"user": {}, "guest": {}, "moderator": {}}}"#,
+                "missing role",
+            ),
+            (
+                r#"{"roles": {"admin": {"inherits": ["root"]}, "moderator": {}, "user": {}, "guest": {}}}"#,
+                "unknown inherited role",
+            ),
+            (
+                r#"{"roles": {"admin": {}, "moderator": {"inherits": ["user"]}, "user": {"inherits": ["moderator"]}, "guest": {}}}"#,
+                "inheritance cycle",
+            ),
+            (
+                r#"{"roles": {"admin": {"grants": [{"resource": "*", "actions": ["fly"]}]}, "moderator": {}, "user": {}, "guest": {}}}"#,
+                "unknown action",
+            ),
+            (
+                r#"{"roles": {"admin": {"grants": [{"resource": "wiki", "actions": ["*"]}]}, "moderator": {}, "user": {}, "guest": {}}}"#,
+                "unknown resource",
+            ),
+            (
+                r#"{"roles": {"admin": {"grants": [{"resource": "*", "actions": ["*"], "when": ["weekday"]}]}, "moderator": {}, "user": {}, "guest": {}}}"#,
+                "unknown condition",
+            ),
+            (
+                r#"{"roles": {"admin": {"grant": []}, "moderator": {}, "user": {}, "guest": {}}}"#,
+                "misspelled field",
+            ),
+            (
+                r#"{"roles": {"admin": {}, "Admin": {}, "moderator": {}, "user": {}, "guest": {}}}"#,
+                "role defined twice",
+            ),
+        ];
+
+        for (json, case) in invalid {
+            assert!(
+                matches!(Policy::from_json(json), Err(Error::InvalidPolicy(_))),
+                "Policy with {case} should be rejected"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_policy_from_file() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("acs-policy-{}.json", Uuid::new_v4()));
+        let path_str = path.to_string_lossy().to_string();
+
+        // Guests lose read access, users keep it through their own grant
+        fs::write(
+            &path,
+            r#"{"roles": {
+                "admin": {"inherits": ["moderator"]},
+                "moderator": {"inherits": ["user"]},
+                "user": {"grants": [{"resource": "*", "actions": ["read"], "when": ["authenticated"]}]},
+                "guest": {}
+            }}"#,
+        )?;
+        let policy = Policy::load(&path_str)?;
+        fs::remove_file(&path)?;
+
+        let post = Resource::Post {
+            id: Uuid::new_v4(),
+            author_id: Uuid::new_v4(),
+        };
+        assert!(!policy.allows(&Role::Guest, &post, Action::Read, None, None));
+        assert!(policy.allows(
+            &Role::Admin,
+            &post,
+            Action::Read,
+            Some(Uuid::new_v4()),
+            None
+        ));
+        assert!(!policy.allows(
+            &Role::Admin,
+            &post,
+            Action::Delete,
+            Some(Uuid::new_v4()),
+            None
+        ));
+
+        assert!(matches!(
+            Policy::load(&path_str),
+            Err(Error::InvalidPolicy(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_explain_points_at_rule() -> Result<()> {
+        let policy = Policy::from_json(
+            r#"{"roles": {
+                "admin": {"inherits": ["user"], "grants": [{"resource": "report", "actions": ["*"]}]},
+                "moderator": {"inherits": ["user"]},
+                "user": {"grants": [
+                    {"resource": "post", "actions": ["read"]},
+                    {"resource": "post", "actions": ["update", "delete"], "when": ["authenticated", "owner"]}
+                ]},
+                "guest": {}
+            }}"#,
+        )?;
+        let author_id = Uuid::new_v4();
+        let post = Resource::Post {
+            id: Uuid::new_v4(),
+            author_id,
+        };
+
+        let explanation = policy.explain(&Role::Admin, &post, Action::Read, None, None);
+        assert!(explanation.allowed);
+        assert_eq!(explanation.reason(), "allowed by roles.user.grants[0]");
+        let rule = explanation.matched.expect("read is granted");
+        assert_eq!((rule.role, rule.index), (Role::User, 0));
+
+        let other_id = Some(Uuid::new_v4());
+        let explanation = policy.explain(&Role::User, &post, Action::Delete, other_id, None);
+        assert!(!explanation.allowed);
+        assert_eq!(explanation.rejected.len(), 1);
+        assert_eq!(explanation.rejected[0].0.index, 1);
+        assert_eq!(explanation.rejected[0].1, vec![Condition::Owner]);
+
+        let explanation = policy.explain(&Role::Guest, &post, Action::Read, None, None);
+        assert!(!explanation.allowed);
+        assert!(explanation.rejected.is_empty());
+
+        for role in ROLES {
+            for action in [Action::Read, Action::Update, Action::Delete] {
+                assert_eq!(
+                    policy
+                        .explain(&role, &post, action, Some(author_id), None)
+                        .allowed,
+                    policy.allows(&role, &post, action, Some(author_id), None)
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -9,7 +9,7 @@ pub async fn set<T>(redis_pool: &Cache, key: &str, value: T, ttl_sec: Option<usi
 where
     T: Serialize + Send + Sync,
 {
-    let mut conn = redis_pool.get().await.unwrap();
+    let mut conn = redis_pool.get().await?;
 
     let value_str = serde_json::to_string(&value)?;
 
@@ -28,20 +28,75 @@ pub async fn get<T>(redis_pool: &Cache, key: &str) -> Result<Option<T>>
 where
     T: DeserializeOwned + Send + Sync,
 {
-    let mut conn = redis_pool.get().await.unwrap();
+    let mut conn = redis_pool.get().await?;
 
-    let result: String = conn.get(key).await?;
+    let result: Option<String> = conn.get(key).await?;
 
-    let value = serde_json::from_str(&result)?;
+    match result {
+        Some(result) => Ok(Some(serde_json::from_str(&result)?)),
+        None => Ok(None),
+    }
+}
+
+/// Reads and removes the value in one step, so it can be consumed only once.
+pub async fn take<T>(redis_pool: &Cache, key: &str) -> Result<Option<T>>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    let mut conn = redis_pool.get().await?;
+
+    let result: Option<String> = conn.get_del(key).await?;
+
+    match result {
+        Some(result) => Ok(Some(serde_json::from_str(&result)?)),
+        None => Ok(None),
+    }
+}
+
+pub async fn exists(redis_pool: &Cache, key: &str) -> Result<bool> {
+    let mut conn = redis_pool.get().await?;
+
+    let result: bool = conn.exists(key).await?;
+
+    Ok(result)
+}
+
+/// Increments a counter and (re)sets its expiry, returning the new value.
+pub async fn incr(redis_pool: &Cache, key: &str, ttl_sec: usize) -> Result<u64> {
+    let mut conn = redis_pool.get().await?;
+
+    let (value, _): (u64, ()) = redis::pipe()
+        .atomic()
+        .incr(key, 1)
+        .expire(key, ttl_sec as i64)
+        .query_async(&mut *conn)
+        .await?;
 
     Ok(value)
 }
 
+/// Remaining time to live in seconds, `None` if the key is missing or never expires.
+pub async fn ttl(redis_pool: &Cache, key: &str) -> Result<Option<u64>> {
+    let mut conn = redis_pool.get().await?;
+
+    let result: i64 = conn.ttl(key).await?;
+
+    Ok(u64::try_from(result).ok())
+}
+
+pub async fn delete(redis_pool: &Cache, keys: &[&str]) -> Result<()> {
+    let mut conn = redis_pool.get().await?;
+
+    let _: () = conn.del(keys).await?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use crate::cache::{
         new_cache_pool,
-        redis_fns::{get, set},
+        redis_fns::{delete, get, incr, set, ttl},
     };
     use anyhow::Result;
     use serde::{Deserialize, Serialize};
@@ -82,4 +137,21 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_incr_counter_with_ttl() -> Result<()> {
+        let pool = new_cache_pool().await?;
+
+        let key = "test_counter";
+        delete(&pool, &[key]).await?;
+
+        assert_eq!(incr(&pool, key, 10).await?, 1);
+        assert_eq!(incr(&pool, key, 10).await?, 2);
+        assert!(ttl(&pool, key).await?.is_some_and(|ttl| ttl <= 10));
+
+        delete(&pool, &[key]).await?;
+        assert_eq!(ttl(&pool, key).await?, None);
+
+        Ok(())
+    }
 }
@@ -11,6 +11,8 @@ pub fn core_config() -> &'static CoreConfig {
 pub struct CoreConfig {
     db_url: String,
     db_max_conn: u32,
+    mail_from: String,
+    mail_outbox_path: Option<String>,
 }
 
 impl CoreConfig {
@@ -18,6 +20,9 @@ impl CoreConfig {
         Ok(Self {
             db_url: lib_utils::env::get_env("DATABASE_URL")?,
             db_max_conn: lib_utils::env::get_parsed_env("DATABASE_MAX_CONNECTIONS")?,
+            mail_from: lib_utils::env::get_env("MAIL_FROM")
+                .unwrap_or_else(|_| "no-reply@localhost".to_string()),
+            mail_outbox_path: lib_utils::env::get_env("MAIL_OUTBOX_PATH").ok(),
         })
     }
 
@@ -28,4 +33,12 @@ impl CoreConfig {
     pub fn db_max_conn(&self) -> &u32 {
         &self.db_max_conn
     }
+
+    pub fn mail_from(&self) -> &str {
+        &self.mail_from
+    }
+
+    pub fn mail_outbox_path(&self) -> Option<&str> {
+        self.mail_outbox_path.as_deref()
+    }
 }
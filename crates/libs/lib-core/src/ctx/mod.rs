@@ -2,15 +2,68 @@ pub mod error;
 
 use uuid::Uuid;
 
-#[derive(Debug, Clone)]
+use crate::acs::{Role, Scope};
+
+#[derive(Debug, Clone, Default)]
 pub struct Ctx {
     pub user_id: Option<Uuid>,
+    /// Role from the access token. `None` when the request was not authenticated with one,
+    /// in which case it has to be looked up.
+    pub role: Option<Role>,
+    /// Admin who is seeing the site as `user_id` through an impersonation token.
+    pub impersonator_id: Option<Uuid>,
+    /// Scopes of the personal access token the request was made with. `None` for every
+    /// other kind of authentication, which is not limited by scopes.
+    pub scopes: Option<Vec<Scope>>,
 }
 
 impl Ctx {
     pub fn new(user_id: Uuid) -> Self {
         Self {
             user_id: Some(user_id),
+            role: None,
+            impersonator_id: None,
+            scopes: None,
+        }
+    }
+
+    pub fn with_role(user_id: Uuid, role: Role) -> Self {
+        Self {
+            user_id: Some(user_id),
+            role: Some(role),
+            impersonator_id: None,
+            scopes: None,
+        }
+    }
+
+    pub fn with_scopes(user_id: Uuid, scopes: Vec<Scope>) -> Self {
+        Self {
+            scopes: Some(scopes),
+            ..Self::new(user_id)
+        }
+    }
+
+    /// Same requester without token scopes, for resources embedded in a response whose own
+    /// action has already been authorized, e.g. the community shown with a post.
+    pub fn unscoped(&self) -> Self {
+        Self {
+            scopes: None,
+            ..self.clone()
+        }
+    }
+
+    pub fn is_impersonated(&self) -> bool {
+        self.impersonator_id.is_some()
+    }
+}
+
+impl From<Option<Uuid>> for Ctx {
+    fn from(user_id: Option<Uuid>) -> Self {
+        Self {
+            user_id,
+            role: None,
+            impersonator_id: None,
+            scopes: None,
         }
     }
 }
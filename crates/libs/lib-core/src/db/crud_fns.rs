@@ -1,4 +1,4 @@
-use sea_query::{Alias, Asterisk, Expr, JoinType, PostgresQueryBuilder, Query};
+use sea_query::{Alias, Asterisk, Expr, JoinType, Order, PostgresQueryBuilder, Query};
 use sea_query_binder::SqlxBinder as _;
 use serde::Serialize;
 use sqlx::FromRow;
@@ -7,6 +7,7 @@ use uuid::Uuid;
 use crate::db::utils::{prepare_sea_query_fields, struct_to_vec};
 use crate::error::{Error, Result};
 
+use super::pagination::{Page, PageRequest, Paginated};
 use super::{Db, DbEntity};
 
 pub async fn create<T, Fc>(db: &Db, fc: Fc) -> Result<T>
@@ -96,6 +97,49 @@ where
     Ok(result)
 }
 
+/// `select_many` one page at a time, see `Paginated` for the order
+pub async fn select_page<T, Fs>(db: &Db, fs: Fs, page: &PageRequest) -> Result<Page<T>>
+where
+    T: DbEntity + Paginated + for<'r> FromRow<'r, sqlx::postgres::PgRow> + Send + Unpin,
+    Fs: Serialize + Sync,
+{
+    let fs_vec = struct_to_vec(&fs);
+    let (columns, sea_values) = prepare_sea_query_fields(fs_vec);
+    let created_at = Alias::new("created_at");
+    let id = Alias::new("id");
+
+    let mut query = Query::select();
+    query.from(T::table_ref());
+    query.columns([Asterisk]);
+    for (column, value) in columns.iter().zip(sea_values) {
+        query.and_where(Expr::col(column.to_owned()).eq(value.to_owned()));
+    }
+    if let Some(cursor) = page.cursor {
+        query.and_where(
+            Expr::tuple([
+                Expr::col(created_at.clone()).into(),
+                Expr::col(id.clone()).into(),
+            ])
+            .lt(Expr::tuple([
+                Expr::val(cursor.created_at).into(),
+                Expr::val(cursor.id).into(),
+            ])),
+        );
+    }
+    query
+        .order_by(created_at, Order::Desc)
+        .order_by(id, Order::Desc)
+        .limit(page.limit() + 1);
+
+    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+
+    let rows = sqlx::query_as_with::<_, T, _>(&sql, values)
+        .fetch_all(db)
+        .await?;
+
+    Ok(Page::from_rows(rows, page.limit()))
+}
+
 pub async fn select_many_with_join<T, J, Fs>(
     db: &Db,
     fs: Fs,
@@ -203,6 +247,96 @@ where
     Ok(count.0 as usize)
 }
 
-// pub async fn select_limit<T>(db: &Db) -> Result<Vec<T>> {
-//     todo!()
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::new_db_pool;
+    use crate::model::chat::{ChatForCreate, ChatRepo};
+    use crate::model::message::{MessageForCreate, MessageForSelect, MessageRepo};
+    use crate::model::user::{UserForCreate, UserRepo};
+    use anyhow::Result;
+
+    #[tokio::test]
+    async fn test_select_page_breaks_ties_by_id() -> Result<()> {
+        let db = new_db_pool().await?;
+
+        let nickname = format!("test_{}", &Uuid::new_v4().simple().to_string()[..12]);
+        let user = UserRepo::create(
+            &db,
+            UserForCreate {
+                email: format!("{nickname}@example.com"),
+                nickname,
+                hashed_password: "not-a-hash".to_string(),
+            },
+        )
+        .await?;
+        let chat = ChatRepo::create(
+            &db,
+            ChatForCreate {
+                name: Some("test".to_string()),
+                is_group: true,
+            },
+        )
+        .await?;
+
+        let mut messages = Vec::new();
+        for i in 0..5 {
+            let message = MessageRepo::create(
+                &db,
+                MessageForCreate {
+                    chat_id: chat.id,
+                    sender_id: user.id,
+                    content: i.to_string(),
+                },
+            )
+            .await?;
+            messages.push(message.id);
+        }
+        // Three rows share one instant, so only `id` can order them across page borders
+        sqlx::query(
+            "UPDATE messages SET created_at = date_trunc('second', NOW())
+                - (CASE WHEN id = ANY($2) THEN INTERVAL '1 second' ELSE INTERVAL '0' END)
+            WHERE chat_id = $1",
+        )
+        .bind(chat.id)
+        .bind(&messages[..2])
+        .execute(&db)
+        .await?;
+
+        let mut expected = MessageRepo::find_all(
+            &db,
+            MessageForSelect {
+                chat_id: Some(chat.id),
+                ..Default::default()
+            },
+        )
+        .await?;
+        expected.sort_by_key(|message| std::cmp::Reverse((message.created_at, message.id)));
+        let expected: Vec<Uuid> = expected.into_iter().map(|message| message.id).collect();
+
+        let mut seen = Vec::new();
+        let mut page = PageRequest {
+            cursor: None,
+            limit: Some(2),
+        };
+        loop {
+            let filter = MessageForSelect {
+                chat_id: Some(chat.id),
+                ..Default::default()
+            };
+            let result = MessageRepo::find_page(&db, filter, &page).await?;
+            assert!(result.items.len() <= 2);
+            seen.extend(result.items.iter().map(|message| message.id));
+
+            match result.next_cursor {
+                Some(cursor) => page.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(seen, expected);
+
+        ChatRepo::delete(&db, &chat.id).await?;
+        UserRepo::delete(&db, &user.id).await?;
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
 use crate::config::core_config;
 
 pub mod crud_fns;
+pub mod pagination;
 mod utils;
 
 pub type Db = Pool<Postgres>;
@@ -0,0 +1,173 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::Error;
+
+pub const DEFAULT_PAGE_SIZE: u64 = 20;
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+/// Rows that can be listed page by page. Pages are ordered by `(created_at, id)`, newest
+/// first, `id` breaks ties between rows created in the same instant.
+pub trait Paginated {
+    fn cursor(&self) -> Cursor;
+}
+
+/// Position of the last row of a page. Sent to clients as `<microseconds>_<id>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub created_at: NaiveDateTime,
+    pub id: Uuid,
+}
+
+impl Display for Cursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}_{}",
+            self.created_at.and_utc().timestamp_micros(),
+            self.id.simple()
+        )
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidInput(format!("Invalid cursor: {s}"));
+
+        let (micros, id) = s.split_once('_').ok_or_else(invalid)?;
+        let created_at = micros
+            .parse()
+            .ok()
+            .and_then(DateTime::from_timestamp_micros)
+            .ok_or_else(invalid)?
+            .naive_utc();
+        let id = Uuid::parse_str(id).map_err(|_| invalid())?;
+
+        Ok(Self { created_at, id })
+    }
+}
+
+impl Serialize for Cursor {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cursor {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Which page to load: the one after `cursor`, or the first one.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct PageRequest {
+    pub cursor: Option<Cursor>,
+    pub limit: Option<u64>,
+}
+
+impl PageRequest {
+    /// Requested size, capped at `MAX_PAGE_SIZE`.
+    pub fn limit(&self) -> u64 {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// `None` on the last page.
+    pub next_cursor: Option<Cursor>,
+}
+
+impl<T> Page<T> {
+    /// `rows` are fetched with one row more than `limit`, that row only tells whether
+    /// another page exists.
+    pub(crate) fn from_rows(mut rows: Vec<T>, limit: u64) -> Self
+    where
+        T: Paginated,
+    {
+        let has_more = rows.len() as u64 > limit;
+        rows.truncate(limit as usize);
+
+        let next_cursor = if has_more {
+            rows.last().map(Paginated::cursor)
+        } else {
+            None
+        };
+        Self {
+            items: rows,
+            next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Row(Cursor);
+
+    impl Paginated for Row {
+        fn cursor(&self) -> Cursor {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_cursor_round_trip() -> anyhow::Result<()> {
+        let cursor = Cursor {
+            created_at: DateTime::from_timestamp_micros(1_730_000_000_123_456)
+                .expect("valid timestamp")
+                .naive_utc(),
+            id: Uuid::new_v4(),
+        };
+
+        assert_eq!(cursor.to_string().parse::<Cursor>()?, cursor);
+        assert!("".parse::<Cursor>().is_err());
+        assert!("123".parse::<Cursor>().is_err());
+        assert!("abc_def".parse::<Cursor>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_page_from_rows() {
+        let rows = |count: usize| {
+            (0..count)
+                .map(|_| {
+                    Row(Cursor {
+                        created_at: NaiveDateTime::default(),
+                        id: Uuid::new_v4(),
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let page = Page::from_rows(rows(3), 3);
+        assert_eq!(page.items.len(), 3);
+        assert!(page.next_cursor.is_none());
+
+        let page = Page::from_rows(rows(4), 3);
+        assert_eq!(page.items.len(), 3);
+        assert_eq!(page.next_cursor, Some(page.items[2].0));
+
+        assert_eq!(PageRequest::default().limit(), DEFAULT_PAGE_SIZE);
+        let page = |limit| PageRequest {
+            cursor: None,
+            limit: Some(limit),
+        };
+        assert_eq!(page(1000).limit(), MAX_PAGE_SIZE);
+        assert_eq!(page(0).limit(), 1);
+    }
+}
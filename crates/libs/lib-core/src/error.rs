@@ -1,4 +1,4 @@
-use crate::acs;
+use crate::{acs, mail};
 
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -45,9 +45,15 @@ pub enum Error {
     #[error(transparent)]
     Redis(#[from] redis::RedisError),
 
+    #[error(transparent)]
+    CachePool(#[from] bb8::RunError<redis::RedisError>),
+
     #[error(transparent)]
     AccessControlSystem(#[from] acs::Error),
 
+    #[error(transparent)]
+    Mail(#[from] mail::Error),
+
     #[error("{0}")]
     InvalidInput(String),
 }
@@ -4,4 +4,5 @@ mod config;
 pub mod ctx;
 pub mod db;
 pub mod error;
+pub mod mail;
 pub mod model;
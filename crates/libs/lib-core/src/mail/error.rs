@@ -0,0 +1,7 @@
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Failed to write mail to outbox: {0}")]
+    Outbox(#[from] std::io::Error),
+}
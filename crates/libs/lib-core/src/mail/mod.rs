@@ -0,0 +1,175 @@
+pub mod error;
+
+use std::fs::OpenOptions;
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use tracing::info;
+
+use crate::config::core_config;
+
+pub use self::error::{Error, Result};
+
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Outgoing mail transport. Implementations must be cheap to share between requests.
+pub trait Mailer: std::fmt::Debug + Send + Sync {
+    fn send(&self, mail: &Mail) -> Result<()>;
+}
+
+pub fn new_mailer() -> Arc<dyn Mailer> {
+    let from = core_config().mail_from().to_string();
+
+    match core_config().mail_outbox_path() {
+        Some(path) => Arc::new(FileMailer::new(from, path)),
+        None => Arc::new(LogMailer::new(from)),
+    }
+}
+
+/// Writes every mail into the application log. Used when no outbox is configured.
+#[derive(Debug)]
+pub struct LogMailer {
+    from: String,
+}
+
+impl LogMailer {
+    pub fn new(from: impl Into<String>) -> Self {
+        Self { from: from.into() }
+    }
+}
+
+impl Mailer for LogMailer {
+    fn send(&self, mail: &Mail) -> Result<()> {
+        info!(
+            "Mail from {} to {}: {}\n{}",
+            self.from, mail.to, mail.subject, mail.body
+        );
+        Ok(())
+    }
+}
+
+/// Appends every mail to a local outbox file instead of delivering it.
+#[derive(Debug)]
+pub struct FileMailer {
+    from: String,
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileMailer {
+    pub fn new(from: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            from: from.into(),
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+impl Mailer for FileMailer {
+    fn send(&self, mail: &Mail) -> Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+
+        writeln!(file, "From: {}", self.from)?;
+        writeln!(file, "To: {}", mail.to)?;
+        writeln!(file, "Subject: {}", mail.subject)?;
+        writeln!(file)?;
+        writeln!(file, "{}", mail.body)?;
+        writeln!(file, ".")?;
+
+        Ok(())
+    }
+}
+
+/// Keeps sent mail in memory so flows that send links can be checked without a mail server.
+#[derive(Debug, Default)]
+pub struct MemoryMailer {
+    sent: Mutex<Vec<Mail>>,
+}
+
+impl MemoryMailer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sent(&self) -> Vec<Mail> {
+        self.sent.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn last_to(&self, to: &str) -> Option<Mail> {
+        self.sent().into_iter().rev().find(|mail| mail.to == to)
+    }
+}
+
+impl Mailer for MemoryMailer {
+    fn send(&self, mail: &Mail) -> Result<()> {
+        self.sent
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(mail.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FileMailer, Mail, Mailer, MemoryMailer};
+
+    #[test]
+    fn test_file_mailer_appends_mail() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("outbox-{}.txt", uuid::Uuid::new_v4()));
+        let mailer = FileMailer::new("no-reply@example.com", &path);
+
+        for subject in ["First", "Second"] {
+            mailer.send(&Mail {
+                to: "user@example.com".to_string(),
+                subject: subject.to_string(),
+                body: "Hello".to_string(),
+            })?;
+        }
+
+        let outbox = std::fs::read_to_string(&path)?;
+        std::fs::remove_file(&path)?;
+
+        assert!(outbox.contains("To: user@example.com"));
+        assert!(outbox.contains("Subject: First"));
+        assert!(outbox.contains("Subject: Second"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_mailer_keeps_sent_mail() -> anyhow::Result<()> {
+        let mailer = MemoryMailer::new();
+
+        for to in [
+            "first@example.com",
+            "second@example.com",
+            "first@example.com",
+        ] {
+            mailer.send(&Mail {
+                to: to.to_string(),
+                subject: "Sign in".to_string(),
+                body: format!("Hello {to}"),
+            })?;
+        }
+
+        assert_eq!(mailer.sent().len(), 3);
+        let last = mailer.last_to("first@example.com").expect("mail was sent");
+        assert_eq!(last.body, "Hello first@example.com");
+        assert!(mailer.last_to("third@example.com").is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::db::{Db, DbEntity};
+use crate::error::Result;
+
+/// Access decision that was denied, kept for abuse investigation
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct AccessDenialRepo {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub role: String,
+    pub resource: String,
+    pub resource_id: Uuid,
+    pub action: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccessDenialForCreate {
+    pub user_id: Uuid,
+    pub role: String,
+    pub resource: String,
+    pub resource_id: Uuid,
+    pub action: String,
+}
+
+impl DbEntity for AccessDenialRepo {
+    const TABLE: &'static str = "access_denials";
+}
+
+impl AccessDenialRepo {
+    pub async fn create(db: &Db, data: AccessDenialForCreate) -> Result<Self> {
+        let query = "INSERT INTO access_denials (user_id, role, resource, resource_id, action)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *";
+        let denial = sqlx::query_as(query)
+            .bind(data.user_id)
+            .bind(data.role)
+            .bind(data.resource)
+            .bind(data.resource_id)
+            .bind(data.action)
+            .fetch_one(db)
+            .await?;
+
+        Ok(denial)
+    }
+
+    /// Stores a batch with a single INSERT, returns the number of rows written
+    pub async fn create_many(db: &Db, data: &[AccessDenialForCreate]) -> Result<u64> {
+        let mut builder = sqlx::QueryBuilder::new(
+            "INSERT INTO access_denials (user_id, role, resource, resource_id, action) ",
+        );
+        builder.push_values(data, |mut row, denial| {
+            row.push_bind(denial.user_id)
+                .push_bind(&denial.role)
+                .push_bind(&denial.resource)
+                .push_bind(denial.resource_id)
+                .push_bind(&denial.action);
+        });
+        let result = builder.build().execute(db).await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Newest denials first, of one user or of everyone
+    pub async fn find_latest(db: &Db, user_id: Option<Uuid>, limit: i64) -> Result<Vec<Self>> {
+        let query = "SELECT * FROM access_denials
+            WHERE $1::uuid IS NULL OR user_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2";
+        let denials = sqlx::query_as(query)
+            .bind(user_id)
+            .bind(limit)
+            .fetch_all(db)
+            .await?;
+
+        Ok(denials)
+    }
+}
@@ -0,0 +1,88 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::db::crud_fns::{delete, select, select_many};
+use crate::db::{Db, DbEntity};
+use crate::error::{Error, Result};
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct ApiTokenRepo {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: String,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct ApiTokenForCreate {
+    pub user_id: Uuid,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: String,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Default)]
+pub struct ApiTokenForSelect {
+    pub id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub token_hash: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct ApiTokenForDelete {
+    pub id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+}
+
+impl DbEntity for ApiTokenRepo {
+    const TABLE: &'static str = "user_api_tokens";
+}
+
+impl ApiTokenRepo {
+    pub async fn create(db: &Db, data: ApiTokenForCreate) -> Result<Self> {
+        let query = "INSERT INTO user_api_tokens (user_id, name, token_hash, scopes, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *";
+        let token = sqlx::query_as(query)
+            .bind(data.user_id)
+            .bind(data.name)
+            .bind(data.token_hash)
+            .bind(data.scopes)
+            .bind(data.expires_at)
+            .fetch_one(db)
+            .await?;
+
+        Ok(token)
+    }
+
+    pub async fn find(db: &Db, filter: ApiTokenForSelect) -> Result<Self> {
+        select::<Self, _>(db, filter).await
+    }
+
+    pub async fn find_all(db: &Db, filter: ApiTokenForSelect) -> Result<Vec<Self>> {
+        select_many::<Self, _>(db, filter).await
+    }
+
+    pub async fn touch(db: &Db, id: &Uuid) -> Result<()> {
+        sqlx::query("UPDATE user_api_tokens SET last_used_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(db: &Db, filter: ApiTokenForDelete) -> Result<()> {
+        if filter.id.is_none() && filter.user_id.is_none() {
+            return Err(Error::AllNone);
+        }
+        delete::<Self, _>(db, filter).await
+    }
+}
@@ -0,0 +1,99 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::db::crud_fns::select_many;
+use crate::db::{Db, DbEntity};
+use crate::error::Result;
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct BanRepo {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub issued_by: Option<Uuid>,
+    pub reason: String,
+    pub starts_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+    pub lifted_at: Option<NaiveDateTime>,
+    pub lifted_by: Option<Uuid>,
+}
+
+#[derive(Serialize)]
+pub struct BanForCreate {
+    pub user_id: Uuid,
+    pub issued_by: Uuid,
+    pub reason: String,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Default)]
+pub struct BanForSelect {
+    pub user_id: Option<Uuid>,
+}
+
+impl DbEntity for BanRepo {
+    const TABLE: &'static str = "user_bans";
+}
+
+impl BanRepo {
+    pub async fn create(db: &Db, data: BanForCreate) -> Result<Self> {
+        let query = "INSERT INTO user_bans (user_id, issued_by, reason, expires_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *";
+        let ban = sqlx::query_as(query)
+            .bind(data.user_id)
+            .bind(data.issued_by)
+            .bind(data.reason)
+            .bind(data.expires_at)
+            .fetch_one(db)
+            .await?;
+
+        Ok(ban)
+    }
+
+    pub async fn find_active(db: &Db, user_id: &Uuid) -> Result<Option<Self>> {
+        let query = "SELECT * FROM user_bans
+            WHERE user_id = $1
+                AND lifted_at IS NULL
+                AND (expires_at IS NULL OR expires_at > NOW())
+            ORDER BY expires_at DESC NULLS FIRST
+            LIMIT 1";
+        let ban = sqlx::query_as(query)
+            .bind(user_id)
+            .fetch_optional(db)
+            .await?;
+
+        Ok(ban)
+    }
+
+    /// The longest active ban of every banned user.
+    pub async fn find_all_active(db: &Db) -> Result<Vec<Self>> {
+        let query = "SELECT DISTINCT ON (user_id) * FROM user_bans
+            WHERE lifted_at IS NULL
+                AND (expires_at IS NULL OR expires_at > NOW())
+            ORDER BY user_id, expires_at DESC NULLS FIRST";
+        let bans = sqlx::query_as(query).fetch_all(db).await?;
+
+        Ok(bans)
+    }
+
+    pub async fn find_all(db: &Db, filter: BanForSelect) -> Result<Vec<Self>> {
+        select_many::<Self, _>(db, filter).await
+    }
+
+    pub async fn lift(db: &Db, user_id: &Uuid, lifted_by: &Uuid) -> Result<u64> {
+        let query = "UPDATE user_bans
+            SET lifted_at = NOW(), lifted_by = $2
+            WHERE user_id = $1
+                AND lifted_at IS NULL
+                AND (expires_at IS NULL OR expires_at > NOW())";
+        let result = sqlx::query(query)
+            .bind(user_id)
+            .bind(lifted_by)
+            .execute(db)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -68,6 +68,10 @@ impl ChatRepo {
     }
 
     pub async fn delete(db: &Db, id: &Uuid) -> Result<()> {
-        delete::<Self, _>(db, id).await
+        let filter = ChatForSelect {
+            id: Some(*id),
+            ..Default::default()
+        };
+        delete::<Self, _>(db, filter).await
     }
 }
@@ -1,4 +1,5 @@
-use crate::db::crud_fns::{count, select, select_many, update};
+use crate::db::crud_fns::{count, select, select_many, select_page, update};
+use crate::db::pagination::{Cursor, Page, PageRequest, Paginated};
 use crate::db::{crud_fns::create, Db, DbEntity};
 use crate::error::Result;
 use chrono::NaiveDateTime;
@@ -22,6 +23,15 @@ impl DbEntity for CommentRepo {
     const TABLE: &'static str = "comments";
 }
 
+impl Paginated for CommentRepo {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.created_at,
+            id: self.id,
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct CommentForCreate {
     pub post_id: Uuid,
@@ -61,6 +71,14 @@ impl CommentRepo {
         select_many::<Self, _>(db, comment_fs).await
     }
 
+    pub async fn find_page(
+        db: &Db,
+        comment_fs: CommentForSelect,
+        page: &PageRequest,
+    ) -> Result<Page<CommentRepo>> {
+        select_page::<Self, _>(db, comment_fs, page).await
+    }
+
     pub async fn update(db: &Db, id: &Uuid, comment_fu: CommentForUpdate) -> Result<CommentRepo> {
         update::<Self, _>(db, id, comment_fu).await
     }
@@ -1,4 +1,5 @@
-use crate::db::crud_fns::{create, delete, select, select_many, update};
+use crate::db::crud_fns::{create, delete, select, select_many, select_page, update};
+use crate::db::pagination::{Cursor, Page, PageRequest, Paginated};
 use crate::db::{Db, DbEntity};
 use crate::error::Result;
 use chrono::NaiveDateTime;
@@ -13,6 +14,7 @@ pub struct CommunityRepo {
     pub name: String,
     pub description: String,
     pub is_private: bool,
+    pub is_invite_only: bool,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
@@ -23,6 +25,7 @@ pub struct CommunityForCreate {
     pub user_id: Uuid,
     pub description: String,
     pub is_private: bool,
+    pub is_invite_only: bool,
 }
 
 #[derive(Serialize, Default)]
@@ -30,6 +33,7 @@ pub struct CommunityForUpdate {
     pub name: Option<String>,
     pub description: Option<String>,
     pub is_private: Option<bool>,
+    pub is_invite_only: Option<bool>,
 }
 
 #[derive(Serialize, Default)]
@@ -50,6 +54,15 @@ impl DbEntity for CommunityRepo {
     const TABLE: &'static str = "communities";
 }
 
+impl Paginated for CommunityRepo {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.created_at,
+            id: self.id,
+        }
+    }
+}
+
 impl CommunityRepo {
     pub async fn create(db: &Db, community_fc: CommunityForCreate) -> Result<CommunityRepo> {
         create::<Self, _>(db, community_fc).await
@@ -71,6 +84,14 @@ impl CommunityRepo {
         select_many::<Self, _>(db, user_fs).await
     }
 
+    pub async fn find_page(
+        db: &Db,
+        community_fs: CommunityForSelect,
+        page: &PageRequest,
+    ) -> Result<Page<CommunityRepo>> {
+        select_page::<Self, _>(db, community_fs, page).await
+    }
+
     pub async fn find_many_by_query(db: &Db, query: &str) -> Result<Vec<CommunityRepo>> {
         let q = format!("%{}%", query);
 
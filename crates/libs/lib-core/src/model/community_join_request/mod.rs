@@ -0,0 +1,117 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Type};
+use uuid::Uuid;
+
+use crate::db::crud_fns::select;
+use crate::db::{Db, DbEntity};
+use crate::error::{Error, Result};
+
+#[derive(Debug, Deserialize, Serialize, Type, Clone, Copy, PartialEq)]
+#[sqlx(type_name = "join_request_status_enum")]
+#[serde(rename_all = "snake_case")]
+pub enum JoinRequestStatusEnum {
+    #[sqlx(rename = "pending")]
+    Pending,
+
+    #[sqlx(rename = "approved")]
+    Approved,
+
+    #[sqlx(rename = "denied")]
+    Denied,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct CommunityJoinRequestRepo {
+    pub id: Uuid,
+    pub community_id: Uuid,
+    pub user_id: Uuid,
+    pub message: Option<String>,
+    pub status: JoinRequestStatusEnum,
+    pub created_at: NaiveDateTime,
+    pub decided_at: Option<NaiveDateTime>,
+    pub decided_by: Option<Uuid>,
+}
+
+#[derive(Serialize, Default)]
+pub struct CommunityJoinRequestForSelect {
+    pub id: Option<Uuid>,
+    pub community_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+}
+
+impl DbEntity for CommunityJoinRequestRepo {
+    const TABLE: &'static str = "community_join_requests";
+}
+
+// Statuses are bound explicitly: the generic crud functions would cast `pending` and
+// `approved` to `report_status_type`
+impl CommunityJoinRequestRepo {
+    pub async fn create(
+        db: &Db,
+        community_id: &Uuid,
+        user_id: &Uuid,
+        message: Option<String>,
+    ) -> Result<Self> {
+        let query = "INSERT INTO community_join_requests (community_id, user_id, message)
+            VALUES ($1, $2, $3)
+            RETURNING *";
+        let request = sqlx::query_as(query)
+            .bind(community_id)
+            .bind(user_id)
+            .bind(message)
+            .fetch_one(db)
+            .await?;
+
+        Ok(request)
+    }
+
+    pub async fn find(db: &Db, filter: CommunityJoinRequestForSelect) -> Result<Self> {
+        select::<Self, _>(db, filter).await
+    }
+
+    pub async fn find_pending(db: &Db, community_id: &Uuid, user_id: &Uuid) -> Result<Self> {
+        let query = "SELECT * FROM community_join_requests
+            WHERE community_id = $1 AND user_id = $2 AND status = 'pending'";
+        sqlx::query_as(query)
+            .bind(community_id)
+            .bind(user_id)
+            .fetch_optional(db)
+            .await?
+            .ok_or(Error::EntityNotFound)
+    }
+
+    pub async fn find_all_pending(db: &Db, community_id: &Uuid) -> Result<Vec<Self>> {
+        let query = "SELECT * FROM community_join_requests
+            WHERE community_id = $1 AND status = 'pending'
+            ORDER BY created_at";
+        let requests = sqlx::query_as(query)
+            .bind(community_id)
+            .fetch_all(db)
+            .await?;
+
+        Ok(requests)
+    }
+
+    /// Approves or denies a pending request. Requests that were already decided are not found
+    pub async fn decide(
+        db: &Db,
+        id: &Uuid,
+        community_id: &Uuid,
+        status: JoinRequestStatusEnum,
+        decided_by: &Uuid,
+    ) -> Result<Self> {
+        let query = "UPDATE community_join_requests
+            SET status = $3, decided_at = NOW(), decided_by = $4
+            WHERE id = $1 AND community_id = $2 AND status = 'pending'
+            RETURNING *";
+        sqlx::query_as(query)
+            .bind(id)
+            .bind(community_id)
+            .bind(status)
+            .bind(decided_by)
+            .fetch_optional(db)
+            .await?
+            .ok_or(Error::EntityNotFound)
+    }
+}
@@ -0,0 +1,105 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::db::crud_fns::{select, select_many};
+use crate::db::{Db, DbEntity};
+use crate::error::Result;
+
+use super::community_role::CommunityRoleEnum;
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct CommunityMemberRepo {
+    pub community_id: Uuid,
+    pub user_id: Uuid,
+    pub role: CommunityRoleEnum,
+    pub joined_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Default)]
+pub struct CommunityMemberForSelect {
+    pub community_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+}
+
+impl DbEntity for CommunityMemberRepo {
+    const TABLE: &'static str = "community_members";
+}
+
+// Roles are bound explicitly: the generic crud functions would cast `owner` and `member`
+// to `chat_role_enum`
+impl CommunityMemberRepo {
+    pub async fn find(db: &Db, filter: CommunityMemberForSelect) -> Result<Self> {
+        select::<Self, _>(db, filter).await
+    }
+
+    pub async fn find_all(db: &Db, filter: CommunityMemberForSelect) -> Result<Vec<Self>> {
+        select_many::<Self, _>(db, filter).await
+    }
+
+    pub async fn find_all_by_roles(
+        db: &Db,
+        community_id: &Uuid,
+        roles: &[CommunityRoleEnum],
+    ) -> Result<Vec<Self>> {
+        let query = "SELECT * FROM community_members
+            WHERE community_id = $1 AND role = ANY($2)
+            ORDER BY joined_at";
+        let members = sqlx::query_as(query)
+            .bind(community_id)
+            .bind(roles)
+            .fetch_all(db)
+            .await?;
+
+        Ok(members)
+    }
+
+    /// Adds a member, or changes the role of an existing one
+    pub async fn upsert(
+        db: &Db,
+        community_id: &Uuid,
+        user_id: &Uuid,
+        role: CommunityRoleEnum,
+    ) -> Result<Self> {
+        let query = "INSERT INTO community_members (community_id, user_id, role)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (community_id, user_id) DO UPDATE SET role = EXCLUDED.role
+            RETURNING *";
+        let member = sqlx::query_as(query)
+            .bind(community_id)
+            .bind(user_id)
+            .bind(role)
+            .fetch_one(db)
+            .await?;
+
+        Ok(member)
+    }
+
+    /// Adds a plain member, keeping the role of someone who is already in the community
+    pub async fn join(db: &Db, community_id: &Uuid, user_id: &Uuid) -> Result<()> {
+        let query = "INSERT INTO community_members (community_id, user_id, role)
+            VALUES ($1, $2, 'member')
+            ON CONFLICT (community_id, user_id) DO NOTHING";
+        sqlx::query(query)
+            .bind(community_id)
+            .bind(user_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Removes a plain member. Moderators and the owner stay until their role is taken away
+    pub async fn leave(db: &Db, community_id: &Uuid, user_id: &Uuid) -> Result<()> {
+        let query = "DELETE FROM community_members
+            WHERE community_id = $1 AND user_id = $2 AND role = 'member'";
+        sqlx::query(query)
+            .bind(community_id)
+            .bind(user_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use sqlx::Type;
+
+use crate::error::Error;
+
+#[derive(Debug, Deserialize, Serialize, Type, Clone, Copy, PartialEq)]
+#[sqlx(type_name = "community_role_enum")]
+#[serde(rename_all = "snake_case")]
+pub enum CommunityRoleEnum {
+    #[sqlx(rename = "owner")]
+    Owner,
+
+    #[sqlx(rename = "moderator")]
+    Moderator,
+
+    #[sqlx(rename = "member")]
+    Member,
+}
+
+impl CommunityRoleEnum {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CommunityRoleEnum::Owner => "owner",
+            CommunityRoleEnum::Moderator => "moderator",
+            CommunityRoleEnum::Member => "member",
+        }
+    }
+}
+
+impl FromStr for CommunityRoleEnum {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "owner" => Ok(CommunityRoleEnum::Owner),
+            "moderator" => Ok(CommunityRoleEnum::Moderator),
+            "member" => Ok(CommunityRoleEnum::Member),
+            _ => Err(Error::ParseEnumError),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::db::crud_fns::select_many;
+use crate::db::{Db, DbEntity};
+use crate::error::{Error, Result};
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct IdentityRepo {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct IdentityForCreate {
+    pub user_id: Uuid,
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct IdentityForSelect {
+    pub user_id: Option<Uuid>,
+}
+
+impl DbEntity for IdentityRepo {
+    const TABLE: &'static str = "user_identities";
+}
+
+// Subjects are opaque provider strings and may look like UUIDs, so they are bound
+// as text explicitly instead of going through the generic crud helpers
+impl IdentityRepo {
+    pub async fn create(db: &Db, data: IdentityForCreate) -> Result<Self> {
+        let query = "INSERT INTO user_identities (user_id, provider, subject, email)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *";
+        let identity = sqlx::query_as(query)
+            .bind(data.user_id)
+            .bind(data.provider)
+            .bind(data.subject)
+            .bind(data.email)
+            .fetch_one(db)
+            .await?;
+
+        Ok(identity)
+    }
+
+    pub async fn find_by_subject(db: &Db, provider: &str, subject: &str) -> Result<Self> {
+        let query = "SELECT * FROM user_identities WHERE provider = $1 AND subject = $2";
+        sqlx::query_as(query)
+            .bind(provider)
+            .bind(subject)
+            .fetch_optional(db)
+            .await?
+            .ok_or(Error::EntityNotFound)
+    }
+
+    pub async fn find_all(db: &Db, filter: IdentityForSelect) -> Result<Vec<Self>> {
+        select_many::<Self, _>(db, filter).await
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::db::crud_fns::{select, select_many};
+use crate::db::{Db, DbEntity};
+use crate::error::Result;
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ImpersonationRepo {
+    pub id: Uuid,
+    pub admin_id: Option<Uuid>,
+    pub user_id: Uuid,
+    pub reason: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub started_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub ended_at: Option<NaiveDateTime>,
+    pub ended_by: Option<Uuid>,
+}
+
+#[derive(Serialize)]
+pub struct ImpersonationForCreate {
+    pub admin_id: Uuid,
+    pub user_id: Uuid,
+    pub reason: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Default)]
+pub struct ImpersonationForSelect {
+    pub id: Option<Uuid>,
+    pub admin_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+}
+
+impl DbEntity for ImpersonationRepo {
+    const TABLE: &'static str = "user_impersonations";
+}
+
+impl ImpersonationRepo {
+    pub async fn create(db: &Db, data: ImpersonationForCreate) -> Result<Self> {
+        let query = "INSERT INTO user_impersonations
+                (admin_id, user_id, reason, user_agent, ip, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *";
+        let impersonation = sqlx::query_as(query)
+            .bind(data.admin_id)
+            .bind(data.user_id)
+            .bind(data.reason)
+            .bind(data.user_agent)
+            .bind(data.ip)
+            .bind(data.expires_at)
+            .fetch_one(db)
+            .await?;
+
+        Ok(impersonation)
+    }
+
+    pub async fn find(db: &Db, filter: ImpersonationForSelect) -> Result<Self> {
+        select::<Self, _>(db, filter).await
+    }
+
+    pub async fn find_all(db: &Db, filter: ImpersonationForSelect) -> Result<Vec<Self>> {
+        select_many::<Self, _>(db, filter).await
+    }
+
+    /// Ends the session early. Sessions that already ended or expired are left as they are
+    pub async fn end(db: &Db, id: &Uuid, ended_by: &Uuid) -> Result<u64> {
+        let query = "UPDATE user_impersonations
+            SET ended_at = NOW(), ended_by = $2
+            WHERE id = $1
+                AND ended_at IS NULL
+                AND expires_at > NOW()";
+        let result = sqlx::query(query)
+            .bind(id)
+            .bind(ended_by)
+            .execute(db)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -3,7 +3,8 @@ use serde::Serialize;
 use sqlx::FromRow;
 use uuid::Uuid;
 
-use crate::db::crud_fns::{create, select, select_many, update};
+use crate::db::crud_fns::{create, select, select_many, select_page, update};
+use crate::db::pagination::{Cursor, Page, PageRequest, Paginated};
 use crate::db::{Db, DbEntity};
 use crate::error::Result;
 
@@ -42,6 +43,15 @@ impl DbEntity for MessageRepo {
     const TABLE: &'static str = "messages";
 }
 
+impl Paginated for MessageRepo {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.created_at,
+            id: self.id,
+        }
+    }
+}
+
 impl MessageRepo {
     pub async fn create(db: &Db, data: MessageForCreate) -> Result<Self> {
         create::<Self, _>(db, data).await
@@ -75,6 +85,14 @@ impl MessageRepo {
         select_many::<Self, _>(db, filter).await
     }
 
+    pub async fn find_page(
+        db: &Db,
+        filter: MessageForSelect,
+        page: &PageRequest,
+    ) -> Result<Page<Self>> {
+        select_page::<Self, _>(db, filter, page).await
+    }
+
     pub async fn find_many_by_query(db: &Db, query: &str) -> Result<Vec<Self>> {
         let q = format!("%{}%", query);
 
@@ -3,34 +3,55 @@ use std::sync::Arc;
 use crate::cache::{new_cache_pool, Cache};
 use crate::db::{new_db_pool, Db};
 use crate::error::Result;
+use crate::mail::{new_mailer, Mailer};
 
+pub mod access_denial;
+pub mod api_token;
+pub mod ban;
 pub mod chat;
 pub mod chat_member;
 pub mod chat_role;
 pub mod comment;
 pub mod community;
+pub mod community_join_request;
+pub mod community_member;
+pub mod community_role;
 pub mod follow;
+pub mod identity;
+pub mod impersonation;
 pub mod like;
 pub mod message;
 pub mod message_status;
 pub mod post;
+pub mod recovery_code;
 pub mod report;
 pub mod role;
 pub mod save;
+pub mod session;
 pub mod token;
+pub mod totp;
 pub mod user;
 
 #[derive(Debug, Clone)]
 pub struct ModelManager {
     db: Arc<Db>,
     cache: Arc<Cache>,
+    mailer: Arc<dyn Mailer>,
 }
 
 impl ModelManager {
     pub async fn new() -> Result<Self> {
         let db = Arc::new(new_db_pool().await?);
         let cache = Arc::new(new_cache_pool().await?);
-        Ok(Self { db, cache })
+        let mailer = new_mailer();
+        Ok(Self { db, cache, mailer })
+    }
+
+    /// Same as `new` but with a given mail transport, e.g. `MemoryMailer` in tests.
+    pub async fn with_mailer(mailer: Arc<dyn Mailer>) -> Result<Self> {
+        let db = Arc::new(new_db_pool().await?);
+        let cache = Arc::new(new_cache_pool().await?);
+        Ok(Self { db, cache, mailer })
     }
 
     pub fn db(&self) -> &Db {
@@ -40,4 +61,8 @@ impl ModelManager {
     pub fn cache(&self) -> &Cache {
         &self.cache
     }
+
+    pub fn mailer(&self) -> Arc<dyn Mailer> {
+        self.mailer.clone()
+    }
 }
@@ -1,4 +1,5 @@
-use crate::db::crud_fns::{create, select, select_many, update};
+use crate::db::crud_fns::{create, select, select_many, select_page, update};
+use crate::db::pagination::{Cursor, Page, PageRequest, Paginated};
 use crate::db::{Db, DbEntity};
 use crate::error::Result;
 use chrono::NaiveDateTime;
@@ -16,12 +17,22 @@ pub struct PostRepo {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub is_deleted: bool,
+    pub is_pinned: bool,
 }
 
 impl DbEntity for PostRepo {
     const TABLE: &'static str = "posts";
 }
 
+impl Paginated for PostRepo {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.created_at,
+            id: self.id,
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct PostForCreate {
     pub user_id: Uuid,
@@ -42,6 +53,7 @@ pub struct PostForSelect {
     pub user_id: Option<Uuid>,
     pub community_id: Option<Uuid>,
     pub is_deleted: Option<bool>,
+    pub is_pinned: Option<bool>,
 }
 
 #[derive(Serialize, Default)]
@@ -66,6 +78,14 @@ impl PostRepo {
         select_many::<Self, _>(db, post_fs).await
     }
 
+    pub async fn find_page(
+        db: &Db,
+        post_fs: PostForSelect,
+        page: &PageRequest,
+    ) -> Result<Page<PostRepo>> {
+        select_page::<Self, _>(db, post_fs, page).await
+    }
+
     pub async fn find_many_by_query(db: &Db, query: &str) -> Result<Vec<PostRepo>> {
         let q = format!("%{}%", query);
 
@@ -77,6 +97,16 @@ impl PostRepo {
         Ok(users)
     }
 
+    pub async fn set_pinned(db: &Db, id: &Uuid, is_pinned: bool) -> Result<PostRepo> {
+        let query = "UPDATE posts SET is_pinned = $2 WHERE id = $1 RETURNING *";
+        let post = sqlx::query_as(query)
+            .bind(id)
+            .bind(is_pinned)
+            .fetch_one(db)
+            .await?;
+        Ok(post)
+    }
+
     pub async fn delete(db: &Db, post_fd: PostForDelete) -> Result<Self> {
         let query = "UPDATE posts SET is_deleted = TRUE, content = '' WHERE id = $1";
         let post = sqlx::query_as(query).bind(post_fd.id).fetch_one(db).await?;
@@ -0,0 +1,42 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::db::crud_fns::{create, delete};
+use crate::db::{Db, DbEntity};
+use crate::error::Result;
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct RecoveryCodeRepo {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub code_hash: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct RecoveryCodeForCreate {
+    pub user_id: Uuid,
+    pub code_hash: String,
+}
+
+#[derive(Serialize, Default)]
+pub struct RecoveryCodeForDelete {
+    pub user_id: Option<Uuid>,
+    pub code_hash: Option<String>,
+}
+
+impl DbEntity for RecoveryCodeRepo {
+    const TABLE: &'static str = "user_recovery_codes";
+}
+
+impl RecoveryCodeRepo {
+    pub async fn create(db: &Db, data: RecoveryCodeForCreate) -> Result<Self> {
+        create::<Self, _>(db, data).await
+    }
+
+    pub async fn delete(db: &Db, filter: RecoveryCodeForDelete) -> Result<()> {
+        delete::<Self, _>(db, filter).await
+    }
+}
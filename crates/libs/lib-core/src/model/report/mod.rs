@@ -7,7 +7,8 @@ use sqlx::Type;
 use std::str::FromStr;
 use uuid::Uuid;
 
-use crate::db::crud_fns::{create, delete, select, select_many, update};
+use crate::db::crud_fns::{create, delete, select, select_many, select_page, update};
+use crate::db::pagination::{Cursor, Page, PageRequest, Paginated};
 use crate::db::{Db, DbEntity};
 use crate::error::Error;
 use crate::error::Result;
@@ -88,6 +89,15 @@ impl DbEntity for ReportRepo {
     const TABLE: &'static str = "reports";
 }
 
+impl Paginated for ReportRepo {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.created_at,
+            id: self.id,
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct ReportForCreate {
     pub report_type: ReportTargetType,
@@ -133,6 +143,14 @@ impl ReportRepo {
         select_many::<Self, _>(db, report_fs).await
     }
 
+    pub async fn find_page(
+        db: &Db,
+        report_fs: ReportForSelect,
+        page: &PageRequest,
+    ) -> Result<Page<ReportRepo>> {
+        select_page::<Self, _>(db, report_fs, page).await
+    }
+
     pub async fn delete(db: &Db, report_fd: ReportForDelete) -> Result<()> {
         delete::<Self, _>(db, report_fd).await
     }
@@ -1,4 +1,5 @@
-use crate::db::crud_fns::{create, delete, select, select_many};
+use crate::db::crud_fns::{create, delete, select, select_many, select_page};
+use crate::db::pagination::{Cursor, Page, PageRequest, Paginated};
 use crate::db::{Db, DbEntity};
 use crate::error::Result;
 use chrono::NaiveDateTime;
@@ -18,6 +19,15 @@ impl DbEntity for SaveRepo {
     const TABLE: &'static str = "user_saves";
 }
 
+impl Paginated for SaveRepo {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.created_at,
+            id: self.id,
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct SaveForCreate {
     pub user_id: Uuid,
@@ -49,6 +59,14 @@ impl SaveRepo {
         select_many::<Self, _>(db, save_fs).await
     }
 
+    pub async fn find_page(
+        db: &Db,
+        save_fs: SaveForSelect,
+        page: &PageRequest,
+    ) -> Result<Page<SaveRepo>> {
+        select_page::<Self, _>(db, save_fs, page).await
+    }
+
     pub async fn delete(db: &Db, save_fd: SaveForDelete) -> Result<()> {
         delete::<Self, _>(db, save_fd).await
     }
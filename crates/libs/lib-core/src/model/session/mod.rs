@@ -0,0 +1,98 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::db::crud_fns::{create, delete, select, select_many};
+use crate::db::{Db, DbEntity};
+use crate::error::{Error, Result};
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct SessionRepo {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub refresh_token: String,
+    pub generation: i32,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct SessionForCreate {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub refresh_token: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct SessionForSelect {
+    pub id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct SessionForDelete {
+    pub id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub refresh_token: Option<String>,
+}
+
+impl DbEntity for SessionRepo {
+    const TABLE: &'static str = "user_sessions";
+}
+
+impl SessionRepo {
+    pub async fn create(db: &Db, data: SessionForCreate) -> Result<Self> {
+        create::<Self, _>(db, data).await
+    }
+
+    pub async fn find(db: &Db, filter: SessionForSelect) -> Result<Self> {
+        select::<Self, _>(db, filter).await
+    }
+
+    pub async fn find_all(db: &Db, filter: SessionForSelect) -> Result<Vec<Self>> {
+        select_many::<Self, _>(db, filter).await
+    }
+
+    pub async fn rotate(
+        db: &Db,
+        id: &Uuid,
+        generation: i32,
+        refresh_token: &str,
+        user_agent: Option<&str>,
+        ip: Option<&str>,
+    ) -> Result<Self> {
+        // Compare-and-set on generation so that two concurrent refreshes cannot both win
+        let query = "UPDATE user_sessions
+            SET refresh_token = $3,
+                generation = generation + 1,
+                user_agent = COALESCE($4, user_agent),
+                ip = COALESCE($5, ip),
+                last_used_at = NOW()
+            WHERE id = $1 AND generation = $2
+            RETURNING *";
+        let session = sqlx::query_as(query)
+            .bind(id)
+            .bind(generation)
+            .bind(refresh_token)
+            .bind(user_agent)
+            .bind(ip)
+            .fetch_optional(db)
+            .await?
+            .ok_or(Error::EntityNotFound)?;
+
+        Ok(session)
+    }
+
+    pub async fn delete(db: &Db, filter: SessionForDelete) -> Result<()> {
+        if filter.id.is_none() && filter.user_id.is_none() && filter.refresh_token.is_none() {
+            return Err(Error::AllNone);
+        }
+        delete::<Self, _>(db, filter).await
+    }
+}
@@ -1,6 +1,7 @@
 use crate::db::crud_fns::{create, delete, select, update};
 use crate::db::{Db, DbEntity};
 use crate::error::{Error, Result};
+use chrono::NaiveDateTime;
 use derive_more::derive::Display;
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::{FromRow, Type};
@@ -30,6 +31,10 @@ pub enum TokenTypeEnum {
     #[sqlx(rename = "email_verification")]
     #[display("email_verification")]
     EmailVerification,
+
+    #[sqlx(rename = "magic_link")]
+    #[display("magic_link")]
+    MagicLink,
 }
 
 impl FromStr for TokenTypeEnum {
@@ -42,6 +47,7 @@ impl FromStr for TokenTypeEnum {
             "reset_password" => Ok(TokenTypeEnum::ResetPassword),
             "reset_email" => Ok(TokenTypeEnum::ResetEmail),
             "email_verification" => Ok(TokenTypeEnum::EmailVerification),
+            "magic_link" => Ok(TokenTypeEnum::MagicLink),
             _ => Err(Error::ParseEnumError),
         }
     }
@@ -53,6 +59,8 @@ pub struct Token {
     pub user_id: Uuid,
     pub token: String,
     pub token_type: TokenTypeEnum,
+    pub payload: Option<String>,
+    pub created_at: NaiveDateTime,
 }
 
 #[derive(Serialize)]
@@ -60,6 +68,7 @@ pub struct TokenForCreate {
     pub user_id: Uuid,
     pub token: String,
     pub token_type: TokenTypeEnum,
+    pub payload: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -83,9 +92,11 @@ pub struct TokenForSelect {
     pub token_type: Option<TokenTypeEnum>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Default)]
 pub struct TokenForDelete {
-    pub token: String,
+    pub token: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub token_type: Option<TokenTypeEnum>,
 }
 
 impl DbEntity for Token {
@@ -109,7 +120,10 @@ impl Token {
         select(db, token_fs).await
     }
 
-    pub async fn delete(db: &Db, token_fs: TokenForDelete) -> Result<()> {
-        delete::<Token, _>(db, token_fs).await
+    pub async fn delete(db: &Db, token_fd: TokenForDelete) -> Result<()> {
+        if token_fd.token.is_none() && token_fd.user_id.is_none() {
+            return Err(Error::AllNone);
+        }
+        delete::<Token, _>(db, token_fd).await
     }
 }
@@ -0,0 +1,72 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::db::crud_fns::{create, delete, select, update};
+use crate::db::{Db, DbEntity};
+use crate::error::Result;
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct TotpRepo {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub secret: String,
+    pub enabled: bool,
+    pub last_used_step: Option<i64>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct TotpForCreate {
+    pub user_id: Uuid,
+    pub secret: String,
+}
+
+#[derive(Serialize, Default)]
+pub struct TotpForUpdate {
+    pub enabled: Option<bool>,
+    pub secret: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct TotpForSelect {
+    pub user_id: Option<Uuid>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct TotpForDelete {
+    pub user_id: Uuid,
+}
+
+impl DbEntity for TotpRepo {
+    const TABLE: &'static str = "user_totp";
+}
+
+impl TotpRepo {
+    pub async fn create(db: &Db, data: TotpForCreate) -> Result<Self> {
+        create::<Self, _>(db, data).await
+    }
+
+    pub async fn update(db: &Db, id: &Uuid, data: TotpForUpdate) -> Result<Self> {
+        update::<Self, _>(db, id, data).await
+    }
+
+    pub async fn find(db: &Db, filter: TotpForSelect) -> Result<Self> {
+        select::<Self, _>(db, filter).await
+    }
+
+    pub async fn use_step(db: &Db, id: &Uuid, step: i64) -> Result<bool> {
+        // Шаг принимается только один раз, повтор того же кода отклоняется
+        let query = "UPDATE user_totp SET last_used_step = $2
+            WHERE id = $1 AND (last_used_step IS NULL OR last_used_step < $2)";
+        let result = sqlx::query(query).bind(id).bind(step).execute(db).await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn delete(db: &Db, filter: TotpForDelete) -> Result<()> {
+        delete::<Self, _>(db, filter).await
+    }
+}
@@ -3,7 +3,8 @@ use serde::Serialize;
 use sqlx::FromRow;
 use uuid::Uuid;
 
-use crate::db::crud_fns::{create, delete, select, select_many, update};
+use crate::db::crud_fns::{create, delete, select, select_many, select_page, update};
+use crate::db::pagination::{Cursor, Page, PageRequest, Paginated};
 use crate::db::{Db, DbEntity};
 use crate::error::Result;
 use crate::model::role::RoleEnum;
@@ -16,6 +17,7 @@ pub struct UserRepo {
     pub email: String,
     pub hashed_password: String,
     pub is_banned: bool,
+    pub email_verified: bool,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
@@ -34,6 +36,7 @@ pub struct UserForUpdate {
     pub role: Option<RoleEnum>,
     pub hashed_password: Option<String>,
     pub is_banned: Option<bool>,
+    pub email_verified: Option<bool>,
 }
 
 #[derive(Serialize, Default)]
@@ -45,10 +48,24 @@ pub struct UserForSelect {
     pub is_banned: Option<bool>,
 }
 
+#[derive(Serialize)]
+pub struct UserForDelete {
+    pub id: Uuid,
+}
+
 impl DbEntity for UserRepo {
     const TABLE: &'static str = "users";
 }
 
+impl Paginated for UserRepo {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.created_at,
+            id: self.id,
+        }
+    }
+}
+
 impl UserRepo {
     pub async fn create(db: &Db, user_fc: UserForCreate) -> Result<UserRepo> {
         create::<Self, _>(db, user_fc).await
@@ -66,6 +83,14 @@ impl UserRepo {
         select_many::<Self, _>(db, user_fs).await
     }
 
+    pub async fn find_page(
+        db: &Db,
+        user_fs: UserForSelect,
+        page: &PageRequest,
+    ) -> Result<Page<UserRepo>> {
+        select_page::<Self, _>(db, user_fs, page).await
+    }
+
     pub async fn find_many_by_query(db: &Db, query: &str) -> Result<Vec<UserRepo>> {
         let q = format!("%{}%", query);
 
@@ -78,6 +103,6 @@ impl UserRepo {
     }
 
     pub async fn delete(db: &Db, id: &Uuid) -> Result<()> {
-        delete::<Self, _>(db, id).await
+        delete::<Self, _>(db, UserForDelete { id: *id }).await
     }
 }
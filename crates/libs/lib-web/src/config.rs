@@ -0,0 +1,97 @@
+use std::sync::OnceLock;
+
+use axum_extra::extract::cookie::SameSite;
+
+pub fn web_config() -> &'static WebConfig {
+    static WEB_CONFIG: OnceLock<WebConfig> = OnceLock::new();
+    WEB_CONFIG.get_or_init(|| {
+        WebConfig::load_from_env()
+            .unwrap_or_else(|err| panic!("PANIC WHILE LOADING WEB CONFIG: {}", err))
+    })
+}
+
+pub struct WebConfig {
+    app_url: String,
+    app_name: String,
+    cookie_secure: bool,
+    cookie_same_site: SameSite,
+    cookie_domain: Option<String>,
+    cors_allowed_origins: Vec<String>,
+}
+
+impl WebConfig {
+    fn load_from_env() -> lib_utils::env::Result<Self> {
+        let app_url = lib_utils::env::get_env("APP_URL")
+            .unwrap_or_else(|_| "http://localhost:5173".to_string());
+
+        let cookie_secure = match lib_utils::env::get_parsed_env("COOKIE_SECURE") {
+            Err(lib_utils::env::Error::NotFound) => true,
+            result => result?,
+        };
+        let cookie_same_site = match lib_utils::env::get_env("COOKIE_SAME_SITE") {
+            Err(lib_utils::env::Error::NotFound) => SameSite::Lax,
+            result => parse_same_site(&result?)?,
+        };
+        // Browsers drop `SameSite=None` cookies that are not `Secure`
+        if cookie_same_site == SameSite::None && !cookie_secure {
+            return Err(lib_utils::env::Error::Invalid);
+        }
+
+        let cors_allowed_origins = match lib_utils::env::get_env("CORS_ALLOWED_ORIGINS") {
+            Err(lib_utils::env::Error::NotFound) => vec![app_url.trim_end_matches('/').to_string()],
+            result => result?
+                .split(',')
+                .map(|origin| origin.trim().trim_end_matches('/').to_string())
+                .filter(|origin| !origin.is_empty())
+                .collect(),
+        };
+
+        Ok(Self {
+            app_name: lib_utils::env::get_env("APP_NAME").unwrap_or_else(|_| "App".to_string()),
+            app_url,
+            cookie_secure,
+            cookie_same_site,
+            cookie_domain: lib_utils::env::get_env("COOKIE_DOMAIN").ok(),
+            cors_allowed_origins,
+        })
+    }
+
+    /// Base URL of the frontend, used to build links sent by mail
+    pub fn app_url(&self) -> &str {
+        self.app_url.trim_end_matches('/')
+    }
+
+    /// Human readable name shown e.g. in authenticator apps
+    pub fn app_name(&self) -> &str {
+        &self.app_name
+    }
+
+    /// `COOKIE_SECURE`, on by default. Turn off only for plain-http development hosts
+    pub fn cookie_secure(&self) -> bool {
+        self.cookie_secure
+    }
+
+    /// `COOKIE_SAME_SITE`: `strict`, `lax` (default) or `none`
+    pub fn cookie_same_site(&self) -> SameSite {
+        self.cookie_same_site
+    }
+
+    /// `COOKIE_DOMAIN`, unset means host-only cookies
+    pub fn cookie_domain(&self) -> Option<&str> {
+        self.cookie_domain.as_deref()
+    }
+
+    /// `CORS_ALLOWED_ORIGINS`, comma separated. Defaults to the frontend origin from `APP_URL`
+    pub fn cors_allowed_origins(&self) -> &[String] {
+        &self.cors_allowed_origins
+    }
+}
+
+fn parse_same_site(value: &str) -> lib_utils::env::Result<SameSite> {
+    match value.to_lowercase().as_str() {
+        "strict" => Ok(SameSite::Strict),
+        "lax" => Ok(SameSite::Lax),
+        "none" => Ok(SameSite::None),
+        _ => Err(lib_utils::env::Error::Invalid),
+    }
+}
@@ -29,6 +29,45 @@ pub enum Error {
     #[error("No required data passed")]
     NoRequiredDataPassed,
 
+    #[error("Email address is not verified")]
+    EmailNotVerified,
+
+    #[error("Token is invalid or expired")]
+    InvalidToken,
+
+    #[error("Email is already taken")]
+    EmailAlreadyTaken,
+
+    #[error("Refresh token was already used, please log in again")]
+    TokenReused,
+
+    #[error("CSRF token is missing or invalid")]
+    CsrfMismatch,
+
+    #[error("This action is not allowed while impersonating a user")]
+    ImpersonationReadOnly,
+
+    #[error("Invalid two-factor authentication code")]
+    InvalidTwoFactorCode,
+
+    #[error("You are not a member of this chat")]
+    NotChatMember,
+
+    #[error("You are not a member of this community")]
+    NotCommunityMember,
+
+    #[error("This community only accepts invited members")]
+    CommunityInviteOnly,
+
+    #[error("{0}")]
+    Banned(String),
+
+    #[error("Too many failed attempts, try again in {0} seconds")]
+    TooManyAttempts(u64),
+
+    #[error("An account with this email exists but its email is not verified")]
+    OidcAccountConflict,
+
     #[error(transparent)]
     Ctx(#[from] lib_core::ctx::error::Error),
 
@@ -47,6 +86,12 @@ pub enum Error {
     #[error(transparent)]
     Token(#[from] lib_auth::token::error::Error),
 
+    #[error(transparent)]
+    Totp(#[from] lib_auth::totp::error::Error),
+
+    #[error(transparent)]
+    Oidc(#[from] lib_auth::oidc::error::Error),
+
     #[error(transparent)]
     JsonValidation(#[from] crate::extractors::JsonValidationError),
 
@@ -60,6 +105,10 @@ pub enum Error {
 impl Error {
     pub fn status_code(&self) -> u16 {
         match self {
+            Error::Core(lib_core::error::Error::EntityNotFound) => 404,
+            Error::Core(lib_core::error::Error::AccessControlSystem(
+                lib_core::acs::Error::AccessDenied(_) | lib_core::acs::Error::ScopeDenied(_),
+            )) => 403,
             Error::Core(_) => 400,
             Error::Password(_) => 400,
             Error::Token(_) => 400,
@@ -76,6 +125,22 @@ impl Error {
             Error::CtxExt(_) => 401,
             Error::Ctx(_) => 401,
             Error::NoRequiredDataPassed => 400,
+            Error::EmailNotVerified => 403,
+            Error::InvalidToken => 400,
+            Error::EmailAlreadyTaken => 409,
+            Error::TokenReused => 401,
+            Error::CsrfMismatch => 403,
+            Error::ImpersonationReadOnly => 403,
+            Error::InvalidTwoFactorCode => 401,
+            Error::Totp(_) => 400,
+            Error::NotChatMember => 403,
+            Error::NotCommunityMember => 403,
+            Error::CommunityInviteOnly => 403,
+            Error::Banned(_) => 403,
+            Error::TooManyAttempts(_) => 429,
+            Error::OidcAccountConflict => 409,
+            Error::Oidc(lib_auth::oidc::Error::UnknownProvider(_)) => 404,
+            Error::Oidc(_) => 401,
             Error::BadRequest(_) => 400,
             Error::Validation(_) => 422,
         }
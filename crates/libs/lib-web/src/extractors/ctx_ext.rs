@@ -3,7 +3,6 @@ use axum::http::request::Parts;
 use axum::response::IntoResponse;
 use lib_core::ctx::Ctx;
 use std::future::Future;
-use uuid::Uuid;
 
 use crate::{error::Error, utils::response::ApiResponse};
 
@@ -18,12 +17,12 @@ where
         _: &S,
     ) -> impl Future<Output = core::result::Result<Self, Self::Rejection>> + Send {
         async move {
-            let user_id = parts
+            let ctx = parts
                 .extensions
-                .get::<Option<Uuid>>()
+                .get::<Ctx>()
                 .ok_or(CtxExtError::CannotExtractContext)?;
 
-            Ok(CtxExt(Ctx { user_id: *user_id }))
+            Ok(CtxExt(ctx.clone()))
         }
     }
 }
@@ -1,5 +1,7 @@
+mod client_info;
 mod ctx_ext;
 mod validated_json;
 
+pub use client_info::*;
 pub use ctx_ext::*;
 pub use validated_json::*;
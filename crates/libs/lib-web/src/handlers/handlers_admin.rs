@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use lib_core::model::ModelManager;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::extractors::CtxExt;
+use crate::services::access_service::{AccessDenialDto, AccessExplanationDto, AccessService};
+use crate::utils::response::ApiResponse;
+
+#[derive(Deserialize)]
+pub struct ExplainQuery {
+    pub user: Uuid,
+    /// `kind:id`, e.g. `post:<uuid>`, or just `kind` for a resource the user would create
+    pub resource: String,
+    pub action: String,
+}
+
+#[derive(Deserialize)]
+pub struct DenialsQuery {
+    pub user: Option<Uuid>,
+}
+
+pub async fn explain_access(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Query(params): Query<ExplainQuery>,
+) -> ApiResponse<ExplanationResponse> {
+    const FAILED_MESSAGE: &str = "Failed to explain access decision";
+    info!("Starting explain access by user: {:?}", ctx.user_id);
+
+    let explanation = match AccessService::explain(
+        mm.db(),
+        &ctx,
+        &params.user,
+        &params.resource,
+        &params.action,
+    )
+    .await
+    {
+        Ok(explanation) => {
+            info!(
+                "Access of {} to {} for {}: {}",
+                params.user, params.resource, params.action, explanation.reason
+            );
+            explanation
+        }
+        Err(err) => {
+            error!("Failed to explain access decision: {}", err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    ApiResponse::success(
+        200,
+        "Access decision explained successully",
+        Some(ExplanationResponse { explanation }),
+    )
+}
+
+pub async fn get_access_denials(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Query(params): Query<DenialsQuery>,
+) -> ApiResponse<DenialsResponse> {
+    const FAILED_MESSAGE: &str = "Failed to get access denials";
+    info!("Starting fetching access denials");
+
+    let denials = match AccessService::get_denials(mm.db(), &ctx, params.user).await {
+        Ok(denials) => denials,
+        Err(err) => {
+            error!("Failed to get access denials: {}", err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    info!("Fetching access denials successful");
+    ApiResponse::success(
+        200,
+        "Access denials fetched successully",
+        Some(DenialsResponse { denials }),
+    )
+}
+
+#[derive(Serialize)]
+pub struct ExplanationResponse {
+    explanation: AccessExplanationDto,
+}
+
+#[derive(Serialize)]
+pub struct DenialsResponse {
+    denials: Vec<AccessDenialDto>,
+}
@@ -1,24 +1,40 @@
-use axum::extract::State;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap};
+use axum::response::IntoResponse;
+use axum::Json;
 use axum_extra::extract::CookieJar;
 use lib_core::model::ModelManager;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tracing::error;
+use tracing::{error, info};
+use uuid::Uuid;
 use validator::Validate;
 
-use crate::extractors::{CtxExt, ValidatedJson};
+use crate::extractors::{ClientInfo, CtxExt, ValidatedJson};
+use crate::services::api_token_service::{ApiTokenDto, ApiTokenService};
+use crate::services::oidc_service::OidcService;
+use crate::services::session_service::{SessionDto, SessionService};
+use crate::services::two_factor_service::{TwoFactorService, TwoFactorSetupDto};
 use crate::services::user_service::UserDto;
 use crate::services::user_service::UserService;
-use crate::{error::Error, services::auth_service::AuthService, utils::response::ApiResponse};
+use crate::utils::cookies::REFRESH_COOKIE;
+use crate::utils::token::bearer_token;
+use crate::{
+    error::{Error, Result},
+    services::auth_service::{AuthService, LoginOutcome},
+    utils::response::ApiResponse,
+};
 
 pub async fn register(
     State(mm): State<Arc<ModelManager>>,
     jar: CookieJar,
+    client: ClientInfo,
     ValidatedJson(payload): ValidatedJson<RegisterPayload>,
 ) -> ApiResponse<AuthResponse> {
     match AuthService::register(
         mm,
         jar,
+        client,
         &payload.nickname,
         &payload.email,
         &payload.password,
@@ -38,21 +54,186 @@ pub async fn register(
 pub async fn login(
     State(mm): State<Arc<ModelManager>>,
     jar: CookieJar,
+    client: ClientInfo,
     ValidatedJson(payload): ValidatedJson<LoginPayload>,
+) -> ApiResponse<LoginResponse> {
+    let outcome = AuthService::login(mm, jar, client, &payload.nickname, &payload.password).await;
+    login_response(outcome)
+}
+
+pub async fn get_oidc_providers() -> ApiResponse<OidcProvidersResponse> {
+    ApiResponse::success(
+        200,
+        "Providers fetched successfully",
+        Some(OidcProvidersResponse {
+            providers: OidcService::providers(),
+        }),
+    )
+}
+
+/// Public keys for access tokens in the JWK Set format, so other services can verify them.
+pub async fn get_jwks() -> impl IntoResponse {
+    (
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(lib_auth::token::jwks()),
+    )
+}
+
+pub async fn oidc_authorize(
+    State(mm): State<Arc<ModelManager>>,
+    Path(provider): Path<String>,
+) -> ApiResponse<OidcAuthorizeResponse> {
+    match OidcService::authorization_url(mm.cache(), &provider).await {
+        Ok(authorization_url) => ApiResponse::success(
+            200,
+            "Authorization started",
+            Some(OidcAuthorizeResponse { authorization_url }),
+        ),
+        Err(e) => {
+            error!("Failed to start OIDC login with {}: {:?}", provider, e);
+            ApiResponse::error("Failed to start login", e)
+        }
+    }
+}
+
+pub async fn oidc_callback(
+    State(mm): State<Arc<ModelManager>>,
+    jar: CookieJar,
+    client: ClientInfo,
+    Path(provider): Path<String>,
+    ValidatedJson(payload): ValidatedJson<OidcCallbackPayload>,
+) -> ApiResponse<LoginResponse> {
+    let outcome =
+        AuthService::oidc_login(mm, jar, client, &provider, &payload.code, &payload.state).await;
+    if let Err(e) = &outcome {
+        error!("Failed OIDC login with {}: {:?}", provider, e);
+    }
+    login_response(outcome)
+}
+
+fn login_response(outcome: Result<LoginOutcome>) -> ApiResponse<LoginResponse> {
+    match outcome {
+        Ok(LoginOutcome::Authenticated(user, access_token, new_jar)) => {
+            ApiResponse::success_with_jar(
+                200,
+                "Login successful",
+                Some(LoginResponse::Authenticated(AuthResponse {
+                    user: *user,
+                    access_token,
+                })),
+                new_jar,
+            )
+        }
+        Ok(LoginOutcome::TwoFactorRequired(challenge_token)) => ApiResponse::success(
+            200,
+            "Two-factor authentication required",
+            Some(LoginResponse::TwoFactorRequired(
+                TwoFactorChallengeResponse {
+                    two_factor_required: true,
+                    challenge_token,
+                },
+            )),
+        ),
+        Err(e) => ApiResponse::error("Failed to login", e),
+    }
+}
+
+pub async fn request_magic_link(
+    State(mm): State<Arc<ModelManager>>,
+    client: ClientInfo,
+    ValidatedJson(payload): ValidatedJson<MagicLinkPayload>,
+) -> ApiResponse<()> {
+    match AuthService::request_magic_link(mm, &client, &payload.email).await {
+        Ok(_) => ApiResponse::success(
+            200,
+            "If the email is registered, a sign-in link has been sent",
+            None,
+        ),
+        Err(e) => ApiResponse::error("Failed to send sign-in link", e),
+    }
+}
+
+pub async fn check_magic_link(
+    State(mm): State<Arc<ModelManager>>,
+    Path(token): Path<String>,
+) -> ApiResponse<()> {
+    match AuthService::check_magic_link(mm, &token).await {
+        Ok(_) => ApiResponse::success(200, "Sign-in link is valid, confirm to sign in", None),
+        Err(e) => ApiResponse::error("Failed to check sign-in link", e),
+    }
+}
+
+pub async fn magic_link_login(
+    State(mm): State<Arc<ModelManager>>,
+    jar: CookieJar,
+    client: ClientInfo,
+    Path(token): Path<String>,
+) -> ApiResponse<LoginResponse> {
+    let outcome = AuthService::magic_link_login(mm, jar, client, &token).await;
+    login_response(outcome)
+}
+
+pub async fn verify_two_factor(
+    State(mm): State<Arc<ModelManager>>,
+    jar: CookieJar,
+    client: ClientInfo,
+    ValidatedJson(payload): ValidatedJson<TwoFactorVerifyPayload>,
 ) -> ApiResponse<AuthResponse> {
-    match AuthService::login(mm, jar, &payload.nickname, &payload.password).await {
+    match AuthService::verify_two_factor(mm, jar, client, &payload.challenge_token, &payload.code)
+        .await
+    {
         Ok((user, access_token, new_jar)) => ApiResponse::success_with_jar(
             200,
             "Login successful",
             Some(AuthResponse { user, access_token }),
             new_jar,
         ),
-        Err(e) => ApiResponse::error("Failed to login", e),
+        Err(e) => ApiResponse::error("Failed to verify two-factor code", e),
     }
 }
 
-pub async fn logout(State(mm): State<Arc<ModelManager>>, jar: CookieJar) -> ApiResponse<()> {
-    match AuthService::logout(mm, jar).await {
+pub async fn setup_two_factor(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+) -> ApiResponse<TwoFactorSetupDto> {
+    match TwoFactorService::setup(mm.db(), ctx.user_id).await {
+        Ok(setup) => ApiResponse::success(200, "Two-factor setup started", Some(setup)),
+        Err(e) => ApiResponse::error("Failed to start two-factor setup", e),
+    }
+}
+
+pub async fn confirm_two_factor(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    ValidatedJson(payload): ValidatedJson<TwoFactorCodePayload>,
+) -> ApiResponse<RecoveryCodesResponse> {
+    match TwoFactorService::confirm(mm.db(), ctx.user_id, &payload.code).await {
+        Ok(recovery_codes) => ApiResponse::success(
+            200,
+            "Two-factor authentication enabled",
+            Some(RecoveryCodesResponse { recovery_codes }),
+        ),
+        Err(e) => ApiResponse::error("Failed to enable two-factor authentication", e),
+    }
+}
+
+pub async fn disable_two_factor(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    ValidatedJson(payload): ValidatedJson<TwoFactorDisablePayload>,
+) -> ApiResponse<()> {
+    match TwoFactorService::disable(mm.db(), ctx.user_id, &payload.password, &payload.code).await {
+        Ok(_) => ApiResponse::success(200, "Two-factor authentication disabled", None),
+        Err(e) => ApiResponse::error("Failed to disable two-factor authentication", e),
+    }
+}
+
+pub async fn logout(
+    State(mm): State<Arc<ModelManager>>,
+    headers: HeaderMap,
+    jar: CookieJar,
+) -> ApiResponse<()> {
+    match AuthService::logout(mm, jar, bearer_token(&headers)).await {
         Ok(new_jar) => ApiResponse::success_with_jar(200, "Logout successful", None::<()>, new_jar),
         Err(e) => ApiResponse::error("Failed to logout", e),
     }
@@ -61,8 +242,9 @@ pub async fn logout(State(mm): State<Arc<ModelManager>>, jar: CookieJar) -> ApiR
 pub async fn refresh(
     State(mm): State<Arc<ModelManager>>,
     jar: CookieJar,
+    client: ClientInfo,
 ) -> ApiResponse<AuthResponse> {
-    match AuthService::refresh(mm, jar).await {
+    match AuthService::refresh(mm, jar, client).await {
         Ok((user, access_token, new_jar)) => ApiResponse::success_with_jar(
             200,
             "Refresh successful",
@@ -73,6 +255,233 @@ pub async fn refresh(
     }
 }
 
+pub async fn logout_all(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    jar: CookieJar,
+) -> ApiResponse<()> {
+    match AuthService::logout_all(mm, ctx, jar).await {
+        Ok(new_jar) => {
+            ApiResponse::success_with_jar(200, "Logged out from all sessions", None::<()>, new_jar)
+        }
+        Err(e) => ApiResponse::error("Failed to logout from all sessions", e),
+    }
+}
+
+pub async fn get_sessions(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    jar: CookieJar,
+) -> ApiResponse<SessionsResponse> {
+    const FAILED_MESSAGE: &str = "Failed to fetch sessions";
+    info!("Starting fetching sessions");
+
+    let current_token = jar.get(REFRESH_COOKIE).map(|c| c.value().to_string());
+
+    let sessions =
+        match SessionService::get_all(mm.db(), ctx.user_id, current_token.as_deref()).await {
+            Ok(sessions) => sessions,
+            Err(err) => {
+                error!("Failed to fetch sessions: {:?}", err);
+                return ApiResponse::error(FAILED_MESSAGE, err);
+            }
+        };
+
+    info!("Fetching sessions successful");
+    ApiResponse::success(
+        200,
+        "Sessions fetched successfully",
+        Some(SessionsResponse { sessions }),
+    )
+}
+
+pub async fn delete_session(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path(id): Path<Uuid>,
+) -> ApiResponse<()> {
+    const FAILED_MESSAGE: &str = "Failed to delete session";
+    info!("Starting delete session");
+
+    if let Err(err) = SessionService::delete(mm.db(), ctx.user_id, &id).await {
+        error!("Failed to delete session: {:?}", err);
+        return ApiResponse::error(FAILED_MESSAGE, err);
+    }
+
+    info!("Deleting session successful");
+    ApiResponse::success(200, "Session deleted successfully", None)
+}
+
+pub async fn get_api_tokens(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+) -> ApiResponse<ApiTokensResponse> {
+    const FAILED_MESSAGE: &str = "Failed to fetch API tokens";
+    info!("Starting fetching API tokens");
+
+    let tokens = match ApiTokenService::get_all(mm.db(), ctx.user_id).await {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            error!("Failed to fetch API tokens: {:?}", err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    info!("Fetching API tokens successful");
+    ApiResponse::success(
+        200,
+        "API tokens fetched successfully",
+        Some(ApiTokensResponse { tokens }),
+    )
+}
+
+pub async fn create_api_token(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    ValidatedJson(payload): ValidatedJson<ApiTokenCreatePayload>,
+) -> ApiResponse<ApiTokenCreatedResponse> {
+    const FAILED_MESSAGE: &str = "Failed to create API token";
+    info!("Starting create API token");
+
+    let (api_token, token) = match ApiTokenService::create(
+        mm.db(),
+        ctx.user_id,
+        &payload.name,
+        &payload.scopes,
+        payload.expires_in_days,
+    )
+    .await
+    {
+        Ok(created) => created,
+        Err(err) => {
+            error!("Failed to create API token: {:?}", err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    info!("Creating API token successful");
+    ApiResponse::success(
+        201,
+        "API token created, copy it now as it will not be shown again",
+        Some(ApiTokenCreatedResponse { api_token, token }),
+    )
+}
+
+pub async fn delete_api_token(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path(id): Path<Uuid>,
+) -> ApiResponse<()> {
+    const FAILED_MESSAGE: &str = "Failed to delete API token";
+    info!("Starting delete API token");
+
+    if let Err(err) = ApiTokenService::delete(mm.db(), ctx.user_id, &id).await {
+        error!("Failed to delete API token: {:?}", err);
+        return ApiResponse::error(FAILED_MESSAGE, err);
+    }
+
+    info!("Deleting API token successful");
+    ApiResponse::success(200, "API token deleted successfully", None)
+}
+
+pub async fn verify_email(
+    State(mm): State<Arc<ModelManager>>,
+    ValidatedJson(payload): ValidatedJson<VerifyEmailPayload>,
+) -> ApiResponse<UserResponse> {
+    match AuthService::verify_email(mm, &payload.token).await {
+        Ok(user) => ApiResponse::success(
+            200,
+            "Email verified successfully",
+            Some(UserResponse { user }),
+        ),
+        Err(e) => ApiResponse::error("Failed to verify email", e),
+    }
+}
+
+pub async fn resend_email_verification(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+) -> ApiResponse<()> {
+    match AuthService::resend_email_verification(mm, ctx).await {
+        Ok(_) => ApiResponse::success(200, "Verification email sent", None),
+        Err(e) => ApiResponse::error("Failed to send verification email", e),
+    }
+}
+
+pub async fn forgot_password(
+    State(mm): State<Arc<ModelManager>>,
+    ValidatedJson(payload): ValidatedJson<ForgotPasswordPayload>,
+) -> ApiResponse<()> {
+    match AuthService::forgot_password(mm, &payload.email).await {
+        Ok(_) => ApiResponse::success(
+            200,
+            "If the email is registered, a reset link has been sent",
+            None,
+        ),
+        Err(e) => ApiResponse::error("Failed to request password reset", e),
+    }
+}
+
+pub async fn reset_password(
+    State(mm): State<Arc<ModelManager>>,
+    ValidatedJson(payload): ValidatedJson<ResetPasswordPayload>,
+) -> ApiResponse<()> {
+    match AuthService::reset_password(mm, &payload.token, &payload.new_password).await {
+        Ok(_) => ApiResponse::success(200, "Password reset successfully", None),
+        Err(e) => ApiResponse::error("Failed to reset password", e),
+    }
+}
+
+pub async fn change_password(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    jar: CookieJar,
+    ValidatedJson(payload): ValidatedJson<ChangePasswordPayload>,
+) -> ApiResponse<()> {
+    match AuthService::change_password(
+        mm,
+        ctx,
+        jar,
+        &payload.current_password,
+        &payload.new_password,
+    )
+    .await
+    {
+        Ok(new_jar) => ApiResponse::success_with_jar(
+            200,
+            "Password changed successfully, please log in again",
+            None::<()>,
+            new_jar,
+        ),
+        Err(e) => ApiResponse::error("Failed to change password", e),
+    }
+}
+
+pub async fn request_email_change(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    ValidatedJson(payload): ValidatedJson<EmailChangePayload>,
+) -> ApiResponse<()> {
+    match AuthService::request_email_change(mm, ctx, &payload.new_email, &payload.password).await {
+        Ok(_) => ApiResponse::success(200, "Confirmation email sent to the new address", None),
+        Err(e) => ApiResponse::error("Failed to request email change", e),
+    }
+}
+
+pub async fn confirm_email_change(
+    State(mm): State<Arc<ModelManager>>,
+    ValidatedJson(payload): ValidatedJson<VerifyEmailPayload>,
+) -> ApiResponse<UserResponse> {
+    match AuthService::confirm_email_change(mm, &payload.token).await {
+        Ok(user) => ApiResponse::success(
+            200,
+            "Email changed successfully",
+            Some(UserResponse { user }),
+        ),
+        Err(e) => ApiResponse::error("Failed to change email", e),
+    }
+}
+
 pub async fn auth_me(
     State(mm): State<Arc<ModelManager>>,
     CtxExt(ctx): CtxExt,
@@ -112,22 +521,55 @@ pub struct RegisterPayload {
     #[validate(email(message = "Invalid email format"))]
     email: String,
 
-    #[validate(length(min = 6, message = "Password must be at least 6 characters long"))]
+    #[validate(length(min = 1, message = "Password is required"))]
     password: String,
 }
 
 #[derive(Debug, Deserialize, Validate)]
-pub struct ResetPasswordPayload {
+pub struct ForgotPasswordPayload {
     #[validate(email(message = "Invalid email format"))]
     email: String,
+}
 
-    #[validate(length(min = 6, message = "Password must be at least 6 characters long"))]
-    old_password: String,
+#[derive(Debug, Deserialize, Validate)]
+pub struct MagicLinkPayload {
+    #[validate(email(message = "Invalid email format"))]
+    email: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ResetPasswordPayload {
+    #[validate(length(min = 1, message = "Token is required"))]
+    token: String,
 
-    #[validate(length(min = 6, message = "Password must be at least 6 characters long"))]
+    #[validate(length(min = 1, message = "Password is required"))]
     new_password: String,
 }
 
+#[derive(Debug, Deserialize, Validate)]
+pub struct ChangePasswordPayload {
+    #[validate(length(min = 1, message = "Current password is required"))]
+    current_password: String,
+
+    #[validate(length(min = 1, message = "Password is required"))]
+    new_password: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct VerifyEmailPayload {
+    #[validate(length(min = 1, message = "Token is required"))]
+    token: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct EmailChangePayload {
+    #[validate(email(message = "Invalid email format"))]
+    new_email: String,
+
+    #[validate(length(min = 1, message = "Password is required"))]
+    password: String,
+}
+
 #[derive(Debug, Deserialize, Validate)]
 pub struct LoginPayload {
     #[validate(length(
@@ -137,17 +579,114 @@ pub struct LoginPayload {
     ))]
     nickname: String,
 
-    #[validate(length(min = 6, message = "Password must be at least 6 characters long"))]
+    #[validate(length(min = 1, message = "Password is required"))]
     password: String,
 }
 
+#[derive(Debug, Deserialize, Validate)]
+pub struct OidcCallbackPayload {
+    #[validate(length(min = 1, message = "Code is required"))]
+    code: String,
+
+    #[validate(length(min = 1, message = "State is required"))]
+    state: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TwoFactorVerifyPayload {
+    #[validate(length(min = 1, message = "Challenge token is required"))]
+    challenge_token: String,
+
+    #[validate(length(min = 1, message = "Code is required"))]
+    code: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TwoFactorCodePayload {
+    #[validate(length(equal = 6, message = "Code must be 6 digits long"))]
+    code: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TwoFactorDisablePayload {
+    #[validate(length(min = 1, message = "Password is required"))]
+    password: String,
+
+    #[validate(length(min = 1, message = "Code is required"))]
+    code: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ApiTokenCreatePayload {
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "Name must contain at least 1 characters and no more than 64"
+    ))]
+    name: String,
+
+    #[validate(length(min = 1, message = "At least one scope is required"))]
+    scopes: Vec<String>,
+
+    #[validate(range(
+        min = 1,
+        max = 365,
+        message = "Expiration must be between 1 and 365 days"
+    ))]
+    expires_in_days: Option<i64>,
+}
+
 #[derive(Serialize)]
 pub struct AuthResponse {
     user: UserDto,
     access_token: String,
 }
 
+#[derive(Serialize)]
+pub struct TwoFactorChallengeResponse {
+    two_factor_required: bool,
+    challenge_token: String,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Authenticated(AuthResponse),
+    TwoFactorRequired(TwoFactorChallengeResponse),
+}
+
+#[derive(Serialize)]
+pub struct OidcProvidersResponse {
+    providers: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct OidcAuthorizeResponse {
+    authorization_url: String,
+}
+
+#[derive(Serialize)]
+pub struct RecoveryCodesResponse {
+    recovery_codes: Vec<String>,
+}
+
 #[derive(Serialize)]
 pub struct UserResponse {
     user: UserDto,
 }
+
+#[derive(Serialize)]
+pub struct SessionsResponse {
+    sessions: Vec<SessionDto>,
+}
+
+#[derive(Serialize)]
+pub struct ApiTokensResponse {
+    tokens: Vec<ApiTokenDto>,
+}
+
+#[derive(Serialize)]
+pub struct ApiTokenCreatedResponse {
+    api_token: ApiTokenDto,
+    token: String,
+}
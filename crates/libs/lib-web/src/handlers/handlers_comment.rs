@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use axum::extract::{Path, Query, State};
 use futures::SinkExt as _;
+use lib_core::db::pagination::{Cursor, Page, PageRequest};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
@@ -22,17 +23,17 @@ pub async fn get_post_comments(
     State(state): State<Arc<AppState>>,
     CtxExt(ctx): CtxExt,
     Path(post_id): Path<Uuid>,
-    Query(_): Query<CommentQuery>,
+    Query(page): Query<PageRequest>,
 ) -> ApiResponse<CommentsResponse> {
     const FAILED_MESSAGE: &str = "Failed to fetch comments";
     info!("Starting fetch comments");
 
     let comments =
-        match CommentService::get_many_by_post_id(state.mm.db(), ctx.user_id, &post_id).await {
+        match CommentService::get_many_by_post_id(state.mm.db(), &ctx, &post_id, &page).await {
             Ok(comments) => {
                 info!(
                     "Successfully fetched {} comments for post: {}",
-                    comments.len(),
+                    comments.items.len(),
                     post_id
                 );
                 comments
@@ -43,7 +44,10 @@ pub async fn get_post_comments(
             }
         };
 
-    let comments_response = CommentsResponse { comments };
+    let comments_response = CommentsResponse {
+        comments: comments.items,
+        next_cursor: comments.next_cursor,
+    };
 
     info!("Comments fetched successfully for post: {}", post_id);
     ApiResponse::success(
@@ -57,17 +61,18 @@ pub async fn get_comments(
     State(state): State<Arc<AppState>>,
     CtxExt(ctx): CtxExt,
     Query(params): Query<CommentQuery>,
+    Query(page): Query<PageRequest>,
 ) -> ApiResponse<CommentsResponse> {
     const FAILED_MESSAGE: &str = "Failed to fetch comments";
     info!("Starting fetch comments");
 
     let comments = if let Some(user_id) = params.user_id {
         // Получение комментариев конкретного пользователя
-        match CommentService::get_many_by_user_id(state.mm.db(), ctx.user_id, &user_id).await {
+        match CommentService::get_many_by_user_id(state.mm.db(), &ctx, &user_id, &page).await {
             Ok(comments) => {
                 info!(
                     "Successfully fetched {} comments for user: {}",
-                    comments.len(),
+                    comments.items.len(),
                     user_id
                 );
                 comments
@@ -79,11 +84,11 @@ pub async fn get_comments(
         }
     } else if let Some(post_id) = params.post_id {
         // Получение комментариев для конкретного поста
-        match CommentService::get_many_by_post_id(state.mm.db(), ctx.user_id, &post_id).await {
+        match CommentService::get_many_by_post_id(state.mm.db(), &ctx, &post_id, &page).await {
             Ok(comments) => {
                 info!(
                     "Successfully fetched {} comments for post: {}",
-                    comments.len(),
+                    comments.items.len(),
                     post_id
                 );
                 comments
@@ -95,10 +100,16 @@ pub async fn get_comments(
         }
     } else {
         error!("Null query params");
-        Vec::<CommentDto>::new()
+        Page {
+            items: Vec::<CommentDto>::new(),
+            next_cursor: None,
+        }
     };
 
-    let comments_response = CommentsResponse { comments };
+    let comments_response = CommentsResponse {
+        comments: comments.items,
+        next_cursor: comments.next_cursor,
+    };
 
     info!("Comments fetched successfully");
     ApiResponse::success(
@@ -116,7 +127,7 @@ pub async fn get_comment(
     const FAILED_MESSAGE: &str = "Failed to fetch comment";
     info!("Starting fetch comment by id: {}", id);
 
-    let comment = match CommentService::get_by_id(state.mm.db(), ctx.user_id, &id).await {
+    let comment = match CommentService::get_by_id(state.mm.db(), &ctx, &id).await {
         Ok(comment) => {
             info!("Comment found: {}", id);
             comment
@@ -142,19 +153,18 @@ pub async fn get_comment_thread(
     info!("Starting to fetch comment thread: {}", comment_id);
 
     // 1. Получаем исходный комментарий
-    let root_comment =
-        match CommentService::get_by_id(state.mm.db(), ctx.user_id, &comment_id).await {
-            Ok(comment) => comment,
-            Err(err) => {
-                error!("Failed to get root comment {}: {:?}", comment_id, err);
-                return ApiResponse::error(FAILED_MESSAGE, err);
-            }
-        };
+    let root_comment = match CommentService::get_by_id(state.mm.db(), &ctx, &comment_id).await {
+        Ok(comment) => comment,
+        Err(err) => {
+            error!("Failed to get root comment {}: {:?}", comment_id, err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
 
     // 2. Получаем все комментарии, связанные с этим постом (можно оптимизировать под потомков этого комментария)
-    let all_comments = match CommentService::get_many_by_post_id(
+    let all_comments = match CommentService::get_all_by_post_id(
         state.mm.db(),
-        ctx.user_id,
+        &ctx,
         &root_comment.post_id,
     )
     .await
@@ -174,7 +184,10 @@ pub async fn get_comment_thread(
     collect_thread(&mut thread, &root_comment.id, &all_comments);
     thread.insert(0, root_comment); // Добавляем корневой комментарий первым
 
-    let response = CommentsResponse { comments: thread };
+    let response = CommentsResponse {
+        comments: thread,
+        next_cursor: None,
+    };
 
     info!(
         "Fetched thread with {} comments for root {}",
@@ -211,7 +224,7 @@ pub async fn create_comment(
 
     let comment = match CommentService::create(
         state.mm.db(),
-        ctx.user_id,
+        &ctx,
         &payload.post_id,
         payload.parent_comment_id,
         &payload.content,
@@ -221,17 +234,16 @@ pub async fn create_comment(
         Ok(comment) => {
             info!("Comment created: {}", comment.id);
 
-            let post =
-                match PostService::get_by_id(state.mm.db(), ctx.user_id, &comment.post_id).await {
-                    Ok(post) => post,
-                    Err(err) => {
-                        error!(
-                            "Failed to fetch post {} for comment {}: {}",
-                            comment.post_id, comment.id, err
-                        );
-                        return ApiResponse::error(FAILED_MESSAGE, err);
-                    }
-                };
+            let post = match PostService::get_by_id(state.mm.db(), &ctx, &comment.post_id).await {
+                Ok(post) => post,
+                Err(err) => {
+                    error!(
+                        "Failed to fetch post {} for comment {}: {}",
+                        comment.post_id, comment.id, err
+                    );
+                    return ApiResponse::error(FAILED_MESSAGE, err);
+                }
+            };
 
             let mut conns = state.notification_conns.lock().await;
             if let Some(sender) = conns.get_mut(&post.user_id) {
@@ -295,17 +307,16 @@ pub async fn update_comment(
     const FAILED_MESSAGE: &str = "Failed to update comment";
     info!("Starting update comment by user: {:?}", ctx.user_id);
 
-    let comment =
-        match CommentService::update(state.mm.db(), ctx.user_id, &id, payload.content).await {
-            Ok(comment) => {
-                info!("Comment updated: {}", comment.id);
-                comment
-            }
-            Err(err) => {
-                error!("Failed to update comment by user: {:?}", ctx.user_id);
-                return ApiResponse::error(FAILED_MESSAGE, err);
-            }
-        };
+    let comment = match CommentService::update(state.mm.db(), &ctx, &id, payload.content).await {
+        Ok(comment) => {
+            info!("Comment updated: {}", comment.id);
+            comment
+        }
+        Err(err) => {
+            error!("Failed to update comment by user: {:?}", ctx.user_id);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
 
     let comment_response = CommentResponse { comment };
 
@@ -321,7 +332,7 @@ pub async fn delete_comment(
     const FAILED_MESSAGE: &str = "Failed to delete comment";
     info!("Starting delete comment by user: {:?}", ctx.user_id);
 
-    match CommentService::delete(state.mm.db(), ctx.user_id, &id).await {
+    match CommentService::delete(state.mm.db(), &ctx, &id).await {
         Ok(_) => {
             info!("Comment deleted: {}", id);
         }
@@ -363,4 +374,5 @@ pub struct CommentResponse {
 #[derive(Serialize)]
 pub struct CommentsResponse {
     pub comments: Vec<CommentDto>,
+    pub next_cursor: Option<Cursor>,
 }
@@ -2,10 +2,15 @@ use std::sync::Arc;
 
 use crate::{
     extractors::{CtxExt, ValidatedJson},
-    services::community_service::{CommunityDto, CommunityService},
+    services::{
+        community_join_request_service::{CommunityJoinRequestService, JoinRequestDto},
+        community_member_service::{CommunityMemberDto, CommunityMemberService},
+        community_service::{CommunityDto, CommunityService},
+    },
     utils::response::ApiResponse,
 };
 use axum::extract::{Path, Query, State};
+use lib_core::db::pagination::{Cursor, PageRequest};
 use lib_core::model::ModelManager;
 use serde::{Deserialize, Serialize};
 use tracing::{error, info, instrument};
@@ -17,12 +22,13 @@ pub async fn get_communities(
     State(mm): State<Arc<ModelManager>>,
     CtxExt(ctx): CtxExt,
     Query(params): Query<CommunityParam>,
+    Query(page): Query<PageRequest>,
 ) -> ApiResponse<CommunitiesResponse> {
     const FAILED_MESSAGE: &str = "Failed to fetch communities";
 
     let communities = match params.user_id {
         Some(user_id) => {
-            match CommunityService::get_many_by_user_id(mm.db(), ctx.user_id, &user_id).await {
+            match CommunityService::get_many_by_user_id(mm.db(), &ctx, &user_id, &page).await {
                 Ok(community) => {
                     info!("Communities fetched");
                     community
@@ -33,7 +39,7 @@ pub async fn get_communities(
                 }
             }
         }
-        None => match CommunityService::get_many(mm.db(), ctx.user_id).await {
+        None => match CommunityService::get_many(mm.db(), &ctx, &page).await {
             Ok(community) => {
                 info!("Communities fetched");
                 community
@@ -45,7 +51,10 @@ pub async fn get_communities(
         },
     };
 
-    let community_response = CommunitiesResponse { communities };
+    let community_response = CommunitiesResponse {
+        communities: communities.items,
+        next_cursor: communities.next_cursor,
+    };
 
     info!("Communities fetched successfully");
     ApiResponse::success(
@@ -63,7 +72,7 @@ pub async fn get(
 ) -> ApiResponse<CommunityResponse> {
     const FAILED_MESSAGE: &str = "Failed to fetch community";
 
-    let community = match CommunityService::get_by_name(mm.db(), ctx.user_id, &name).await {
+    let community = match CommunityService::get_by_name(mm.db(), &ctx, &name).await {
         Ok(community) => {
             info!("Community found: {}", community.name);
             community
@@ -98,10 +107,11 @@ pub async fn create(
 
     let community = match CommunityService::create(
         mm.db(),
-        ctx.user_id,
+        &ctx,
         &payload.name,
         &payload.description,
         &payload.is_private,
+        &payload.is_invite_only,
     )
     .await
     {
@@ -136,11 +146,12 @@ pub async fn update(
 
     let community = match CommunityService::update_by_name(
         mm.db(),
-        ctx.user_id,
+        &ctx,
         &name,
         payload.name,
         payload.description,
         payload.is_private,
+        payload.is_invite_only,
     )
     .await
     {
@@ -175,7 +186,7 @@ pub async fn delete(
 ) -> ApiResponse<()> {
     const FAILED_MESSAGE: &str = "Failed to delete community";
 
-    match CommunityService::delete_by_name(mm.db(), ctx.user_id, &name).await {
+    match CommunityService::delete_by_name(mm.db(), &ctx, &name).await {
         Ok(_) => {
             info!("Community deleted successfully");
             return ApiResponse::success(200, "Community deleted successfully", None);
@@ -187,6 +198,290 @@ pub async fn delete(
     }
 }
 
+#[instrument(skip(mm))]
+pub async fn get_moderators(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path(id): Path<Uuid>,
+) -> ApiResponse<ModeratorsResponse> {
+    const FAILED_MESSAGE: &str = "Failed to fetch community moderators";
+
+    let moderators = match CommunityMemberService::get_moderators(mm.db(), &ctx, &id).await {
+        Ok(moderators) => {
+            info!("Moderators of community {} fetched", id);
+            moderators
+        }
+        Err(err) => {
+            error!("Failed to fetch moderators of community {}: {}", id, err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    let moderators_response = ModeratorsResponse { moderators };
+
+    info!("Community moderators fetched successfully");
+    ApiResponse::success(
+        200,
+        "Community moderators fetched successfully",
+        Some(moderators_response),
+    )
+}
+
+#[instrument(skip(mm))]
+pub async fn appoint_moderator(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path(id): Path<Uuid>,
+    ValidatedJson(payload): ValidatedJson<AppointModeratorPayload>,
+) -> ApiResponse<ModeratorResponse> {
+    const FAILED_MESSAGE: &str = "Failed to appoint moderator";
+    info!("Starting appoint moderator by user: {:?}", ctx.user_id);
+
+    let moderator =
+        match CommunityMemberService::appoint_moderator(mm.db(), &ctx, &id, &payload.user_id).await
+        {
+            Ok(moderator) => {
+                info!("User {} appointed as moderator", moderator.user.id);
+                moderator
+            }
+            Err(err) => {
+                error!("Failed to appoint moderator in community {}: {}", id, err);
+                return ApiResponse::error(FAILED_MESSAGE, err);
+            }
+        };
+
+    let moderator_response = ModeratorResponse { moderator };
+
+    info!("Moderator appointed successully in community: {}", id);
+    ApiResponse::success(
+        201,
+        "Moderator appointed successully",
+        Some(moderator_response),
+    )
+}
+
+#[instrument(skip(mm))]
+pub async fn remove_moderator(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+) -> ApiResponse<()> {
+    const FAILED_MESSAGE: &str = "Failed to remove moderator";
+    info!("Starting remove moderator by user: {:?}", ctx.user_id);
+
+    match CommunityMemberService::remove_moderator(mm.db(), &ctx, &id, &user_id).await {
+        Ok(_) => {
+            info!("Moderator {} removed from community {}", user_id, id);
+            ApiResponse::success(200, "Moderator removed successully", None)
+        }
+        Err(err) => {
+            error!("Failed to remove moderator from community {}: {}", id, err);
+            ApiResponse::error(FAILED_MESSAGE, err)
+        }
+    }
+}
+
+#[instrument(skip(mm))]
+pub async fn get_members(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path(id): Path<Uuid>,
+) -> ApiResponse<MembersResponse> {
+    const FAILED_MESSAGE: &str = "Failed to fetch community members";
+
+    let members = match CommunityMemberService::get_members(mm.db(), &ctx, &id).await {
+        Ok(members) => {
+            info!("Members of community {} fetched", id);
+            members
+        }
+        Err(err) => {
+            error!("Failed to fetch members of community {}: {}", id, err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    let members_response = MembersResponse { members };
+
+    info!("Community members fetched successfully");
+    ApiResponse::success(
+        200,
+        "Community members fetched successfully",
+        Some(members_response),
+    )
+}
+
+#[instrument(skip(mm))]
+pub async fn add_member(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path(id): Path<Uuid>,
+    ValidatedJson(payload): ValidatedJson<AddMemberPayload>,
+) -> ApiResponse<MemberResponse> {
+    const FAILED_MESSAGE: &str = "Failed to add member";
+    info!("Starting add member by user: {:?}", ctx.user_id);
+
+    let member =
+        match CommunityMemberService::add_member(mm.db(), &ctx, &id, &payload.user_id).await {
+            Ok(member) => {
+                info!("User {} added to community {}", member.user.id, id);
+                member
+            }
+            Err(err) => {
+                error!("Failed to add member to community {}: {}", id, err);
+                return ApiResponse::error(FAILED_MESSAGE, err);
+            }
+        };
+
+    let member_response = MemberResponse { member };
+
+    info!("Member added successully to community: {}", id);
+    ApiResponse::success(201, "Member added successully", Some(member_response))
+}
+
+#[instrument(skip(mm))]
+pub async fn remove_member(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+) -> ApiResponse<()> {
+    const FAILED_MESSAGE: &str = "Failed to remove member";
+    info!("Starting remove member by user: {:?}", ctx.user_id);
+
+    match CommunityMemberService::remove_member(mm.db(), &ctx, &id, &user_id).await {
+        Ok(_) => {
+            info!("Member {} removed from community {}", user_id, id);
+            ApiResponse::success(200, "Member removed successully", None)
+        }
+        Err(err) => {
+            error!("Failed to remove member from community {}: {}", id, err);
+            ApiResponse::error(FAILED_MESSAGE, err)
+        }
+    }
+}
+
+#[instrument(skip(mm))]
+pub async fn create_join_request(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path(id): Path<Uuid>,
+    ValidatedJson(payload): ValidatedJson<JoinRequestPayload>,
+) -> ApiResponse<JoinRequestResponse> {
+    const FAILED_MESSAGE: &str = "Failed to send join request";
+    info!("Starting join request by user: {:?}", ctx.user_id);
+
+    let join_request =
+        match CommunityJoinRequestService::create(mm.db(), &ctx, &id, payload.message).await {
+            Ok(join_request) => {
+                info!("Join request {} created", join_request.id);
+                join_request
+            }
+            Err(err) => {
+                error!("Failed to create join request to community {}: {}", id, err);
+                return ApiResponse::error(FAILED_MESSAGE, err);
+            }
+        };
+
+    let join_request_response = JoinRequestResponse { join_request };
+
+    info!("Join request sent successully to community: {}", id);
+    ApiResponse::success(
+        201,
+        "Join request sent successully",
+        Some(join_request_response),
+    )
+}
+
+#[instrument(skip(mm))]
+pub async fn get_join_requests(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path(id): Path<Uuid>,
+) -> ApiResponse<JoinRequestsResponse> {
+    const FAILED_MESSAGE: &str = "Failed to fetch join requests";
+
+    let join_requests = match CommunityJoinRequestService::get_pending(mm.db(), &ctx, &id).await {
+        Ok(join_requests) => {
+            info!("Join requests of community {} fetched", id);
+            join_requests
+        }
+        Err(err) => {
+            error!("Failed to fetch join requests of community {}: {}", id, err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    let join_requests_response = JoinRequestsResponse { join_requests };
+
+    info!("Join requests fetched successfully");
+    ApiResponse::success(
+        200,
+        "Join requests fetched successfully",
+        Some(join_requests_response),
+    )
+}
+
+#[instrument(skip(mm))]
+pub async fn approve_join_request(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path((id, request_id)): Path<(Uuid, Uuid)>,
+) -> ApiResponse<JoinRequestResponse> {
+    const FAILED_MESSAGE: &str = "Failed to approve join request";
+    info!("Starting approve join request by user: {:?}", ctx.user_id);
+
+    let join_request =
+        match CommunityJoinRequestService::approve(mm.db(), &ctx, &id, &request_id).await {
+            Ok(join_request) => {
+                info!("Join request {} approved", join_request.id);
+                join_request
+            }
+            Err(err) => {
+                error!("Failed to approve join request {}: {}", request_id, err);
+                return ApiResponse::error(FAILED_MESSAGE, err);
+            }
+        };
+
+    let join_request_response = JoinRequestResponse { join_request };
+
+    info!("Join request approved successully in community: {}", id);
+    ApiResponse::success(
+        200,
+        "Join request approved successully",
+        Some(join_request_response),
+    )
+}
+
+#[instrument(skip(mm))]
+pub async fn deny_join_request(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path((id, request_id)): Path<(Uuid, Uuid)>,
+) -> ApiResponse<JoinRequestResponse> {
+    const FAILED_MESSAGE: &str = "Failed to deny join request";
+    info!("Starting deny join request by user: {:?}", ctx.user_id);
+
+    let join_request =
+        match CommunityJoinRequestService::deny(mm.db(), &ctx, &id, &request_id).await {
+            Ok(join_request) => {
+                info!("Join request {} denied", join_request.id);
+                join_request
+            }
+            Err(err) => {
+                error!("Failed to deny join request {}: {}", request_id, err);
+                return ApiResponse::error(FAILED_MESSAGE, err);
+            }
+        };
+
+    let join_request_response = JoinRequestResponse { join_request };
+
+    info!("Join request denied successully in community: {}", id);
+    ApiResponse::success(
+        200,
+        "Join request denied successully",
+        Some(join_request_response),
+    )
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CommunityParam {
     user_id: Option<Uuid>,
@@ -197,6 +492,9 @@ pub struct CreatePayload {
     name: String,
     description: String,
     is_private: bool,
+    /// Private communities that only accept members added by their moderators
+    #[serde(default)]
+    is_invite_only: bool,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -204,6 +502,7 @@ pub struct UpdateCommunityPayload {
     name: Option<String>,
     description: Option<String>,
     is_private: Option<bool>,
+    is_invite_only: Option<bool>,
 }
 
 #[derive(Serialize)]
@@ -214,4 +513,51 @@ pub struct CommunityResponse {
 #[derive(Serialize)]
 pub struct CommunitiesResponse {
     communities: Vec<CommunityDto>,
+    next_cursor: Option<Cursor>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AppointModeratorPayload {
+    user_id: Uuid,
+}
+
+#[derive(Serialize)]
+pub struct ModeratorResponse {
+    moderator: CommunityMemberDto,
+}
+
+#[derive(Serialize)]
+pub struct ModeratorsResponse {
+    moderators: Vec<CommunityMemberDto>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AddMemberPayload {
+    user_id: Uuid,
+}
+
+#[derive(Serialize)]
+pub struct MemberResponse {
+    member: CommunityMemberDto,
+}
+
+#[derive(Serialize)]
+pub struct MembersResponse {
+    members: Vec<CommunityMemberDto>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct JoinRequestPayload {
+    #[validate(length(max = 500, message = "Message must be at most 500 characters"))]
+    message: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct JoinRequestResponse {
+    join_request: JoinRequestDto,
+}
+
+#[derive(Serialize)]
+pub struct JoinRequestsResponse {
+    join_requests: Vec<JoinRequestDto>,
 }
@@ -16,7 +16,7 @@ pub async fn follow(
 ) -> ApiResponse<()> {
     const FAILED_MESSAGE: &str = "Faield to follow";
 
-    match FollowService::follow(&mm.db(), ctx.user_id, &community_id).await {
+    match FollowService::follow(&mm.db(), &ctx, &community_id).await {
         Ok(_) => {
             // info!(
             //     "User {} successfully followed to entity {}",
@@ -40,7 +40,7 @@ pub async fn unfollow(
 ) -> ApiResponse<()> {
     const FAILED_MESSAGE: &str = "Faield to unfollow";
 
-    match FollowService::unfollow(&mm.db(), ctx.user_id, &community_id).await {
+    match FollowService::unfollow(&mm.db(), &ctx, &community_id).await {
         Ok(_) => {
             // info!(
             //     "User {} successfully unfollowed from entity {}",
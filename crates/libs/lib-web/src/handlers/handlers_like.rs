@@ -47,11 +47,11 @@ pub async fn like(
     };
 
     if let Some(post_id) = params.post_id {
-        let _ = match LikeService::like_post(&state.mm.db(), ctx.user_id, &post_id, 1).await {
+        let _ = match LikeService::like_post(&state.mm.db(), &ctx, &post_id, 1).await {
             Ok(_) => {
                 info!("Post liked successfully");
 
-                match PostService::get_by_id(state.mm.db(), ctx.user_id, &post_id).await {
+                match PostService::get_by_id(state.mm.db(), &ctx.unscoped(), &post_id).await {
                     Ok(post) => {
                         debug!("Retrieved post {} for like notification", post.id);
 
@@ -128,11 +128,11 @@ pub async fn like(
             }
         };
     } else if let Some(comment_id) = params.comment_id {
-        let _ = match LikeService::like_comment(&state.mm.db(), ctx.user_id, &comment_id, 1).await {
+        let _ = match LikeService::like_comment(&state.mm.db(), &ctx, &comment_id, 1).await {
             Ok(_) => {
                 info!("Comment liked successfully");
 
-                match CommentService::get_by_id(state.mm.db(), ctx.user_id, &comment_id).await {
+                match CommentService::get_by_id(state.mm.db(), &ctx.unscoped(), &comment_id).await {
                     Ok(comment) => {
                         debug!("Retrieved comment {} for like notification", comment.id);
 
@@ -225,7 +225,7 @@ pub async fn dislike(
     info!("Starting dislike");
 
     if let Some(post_id) = params.post_id {
-        let _ = match LikeService::like_post(&state.mm.db(), ctx.user_id, &post_id, -1).await {
+        let _ = match LikeService::like_post(&state.mm.db(), &ctx, &post_id, -1).await {
             Ok(_) => {
                 info!("Post disliked successfully");
                 return ApiResponse::success(201, "Post disliked successfully", None);
@@ -236,8 +236,7 @@ pub async fn dislike(
             }
         };
     } else if let Some(comment_id) = params.comment_id {
-        let _ = match LikeService::like_comment(&state.mm.db(), ctx.user_id, &comment_id, -1).await
-        {
+        let _ = match LikeService::like_comment(&state.mm.db(), &ctx, &comment_id, -1).await {
             Ok(_) => {
                 info!("Comment disliked successfully");
                 return ApiResponse::success(201, "Comment disliked successfully", None);
@@ -262,7 +261,7 @@ pub async fn unlike(
     info!("Starting unlike");
 
     if let Some(post_id) = params.post_id {
-        let _ = match LikeService::unlike_post(&state.mm.db(), ctx.user_id, &post_id).await {
+        let _ = match LikeService::unlike_post(&state.mm.db(), &ctx, &post_id).await {
             Ok(_) => {
                 info!("Post unliked successfully");
                 return ApiResponse::success(201, "Post unliked successfully", None);
@@ -273,7 +272,7 @@ pub async fn unlike(
             }
         };
     } else if let Some(comment_id) = params.comment_id {
-        let _ = match LikeService::unlike_comment(&state.mm.db(), ctx.user_id, &comment_id).await {
+        let _ = match LikeService::unlike_comment(&state.mm.db(), &ctx, &comment_id).await {
             Ok(_) => {
                 info!("Comment unliked successfully");
                 return ApiResponse::success(201, "Comment liked successfully", None);
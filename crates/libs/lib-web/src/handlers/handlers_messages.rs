@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use axum::extract::{Path, Query, State};
 use futures::SinkExt as _;
+use lib_core::db::pagination::{Cursor, PageRequest};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
@@ -51,23 +52,33 @@ pub async fn get_messages(
     State(state): State<Arc<AppState>>,
     CtxExt(ctx): CtxExt,
     Query(params): Query<MessageQuery>,
+    Query(page): Query<PageRequest>,
 ) -> ApiResponse<MessagesResposnse> {
     const FAILED_MESSAGE: &str = "Failed to fetch messages";
     info!("Starting fetch messages by user: {:?}", ctx.user_id);
 
-    let messages =
-        match ChatService::get_messages(state.mm.clone(), ctx.clone(), &params.chat_id).await {
-            Ok(msg) => {
-                info!("Messages fetched: {}", msg.len());
-                msg
-            }
-            Err(err) => {
-                error!("Failed to fetch messages by user: {:?}", ctx.user_id);
-                return ApiResponse::error(FAILED_MESSAGE, err);
-            }
-        };
+    let messages = match ChatService::get_messages(
+        state.mm.clone(),
+        ctx.clone(),
+        &params.chat_id,
+        &page,
+    )
+    .await
+    {
+        Ok(msg) => {
+            info!("Messages fetched: {}", msg.items.len());
+            msg
+        }
+        Err(err) => {
+            error!("Failed to fetch messages by user: {:?}", ctx.user_id);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
 
-    let msg_response = MessagesResposnse { messages };
+    let msg_response = MessagesResposnse {
+        messages: messages.items,
+        next_cursor: messages.next_cursor,
+    };
 
     info!("Messages fetched successully by user: {:?}", ctx.user_id);
     ApiResponse::success(201, "Messages fetched successully", Some(msg_response))
@@ -330,4 +341,5 @@ pub struct MessageResposnse {
 #[derive(Serialize)]
 pub struct MessagesResposnse {
     messages: Vec<MessageDto>,
+    next_cursor: Option<Cursor>,
 }
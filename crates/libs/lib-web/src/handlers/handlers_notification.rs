@@ -5,26 +5,48 @@ use axum::{
         ws::{Message as WsMessage, WebSocket},
         Query, State, WebSocketUpgrade,
     },
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
 use futures::StreamExt as _;
+use lib_core::acs::ScopeAccess;
 use serde::Deserialize;
-use tracing::debug;
+use tracing::{debug, warn};
 use uuid::Uuid;
 
 use super::AppState;
+use crate::extractors::CtxExt;
+use crate::services::ws_ticket_service::WsTicketService;
+use crate::utils::{response::ApiResponse, token::WS_TOKEN_PROTOCOL};
 
 #[derive(Deserialize)]
 pub struct NotificationQuery {
-    user_id: Uuid,
+    ticket: Option<String>,
 }
 
 pub async fn notification_ws_handler(
     ws: WebSocketUpgrade,
+    CtxExt(ctx): CtxExt,
     Query(params): Query<NotificationQuery>,
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, params.user_id, state))
+) -> Response {
+    let user_id = match WsTicketService::resolve_user(
+        state.mm.cache(),
+        &ctx,
+        params.ticket.as_deref(),
+        ScopeAccess::Read,
+    )
+    .await
+    {
+        Ok(user_id) => user_id,
+        Err(err) => {
+            warn!("Notification socket rejected: {:?}", err);
+            return ApiResponse::<()>::error("Failed to connect to notifications", err)
+                .into_response();
+        }
+    };
+
+    ws.protocols([WS_TOKEN_PROTOCOL])
+        .on_upgrade(move |socket| handle_socket(socket, user_id, state))
 }
 
 async fn handle_socket(socket: WebSocket, user_id: Uuid, state: Arc<AppState>) {
@@ -2,6 +2,8 @@ use std::sync::Arc;
 
 use axum::extract::{Path, Query, State};
 use futures::SinkExt as _;
+use lib_core::ctx::Ctx;
+use lib_core::db::pagination::{Cursor, PageRequest};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
@@ -23,16 +25,17 @@ pub async fn get_posts(
     State(state): State<Arc<AppState>>,
     CtxExt(ctx): CtxExt,
     Query(params): Query<PostQuery>,
+    Query(page): Query<PageRequest>,
 ) -> ApiResponse<PostsResponse> {
     const FAILED_MESSAGE: &str = "Failed to fetch posts";
     info!("Starting fetch posts");
 
     let posts = if let Some(user_id) = params.user_id {
-        match PostService::get_many_by_user_id(state.mm.db(), ctx.user_id, &user_id).await {
+        match PostService::get_many_by_user_id(state.mm.db(), &ctx, &user_id, &page).await {
             Ok(posts) => {
                 info!(
                     "Successfully fetched {} posts for user: {}",
-                    posts.len(),
+                    posts.items.len(),
                     user_id
                 );
                 posts
@@ -43,12 +46,12 @@ pub async fn get_posts(
             }
         }
     } else if let Some(community_id) = params.community_id {
-        match PostService::get_many_by_community_id(state.mm.db(), ctx.user_id, &community_id).await
+        match PostService::get_many_by_community_id(state.mm.db(), &ctx, &community_id, &page).await
         {
             Ok(posts) => {
                 info!(
                     "Successfully fetched {} posts for community: {}",
-                    posts.len(),
+                    posts.items.len(),
                     community_id
                 );
                 posts
@@ -62,9 +65,9 @@ pub async fn get_posts(
             }
         }
     } else {
-        match PostService::get_many(state.mm.db(), ctx.user_id).await {
+        match PostService::get_many(state.mm.db(), &ctx, &page).await {
             Ok(posts) => {
-                info!("Successfully fetched {} posts", posts.len(),);
+                info!("Successfully fetched {} posts", posts.items.len(),);
                 posts
             }
             Err(err) => {
@@ -74,7 +77,10 @@ pub async fn get_posts(
         }
     };
 
-    let posts_response = PostsResponse { posts };
+    let posts_response = PostsResponse {
+        posts: posts.items,
+        next_cursor: posts.next_cursor,
+    };
 
     info!("Posts fetched successfully");
     ApiResponse::success(200, "Posts fetched successully", Some(posts_response))
@@ -88,7 +94,7 @@ pub async fn get_post(
     const FAILED_MESSAGE: &str = "Failed to fetch post";
     info!("Starting fetch post by id: {}", id);
 
-    let post = match PostService::get_by_id(state.mm.db(), ctx.user_id, &id).await {
+    let post = match PostService::get_by_id(state.mm.db(), &ctx, &id).await {
         Ok(post) => {
             info!("Post found: ");
             post
@@ -130,7 +136,7 @@ pub async fn create_post(
 
     let post = match PostService::create(
         state.mm.db(),
-        ctx.user_id,
+        &ctx,
         &payload.community_id,
         &payload.title,
         &payload.content,
@@ -141,7 +147,7 @@ pub async fn create_post(
             info!("Post created: {}", post.id);
 
             if let Ok(uids) =
-                FollowService::get_followers(state.mm.db(), ctx.user_id, &post.community_id).await
+                FollowService::get_followers(state.mm.db(), &ctx, &post.community_id).await
             {
                 for uid in uids
                     .into_iter()
@@ -204,24 +210,17 @@ pub async fn update_post(
     const FAILED_MESSAGE: &str = "Failed to update post";
     info!("Starting update post by user: {:?}", ctx.user_id);
 
-    let post = match PostService::update(
-        state.mm.db(),
-        ctx.user_id,
-        &id,
-        payload.title,
-        payload.content,
-    )
-    .await
-    {
-        Ok(post) => {
-            info!("Post updated: {}", post.id);
-            post
-        }
-        Err(err) => {
-            error!("Failed to update post by user: {:?}", ctx.user_id);
-            return ApiResponse::error(FAILED_MESSAGE, err);
-        }
-    };
+    let post =
+        match PostService::update(state.mm.db(), &ctx, &id, payload.title, payload.content).await {
+            Ok(post) => {
+                info!("Post updated: {}", post.id);
+                post
+            }
+            Err(err) => {
+                error!("Failed to update post by user: {:?}", ctx.user_id);
+                return ApiResponse::error(FAILED_MESSAGE, err);
+            }
+        };
 
     let post_response = PostResposnse { post };
 
@@ -237,7 +236,7 @@ pub async fn delete_post(
     const FAILED_MESSAGE: &str = "Failed to delete post";
     info!("Starting delete post by user: {:?}", ctx.user_id);
 
-    let _ = match PostService::delete(state.mm.db(), ctx.user_id, &id).await {
+    let _ = match PostService::delete(state.mm.db(), &ctx, &id).await {
         Ok(post) => {
             info!("Post deleted: {}", &id);
             post
@@ -252,6 +251,51 @@ pub async fn delete_post(
     ApiResponse::success(201, "Post deleted successully", None)
 }
 
+pub async fn pin_post(
+    State(state): State<Arc<AppState>>,
+    CtxExt(ctx): CtxExt,
+    Path(id): Path<Uuid>,
+) -> ApiResponse<PostResposnse> {
+    set_pinned(state, ctx, id, true).await
+}
+
+pub async fn unpin_post(
+    State(state): State<Arc<AppState>>,
+    CtxExt(ctx): CtxExt,
+    Path(id): Path<Uuid>,
+) -> ApiResponse<PostResposnse> {
+    set_pinned(state, ctx, id, false).await
+}
+
+async fn set_pinned(
+    state: Arc<AppState>,
+    ctx: Ctx,
+    id: Uuid,
+    is_pinned: bool,
+) -> ApiResponse<PostResposnse> {
+    const FAILED_MESSAGE: &str = "Failed to pin post";
+    info!(
+        "Starting set pinned={} for post {} by user: {:?}",
+        is_pinned, id, ctx.user_id
+    );
+
+    let post = match PostService::set_pinned(state.mm.db(), &ctx, &id, is_pinned).await {
+        Ok(post) => {
+            info!("Post {} pinned: {}", post.id, post.is_pinned);
+            post
+        }
+        Err(err) => {
+            error!("Failed to pin post by user: {:?}", ctx.user_id);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    let post_response = PostResposnse { post };
+
+    info!("Post pin updated successully by user: {:?}", ctx.user_id);
+    ApiResponse::success(200, "Post pin updated successully", Some(post_response))
+}
+
 #[derive(Deserialize)]
 pub struct PostQuery {
     user_id: Option<Uuid>,
@@ -289,4 +333,5 @@ pub struct PostResposnse {
 #[derive(Serialize)]
 pub struct PostsResponse {
     posts: Vec<PostDto>,
+    next_cursor: Option<Cursor>,
 }
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
-use axum::extract::State;
+use axum::extract::{Query, State};
+use lib_core::db::pagination::{Cursor, PageRequest};
 use lib_core::model::ModelManager;
 use serde::{Deserialize, Serialize};
 use tracing::{error, info};
@@ -16,13 +17,14 @@ use crate::{
 pub async fn get_saves(
     State(mm): State<Arc<ModelManager>>,
     CtxExt(ctx): CtxExt,
+    Query(page): Query<PageRequest>,
 ) -> ApiResponse<SavesResponse> {
     const FAILED_MESSAGE: &str = "Failed to fetch saves";
     info!("Starting fetch saves");
 
-    let saves = match ProfileService::get_saves(mm.db(), ctx.user_id).await {
+    let saves = match ProfileService::get_saves(mm.db(), &ctx, &page).await {
         Ok(saves) => {
-            info!("Successfully fetched {} saves", saves.len(),);
+            info!("Successfully fetched {} saves", saves.items.len(),);
             saves
         }
         Err(err) => {
@@ -31,7 +33,10 @@ pub async fn get_saves(
         }
     };
 
-    let saves_response = SavesResponse { saves };
+    let saves_response = SavesResponse {
+        saves: saves.items,
+        next_cursor: saves.next_cursor,
+    };
 
     info!("Saves fetched successfully");
     ApiResponse::success(200, "Saves fetched successully", Some(saves_response))
@@ -45,7 +50,7 @@ pub async fn create_save(
     const FAILED_MESSAGE: &str = "Failed to create save";
     info!("Starting craete save");
 
-    let save = match ProfileService::create_save(mm.db(), ctx.user_id, &paylaod.post_id).await {
+    let save = match ProfileService::create_save(mm.db(), &ctx, &paylaod.post_id).await {
         Ok(save) => {
             info!("Successfully created save");
             save
@@ -70,7 +75,7 @@ pub async fn delete_save(
     const FAILED_MESSAGE: &str = "Failed to delete save";
     info!("Starting delete save");
 
-    let _ = match ProfileService::delete_save(mm.db(), ctx.user_id, &paylaod.post_id).await {
+    let _ = match ProfileService::delete_save(mm.db(), &ctx, &paylaod.post_id).await {
         Ok(_) => {
             info!("Successfully deleted save");
         }
@@ -97,4 +102,5 @@ pub struct SaveResponse {
 #[derive(Serialize)]
 pub struct SavesResponse {
     saves: Vec<SaveDto>,
+    next_cursor: Option<Cursor>,
 }
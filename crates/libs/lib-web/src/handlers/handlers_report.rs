@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use axum::extract::{Path, Query, State};
+use lib_core::db::pagination::{Cursor, PageRequest};
 use lib_core::model::report::{ReportStatusType, ReportTargetType};
 use serde::{Deserialize, Serialize};
 use tracing::{error, info};
@@ -8,7 +9,6 @@ use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
-    error::Error,
     extractors::{CtxExt, ValidatedJson},
     services::report_service::{ReportDto, ReportService},
     utils::response::ApiResponse,
@@ -31,19 +31,11 @@ pub async fn create_report(
     const FAILED_MESSAGE: &str = "Failed to create report";
     info!("Starting create report by user: {:?}", ctx.user_id);
 
-    let requester_id = match ctx.user_id.ok_or(Error::Unauthorized) {
-        Ok(uid) => uid,
-        Err(e) => {
-            error!("Requester Unauthorized");
-            return ApiResponse::error(FAILED_MESSAGE, e);
-        }
-    };
-
     let report = match ReportService::create(
         state.mm.db(),
+        &ctx,
         payload.report_type,
         &payload.reported_id,
-        &requester_id,
         payload.reason.clone(),
     )
     .await
@@ -66,13 +58,13 @@ pub async fn create_report(
 
 pub async fn get_report(
     State(state): State<Arc<AppState>>,
-    CtxExt(_): CtxExt,
+    CtxExt(ctx): CtxExt,
     Path(id): Path<Uuid>,
 ) -> ApiResponse<ReportResponse> {
     const FAILED_MESSAGE: &str = "Failed to fetch report";
     info!("Starting fetch report by id: {}", id);
 
-    let report = match ReportService::get_by_id(state.mm.db(), &id).await {
+    let report = match ReportService::get_by_id(state.mm.db(), &ctx, &id).await {
         Ok(report) => {
             info!("Report found: {}", id);
             report
@@ -91,18 +83,20 @@ pub async fn get_report(
 
 pub async fn get_reports(
     State(state): State<Arc<AppState>>,
-    CtxExt(_): CtxExt,
+    CtxExt(ctx): CtxExt,
     Query(params): Query<ReportQuery>,
+    Query(page): Query<PageRequest>,
 ) -> ApiResponse<ReportsResponse> {
     const FAILED_MESSAGE: &str = "Failed to fetch reports";
     info!("Starting fetch reports");
 
     let reports = if let Some(reported_id) = params.reported_id {
-        match ReportService::get_many_by_reported_id(state.mm.db(), &reported_id).await {
+        match ReportService::get_many_by_reported_id(state.mm.db(), &ctx, &reported_id, &page).await
+        {
             Ok(reports) => {
                 info!(
                     "Successfully fetched {} reports for reported_id: {}",
-                    reports.len(),
+                    reports.items.len(),
                     reported_id
                 );
                 reports
@@ -116,11 +110,12 @@ pub async fn get_reports(
             }
         }
     } else if let Some(reporter_id) = params.reporter_id {
-        match ReportService::get_many_by_reporter_id(state.mm.db(), &reporter_id).await {
+        match ReportService::get_many_by_reporter_id(state.mm.db(), &ctx, &reporter_id, &page).await
+        {
             Ok(reports) => {
                 info!(
                     "Successfully fetched {} reports for reporter_id: {}",
-                    reports.len(),
+                    reports.items.len(),
                     reporter_id
                 );
                 reports
@@ -134,11 +129,11 @@ pub async fn get_reports(
             }
         }
     } else if let Some(status) = params.status {
-        match ReportService::get_many_by_status(state.mm.db(), status).await {
+        match ReportService::get_many_by_status(state.mm.db(), &ctx, status, &page).await {
             Ok(reports) => {
                 info!(
                     "Successfully fetched {} reports with status: {}",
-                    reports.len(),
+                    reports.items.len(),
                     status
                 );
                 reports
@@ -149,9 +144,9 @@ pub async fn get_reports(
             }
         }
     } else {
-        match ReportService::get_many(state.mm.db()).await {
+        match ReportService::get_many(state.mm.db(), &ctx, &page).await {
             Ok(reports) => {
-                info!("Successfully fetched {} reports", reports.len());
+                info!("Successfully fetched {} reports", reports.items.len());
                 reports
             }
             Err(err) => {
@@ -161,7 +156,10 @@ pub async fn get_reports(
         }
     };
 
-    let reports_response = ReportsResponse { reports };
+    let reports_response = ReportsResponse {
+        reports: reports.items,
+        next_cursor: reports.next_cursor,
+    };
 
     info!("Reports fetched successfully");
     ApiResponse::success(200, "Reports fetched successfully", Some(reports_response))
@@ -178,6 +176,7 @@ pub async fn update_report_status(
 
     let report = match ReportService::update_status(
         state.mm.db(),
+        &ctx,
         &id,
         payload.status,
         payload.reason.clone(),
@@ -215,7 +214,7 @@ pub async fn delete_report(
     const FAILED_MESSAGE: &str = "Failed to delete report";
     info!("Starting delete report by user: {:?}", ctx.user_id);
 
-    match ReportService::delete(state.mm.db(), &id).await {
+    match ReportService::delete(state.mm.db(), &ctx, &id).await {
         Ok(_) => {
             info!("Report deleted: {}", id);
             ApiResponse::success(200, "Report deleted successfully", None)
@@ -255,4 +254,5 @@ pub struct ReportResponse {
 #[derive(Serialize)]
 pub struct ReportsResponse {
     reports: Vec<ReportDto>,
+    next_cursor: Option<Cursor>,
 }
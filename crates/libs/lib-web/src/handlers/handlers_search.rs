@@ -1,12 +1,14 @@
 use std::sync::Arc;
 
 use axum::extract::{Query, State};
+use lib_core::acs::{ScopeAccess, ScopeResource};
 use serde::{Deserialize, Serialize};
 use tracing::{error, info};
 
 use crate::{
     extractors::CtxExt,
     services::{
+        access_service::check_scope,
         community_service::{CommunityDto, CommunityService},
         post_service::{PostDto, PostService},
         user_service::{UserDto, UserService},
@@ -29,6 +31,10 @@ pub async fn search(
     const FAILED_MESSAGE: &str = "Failed to search";
     info!("Starting search for user: {:?}", ctx.user_id);
 
+    if let Err(err) = check_scope(&ctx, ScopeResource::Users, ScopeAccess::Read) {
+        return ApiResponse::error(FAILED_MESSAGE, err);
+    }
+
     let users =
         match UserService::get_meny_by_query(state.mm.db(), ctx.user_id, &params.query).await {
             Ok(users) => users,
@@ -38,29 +44,23 @@ pub async fn search(
             }
         };
 
-    let communities = match CommunityService::get_meny_by_query(
-        state.mm.db(),
-        ctx.user_id,
-        &params.query,
-    )
-    .await
-    {
-        Ok(communities) => communities,
-        Err(e) => {
-            error!("Error while searching communities");
-            return ApiResponse::error(FAILED_MESSAGE, e);
-        }
-    };
-
-    let posts =
-        match PostService::get_meny_by_query(state.mm.db(), ctx.user_id, &params.query).await {
-            Ok(posts) => posts,
+    let communities =
+        match CommunityService::get_meny_by_query(state.mm.db(), &ctx, &params.query).await {
+            Ok(communities) => communities,
             Err(e) => {
-                error!("Error while searching posts");
+                error!("Error while searching communities");
                 return ApiResponse::error(FAILED_MESSAGE, e);
             }
         };
 
+    let posts = match PostService::get_meny_by_query(state.mm.db(), &ctx, &params.query).await {
+        Ok(posts) => posts,
+        Err(e) => {
+            error!("Error while searching posts");
+            return ApiResponse::error(FAILED_MESSAGE, e);
+        }
+    };
+
     let search_response = SearchResponse {
         users,
         communities,
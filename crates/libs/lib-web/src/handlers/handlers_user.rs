@@ -2,7 +2,9 @@
 
 use axum::extract::{Path, Query, State};
 use lib_core::{
+    acs::{ScopeAccess, ScopeResource},
     ctx::Ctx,
+    db::pagination::{Cursor, PageRequest},
     model::{
         role::RoleEnum,
         user::{self},
@@ -15,7 +17,11 @@ use tracing::{debug, error, info};
 use uuid::Uuid;
 use validator::Validate;
 
-use crate::extractors::{CtxExt, ValidatedJson};
+use crate::extractors::{ClientInfo, CtxExt, ValidatedJson};
+use crate::services::access_service::check_scope;
+use crate::services::ban_service::{BanDto, BanService};
+use crate::services::impersonation_service::{ImpersonationDto, ImpersonationService};
+use crate::services::revocation_service::RevocationService;
 use crate::services::user_service::{UserDto, UserService};
 use crate::utils::response::ApiResponse;
 
@@ -28,12 +34,17 @@ pub async fn get_all_users(
     State(mm): State<Arc<ModelManager>>,
     CtxExt(ctx): CtxExt,
     Query(params): Query<UserQuery>,
+    Query(page): Query<PageRequest>,
 ) -> ApiResponse<UsersResponse> {
     const FAILED_MESSAGE: &str = "Failed to register";
     info!("Starting fetching users");
 
+    if let Err(err) = check_scope(&ctx, ScopeResource::Users, ScopeAccess::Read) {
+        return ApiResponse::error(FAILED_MESSAGE, err);
+    }
+
     let users = match params.is_banned {
-        Some(is_banned) => match UserService::get_banned(mm.db(), ctx.user_id).await {
+        Some(is_banned) => match UserService::get_banned(mm.db(), ctx.user_id, &page).await {
             Ok(users) => {
                 info!("Banned users fetched successul");
                 users
@@ -43,7 +54,7 @@ pub async fn get_all_users(
                 return ApiResponse::error(FAILED_MESSAGE, err);
             }
         },
-        None => match UserService::get_all(mm.db(), ctx.user_id).await {
+        None => match UserService::get_all(mm.db(), ctx.user_id, &page).await {
             Ok(users) => {
                 info!("Users fetched successul");
                 users
@@ -55,7 +66,10 @@ pub async fn get_all_users(
         },
     };
 
-    let users_response = UsersResponse { users };
+    let users_response = UsersResponse {
+        users: users.items,
+        next_cursor: users.next_cursor,
+    };
 
     info!("Fetching users successful");
     ApiResponse::success(200, "Users fetched successully", Some(users_response))
@@ -69,6 +83,10 @@ pub async fn get_user_profile(
     const FAILED_MESSAGE: &str = "Failed to find user";
     info!("Starting fetching user");
 
+    if let Err(err) = check_scope(&ctx, ScopeResource::Users, ScopeAccess::Read) {
+        return ApiResponse::error(FAILED_MESSAGE, err);
+    }
+
     let user = match UserService::get_by_nickname(mm.db(), ctx.user_id, &nickname).await {
         Ok(user) => {
             debug!("User found: {}", user.nickname);
@@ -95,6 +113,10 @@ pub async fn update_user_profile(
     const FAILED_MESSAGE: &str = "Failed to update user";
     info!("Starting udpate user");
 
+    if let Err(err) = check_scope(&ctx, ScopeResource::Users, ScopeAccess::Write) {
+        return ApiResponse::error(FAILED_MESSAGE, err);
+    }
+
     let user = match UserService::get_by_nickname(mm.db(), ctx.user_id, &nickname).await {
         Ok(user) => {
             debug!("User found: {}", user.nickname);
@@ -106,27 +128,29 @@ pub async fn update_user_profile(
         }
     };
 
-    let user = match UserService::update(
-        mm.db(),
-        ctx.user_id,
-        &user.id,
-        payload.nickname,
-        payload.email,
-        payload.role,
-        None,
-        payload.is_banned,
-    )
-    .await
-    {
-        Ok(user) => {
-            debug!("User updated: {}", user.id);
-            user
-        }
-        Err(err) => {
-            error!("Failed to update user: {}", err);
+    let role_changed = payload.role.is_some();
+
+    let user =
+        match UserService::update_profile(mm.db(), &ctx, &user.id, payload.nickname, payload.role)
+            .await
+        {
+            Ok(user) => {
+                debug!("User updated: {}", user.id);
+                user
+            }
+            Err(err) => {
+                error!("Failed to update user: {}", err);
+                return ApiResponse::error(FAILED_MESSAGE, err);
+            }
+        };
+
+    // Access tokens carry the role, the old ones must not outlive a role change
+    if role_changed {
+        if let Err(err) = RevocationService::revoke_user(mm.cache(), &user.id).await {
+            error!("Failed to revoke tokens after role change: {}", err);
             return ApiResponse::error(FAILED_MESSAGE, err);
         }
-    };
+    }
 
     let user_response = UserResponse { user };
 
@@ -142,6 +166,10 @@ pub async fn delete_user_profile(
     const FAILED_MESSAGE: &str = "Failed to delete user";
     info!("Starting delete user");
 
+    if let Err(err) = check_scope(&ctx, ScopeResource::Users, ScopeAccess::Write) {
+        return ApiResponse::error(FAILED_MESSAGE, err);
+    }
+
     let user = match UserService::get_by_nickname(mm.db(), ctx.user_id, &nickname).await {
         Ok(user) => {
             debug!("User found: {}", user.nickname);
@@ -167,6 +195,200 @@ pub async fn delete_user_profile(
     ApiResponse::success(200, "User deleted successully", None)
 }
 
+pub async fn ban_user(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path(nickname): Path<String>,
+    ValidatedJson(payload): ValidatedJson<BanPayload>,
+) -> ApiResponse<BanResponse> {
+    const FAILED_MESSAGE: &str = "Failed to ban user";
+    info!("Starting ban user");
+
+    let user = match UserService::get_by_nickname(mm.db(), ctx.user_id, &nickname).await {
+        Ok(user) => {
+            debug!("User found: {}", user.nickname);
+            user
+        }
+        Err(err) => {
+            error!("Failed to find user: {}", err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    let ban = match BanService::ban(&mm, &ctx, &user, &payload.reason, payload.duration_hours).await
+    {
+        Ok(ban) => {
+            debug!("User banned: {}", user.id);
+            ban
+        }
+        Err(err) => {
+            error!("Failed to ban user: {}", err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    info!("Banning user successful");
+    ApiResponse::success(201, "User banned successully", Some(BanResponse { ban }))
+}
+
+pub async fn unban_user(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path(nickname): Path<String>,
+) -> ApiResponse<()> {
+    const FAILED_MESSAGE: &str = "Failed to unban user";
+    info!("Starting unban user");
+
+    let user = match UserService::get_by_nickname(mm.db(), ctx.user_id, &nickname).await {
+        Ok(user) => {
+            debug!("User found: {}", user.nickname);
+            user
+        }
+        Err(err) => {
+            error!("Failed to find user: {}", err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    if let Err(err) = BanService::lift(&mm, &ctx, &user).await {
+        error!("Failed to unban user: {}", err);
+        return ApiResponse::error(FAILED_MESSAGE, err);
+    }
+
+    info!("Unbanning user successful");
+    ApiResponse::success(200, "User unbanned successully", None)
+}
+
+pub async fn get_user_bans(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path(nickname): Path<String>,
+) -> ApiResponse<BansResponse> {
+    const FAILED_MESSAGE: &str = "Failed to fetch bans";
+    info!("Starting fetching bans");
+
+    let user = match UserService::get_by_nickname(mm.db(), ctx.user_id, &nickname).await {
+        Ok(user) => {
+            debug!("User found: {}", user.nickname);
+            user
+        }
+        Err(err) => {
+            error!("Failed to find user: {}", err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    let bans = match BanService::get_history(&mm, &ctx, &user).await {
+        Ok(bans) => bans,
+        Err(err) => {
+            error!("Failed to fetch bans: {}", err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    info!("Fetching bans successful");
+    ApiResponse::success(200, "Bans fetched successully", Some(BansResponse { bans }))
+}
+
+pub async fn impersonate_user(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    client: ClientInfo,
+    Path(nickname): Path<String>,
+    ValidatedJson(payload): ValidatedJson<ImpersonatePayload>,
+) -> ApiResponse<ImpersonationResponse> {
+    const FAILED_MESSAGE: &str = "Failed to impersonate user";
+    info!("Starting impersonate user");
+
+    let user = match UserService::get_by_nickname(mm.db(), ctx.user_id, &nickname).await {
+        Ok(user) => {
+            debug!("User found: {}", user.nickname);
+            user
+        }
+        Err(err) => {
+            error!("Failed to find user: {}", err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    let (impersonation, access_token) =
+        match ImpersonationService::start(&mm, &ctx, &user, &payload.reason, client).await {
+            Ok(result) => {
+                info!(
+                    "User {} impersonated by {:?}: {}",
+                    user.id, ctx.user_id, payload.reason
+                );
+                result
+            }
+            Err(err) => {
+                error!("Failed to impersonate user: {}", err);
+                return ApiResponse::error(FAILED_MESSAGE, err);
+            }
+        };
+
+    info!("Impersonating user successful");
+    ApiResponse::success(
+        201,
+        "Impersonation started successully",
+        Some(ImpersonationResponse {
+            impersonation,
+            access_token,
+        }),
+    )
+}
+
+pub async fn end_impersonation(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path(id): Path<Uuid>,
+) -> ApiResponse<()> {
+    const FAILED_MESSAGE: &str = "Failed to end impersonation";
+    info!("Starting end impersonation");
+
+    if let Err(err) = ImpersonationService::end(&mm, &ctx, &id).await {
+        error!("Failed to end impersonation: {}", err);
+        return ApiResponse::error(FAILED_MESSAGE, err);
+    }
+
+    info!("Ending impersonation successful");
+    ApiResponse::success(200, "Impersonation ended successully", None)
+}
+
+pub async fn get_user_impersonations(
+    State(mm): State<Arc<ModelManager>>,
+    CtxExt(ctx): CtxExt,
+    Path(nickname): Path<String>,
+) -> ApiResponse<ImpersonationsResponse> {
+    const FAILED_MESSAGE: &str = "Failed to fetch impersonations";
+    info!("Starting fetching impersonations");
+
+    let user = match UserService::get_by_nickname(mm.db(), ctx.user_id, &nickname).await {
+        Ok(user) => {
+            debug!("User found: {}", user.nickname);
+            user
+        }
+        Err(err) => {
+            error!("Failed to find user: {}", err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    let impersonations = match ImpersonationService::get_history(&mm, &ctx, &user).await {
+        Ok(impersonations) => impersonations,
+        Err(err) => {
+            error!("Failed to fetch impersonations: {}", err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    info!("Fetching impersonations successful");
+    ApiResponse::success(
+        200,
+        "Impersonations fetched successully",
+        Some(ImpersonationsResponse { impersonations }),
+    )
+}
+
 #[derive(Deserialize, Validate)]
 pub struct UserUpdatePayload {
     #[validate(length(
@@ -176,12 +398,30 @@ pub struct UserUpdatePayload {
     ))]
     nickname: Option<String>,
 
-    #[validate(email(message = "Invalid email format"))]
-    email: Option<String>,
-
     role: Option<RoleEnum>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct BanPayload {
+    #[validate(length(
+        min = 1,
+        max = 500,
+        message = "Reason must contain at least 1 characters and no more than 500"
+    ))]
+    reason: String,
 
-    is_banned: Option<bool>,
+    #[validate(range(min = 1, message = "Ban duration must be at least 1 hour"))]
+    duration_hours: Option<i64>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ImpersonatePayload {
+    #[validate(length(
+        min = 1,
+        max = 500,
+        message = "Reason must contain at least 1 characters and no more than 500"
+    ))]
+    reason: String,
 }
 
 #[derive(Serialize)]
@@ -192,4 +432,26 @@ pub struct UserResponse {
 #[derive(Serialize)]
 pub struct UsersResponse {
     users: Vec<UserDto>,
+    next_cursor: Option<Cursor>,
+}
+
+#[derive(Serialize)]
+pub struct BanResponse {
+    ban: BanDto,
+}
+
+#[derive(Serialize)]
+pub struct BansResponse {
+    bans: Vec<BanDto>,
+}
+
+#[derive(Serialize)]
+pub struct ImpersonationResponse {
+    impersonation: ImpersonationDto,
+    access_token: String,
+}
+
+#[derive(Serialize)]
+pub struct ImpersonationsResponse {
+    impersonations: Vec<ImpersonationDto>,
 }
@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use serde::Serialize;
+use tracing::{error, info};
+
+use super::AppState;
+use crate::extractors::CtxExt;
+use crate::services::ws_ticket_service::WsTicketService;
+use crate::utils::response::ApiResponse;
+
+pub async fn create_ticket(
+    State(state): State<Arc<AppState>>,
+    CtxExt(ctx): CtxExt,
+) -> ApiResponse<WsTicketResponse> {
+    const FAILED_MESSAGE: &str = "Failed to create socket ticket";
+    info!("Starting create socket ticket");
+
+    let ticket = match WsTicketService::issue(state.mm.cache(), &ctx).await {
+        Ok(ticket) => ticket,
+        Err(err) => {
+            error!("Failed to create socket ticket: {:?}", err);
+            return ApiResponse::error(FAILED_MESSAGE, err);
+        }
+    };
+
+    info!("Creating socket ticket successful");
+    ApiResponse::success(
+        201,
+        "Socket ticket created successfully",
+        Some(WsTicketResponse { ticket }),
+    )
+}
+
+#[derive(Serialize)]
+pub struct WsTicketResponse {
+    ticket: String,
+}
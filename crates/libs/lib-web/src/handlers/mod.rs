@@ -6,6 +6,7 @@ use lib_core::model::ModelManager;
 use tokio::sync::{mpsc, Mutex};
 use uuid::Uuid;
 
+pub mod handlers_admin;
 pub mod handlers_auth;
 pub mod handlers_chat;
 pub mod handlers_comment;
@@ -19,6 +20,7 @@ pub mod handlers_profile;
 pub mod handlers_report;
 pub mod handlers_search;
 pub mod handlers_user;
+pub mod handlers_ws;
 pub mod ws_handlers_chat;
 
 #[derive(Debug, Clone)]
@@ -7,35 +7,65 @@ use axum::{
         ws::{Message as WsMessage, WebSocket},
         ConnectInfo, Path, Query, State, WebSocketUpgrade,
     },
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
 use futures::{SinkExt as _, StreamExt as _};
+use lib_core::acs::ScopeAccess;
 use lib_core::ctx::Ctx;
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 use tracing::{debug, error, warn};
 use uuid::Uuid;
 
+use crate::extractors::CtxExt;
 use crate::services::{
+    ban_service::BanService,
     chat_service::{ChatDto, ChatService, MessageDto},
     user_service::UserDto,
+    ws_ticket_service::WsTicketService,
 };
+use crate::utils::{response::ApiResponse, token::WS_TOKEN_PROTOCOL};
 
 use super::{AppState, UserConnection};
 
 #[derive(Deserialize)]
 pub struct WsChatQuery {
-    user_id: Uuid,
+    ticket: Option<String>,
 }
 
 pub async fn ws_handler(
     State(state): State<Arc<AppState>>,
+    CtxExt(ctx): CtxExt,
     Path(id): Path<Uuid>,
     Query(params): Query<WsChatQuery>,
     ws: WebSocketUpgrade,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_chat_socket(socket, addr, state, id, params.user_id))
+) -> Response {
+    const FAILED_MESSAGE: &str = "Failed to connect to chat";
+
+    // Сообщения тоже отправляются через сокет, поэтому нужен доступ на запись
+    let user_id = match WsTicketService::resolve_user(
+        state.mm.cache(),
+        &ctx,
+        params.ticket.as_deref(),
+        ScopeAccess::Write,
+    )
+    .await
+    {
+        Ok(user_id) => user_id,
+        Err(err) => {
+            warn!("Chat socket rejected: {:?}", err);
+            return ApiResponse::<()>::error(FAILED_MESSAGE, err).into_response();
+        }
+    };
+
+    if let Err(err) = ChatService::check_member(state.mm.clone(), Ctx::new(user_id), &id).await {
+        warn!("User {user_id} rejected from chat {id}: {:?}", err);
+        return ApiResponse::<()>::error(FAILED_MESSAGE, err).into_response();
+    }
+
+    ws.protocols([WS_TOKEN_PROTOCOL])
+        .on_upgrade(move |socket| handle_chat_socket(socket, addr, state, id, user_id))
 }
 
 async fn send_to_chat_members(
@@ -135,6 +165,12 @@ pub async fn handle_chat_socket(
     let state_clone = Arc::clone(&state);
     let mut recv_task = tokio::spawn(async move {
         while let Some(Ok(WsMessage::Text(msg))) = receiver.next().await {
+            // Блокировка могла случиться уже после подключения
+            if let Err(err) = BanService::check_cached(state_clone.mm.cache(), &user_id).await {
+                warn!("Closing chat socket of user {user_id}: {:?}", err);
+                break;
+            }
+
             match serde_json::from_str(msg.as_str()) {
                 Ok(IncomingWsMessage::SendMessage { chat_id, content }) => {
                     match ChatService::send_message(
@@ -1,3 +1,4 @@
+pub mod config;
 pub mod error;
 pub mod extractors;
 pub mod handlers;
@@ -1,3 +1,5 @@
 mod mw_auth;
+mod mw_csrf;
 
 pub use mw_auth::*;
+pub use mw_csrf::*;
@@ -1,26 +1,65 @@
 use crate::error::Error;
 use crate::error::Result;
+use crate::services::api_token_service::ApiTokenService;
+use crate::services::ban_service::BanService;
+use crate::services::impersonation_service::ImpersonationService;
+use crate::services::revocation_service::RevocationService;
 use crate::utils::response::ApiResponse;
-use axum::{extract::Request, middleware::Next, response::IntoResponse};
-use lib_auth::token::{verify_token, TokenType};
+use crate::utils::token::{bearer_token, ws_protocol_token};
+use axum::{
+    extract::{OriginalUri, Request, State},
+    middleware::Next,
+    response::IntoResponse,
+};
+use lib_auth::token::{verify_token, TokenType, API_TOKEN_PREFIX};
+use lib_core::acs::{AccessControl, Role, ScopeAccess};
+use lib_core::ctx::Ctx;
+use lib_core::model::ModelManager;
+use std::str::FromStr;
+use std::sync::Arc;
 use tracing::info;
 use tracing::warn;
 use uuid::Uuid;
 
-pub async fn require_auth(mut req: Request, next: Next) -> impl IntoResponse {
+pub async fn require_auth(
+    State(mm): State<Arc<ModelManager>>,
+    mut req: Request,
+    next: Next,
+) -> impl IntoResponse {
     info!("Access checking by request");
 
-    let auth_result = req
-        .headers()
-        .get("Authorization")
-        .and_then(|h| h.to_str().ok())
-        .and_then(|h| h.strip_prefix("Bearer "))
-        .map(|token| validate_token(token));
+    let token = bearer_token(req.headers()).or_else(|| ws_protocol_token(req.headers()));
+
+    let auth_result = match token {
+        Some(token) if token.starts_with(API_TOKEN_PREFIX) => {
+            Some(validate_api_token(&mm, token).await)
+        }
+        Some(token) => Some(validate_token(&mm, token).await),
+        None => None,
+    };
+    let auth_result = match auth_result {
+        Some(Ok(ctx)) => Some(check_ban(&mm, ctx).await),
+        Some(Err(e)) => Some(Err(e)),
+        None => None,
+    };
 
     match auth_result {
-        Some(Ok(user_id)) => {
-            info!("Access allowed for: {}", user_id);
-            req.extensions_mut().insert(Some(user_id));
+        Some(Ok(ctx)) if ctx.is_impersonated() && request_access(&req) == ScopeAccess::Write => {
+            warn!(
+                "Request canceled: {:?} tried to write while impersonating {:?}",
+                ctx.impersonator_id, ctx.user_id
+            );
+            ApiResponse::<()>::error("Access denied", Error::ImpersonationReadOnly).into_response()
+        }
+        Some(Ok(ctx)) => {
+            match ctx.impersonator_id {
+                Some(impersonator_id) => info!(
+                    "Access allowed for: {:?}, impersonated by {}",
+                    ctx.user_id, impersonator_id
+                ),
+                None => info!("Access allowed for: {:?}", ctx.user_id),
+            }
+            req.extensions_mut().insert(ctx);
             next.run(req).await
         }
         Some(Err(e)) => {
@@ -29,13 +68,90 @@ pub async fn require_auth(mut req: Request, next: Next) -> impl IntoResponse {
         }
         None => {
             info!("No valid auth provided, continuing as guest");
-            req.extensions_mut().insert(None::<Uuid>);
+            req.extensions_mut().insert(Ctx::default());
             next.run(req).await
         }
     }
 }
 
-fn validate_token(token: &str) -> Result<Uuid> {
+async fn validate_token(mm: &ModelManager, token: &str) -> Result<Ctx> {
     let token_data = verify_token(token, TokenType::Access).map_err(|_| Error::Unauthorized)?;
-    Uuid::parse_str(&token_data.claims.sub).map_err(|_| Error::Unauthorized)
+    let claims = token_data.claims;
+
+    if RevocationService::is_revoked(mm.cache(), &claims).await? {
+        return Err(Error::Unauthorized);
+    }
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| Error::Unauthorized)?;
+    let role = claims
+        .role
+        .as_deref()
+        .and_then(|role| Role::from_str(role).ok());
+
+    // Impersonation tokens stop working as soon as the admin ends the session
+    let impersonator_id = match (&claims.act, &claims.sid) {
+        (Some(act), Some(sid)) => {
+            if !ImpersonationService::is_active(mm.cache(), sid).await? {
+                return Err(Error::Unauthorized);
+            }
+            Some(Uuid::parse_str(act).map_err(|_| Error::Unauthorized)?)
+        }
+        (Some(_), None) => return Err(Error::Unauthorized),
+        _ => None,
+    };
+
+    Ok(Ctx {
+        user_id: Some(user_id),
+        role,
+        impersonator_id,
+        scopes: None,
+    })
+}
+
+async fn check_ban(mm: &ModelManager, ctx: Ctx) -> Result<Ctx> {
+    if let Some(user_id) = &ctx.user_id {
+        BanService::check_cached(mm.cache(), user_id).await?;
+    }
+
+    Ok(ctx)
+}
+
+/// Scopes are enforced by `AccessControl` for each action the request performs
+async fn validate_api_token(mm: &ModelManager, token: &str) -> Result<Ctx> {
+    let (user_id, scopes) = ApiTokenService::authenticate(mm.db(), token).await?;
+
+    // Role is not stored with the token and is looked up when needed
+    Ok(Ctx::with_scopes(user_id, scopes))
+}
+
+/// For routes personal access tokens can never reach, such as minting new tokens or changing
+/// credentials. Goes after `require_auth`.
+pub async fn require_session(req: Request, next: Next) -> impl IntoResponse {
+    let ctx = req.extensions().get::<Ctx>().cloned().unwrap_or_default();
+
+    match AccessControl::check_scope(&ctx, None) {
+        Ok(()) => next.run(req).await,
+        Err(e) => {
+            warn!("Request canceled: {}", e);
+            ApiResponse::<()>::error("Access denied", Error::Core(e.into())).into_response()
+        }
+    }
+}
+
+fn request_access(req: &Request) -> ScopeAccess {
+    let path = request_path(req);
+
+    // Sockets carry writes too, so they need write access even though they open with GET
+    if req.method().is_safe() && !path.starts_with("/api/ws/") {
+        ScopeAccess::Read
+    } else {
+        ScopeAccess::Write
+    }
+}
+
+fn request_path(req: &Request) -> &str {
+    req.extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.path())
+        .unwrap_or_else(|| req.uri().path())
 }
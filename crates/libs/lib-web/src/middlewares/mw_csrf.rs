@@ -0,0 +1,31 @@
+use crate::error::Error;
+use crate::utils::cookies::{CSRF_COOKIE, CSRF_HEADER, REFRESH_COOKIE};
+use crate::utils::response::ApiResponse;
+use axum::{extract::Request, middleware::Next, response::IntoResponse};
+use axum_extra::extract::CookieJar;
+use lib_auth::token::verify_csrf_token;
+use tracing::warn;
+
+/// Double-submit check for endpoints authenticated by the refresh cookie alone.
+/// The browser attaches the cookie to cross-site requests too, but only our frontend
+/// can read the CSRF cookie and copy it into the header.
+pub async fn require_csrf(jar: CookieJar, req: Request, next: Next) -> impl IntoResponse {
+    // Without the cookie there is nothing to forge, the endpoint rejects the request itself
+    if jar.get(REFRESH_COOKIE).is_none() {
+        return next.run(req).await;
+    }
+
+    let cookie = jar.get(CSRF_COOKIE).map(|c| c.value()).unwrap_or_default();
+    let header = req
+        .headers()
+        .get(CSRF_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or_default();
+
+    if !verify_csrf_token(cookie, header) {
+        warn!("Request canceled: CSRF token mismatch");
+        return ApiResponse::<()>::error("Access denied", Error::CsrfMismatch).into_response();
+    }
+
+    next.run(req).await
+}
@@ -0,0 +1,343 @@
+use chrono::NaiveDateTime;
+use lib_core::acs::{
+    AccessControl, Action, CommunityRole, Condition, Resource, ResourceKind, Role, Rule, Scope,
+    ScopeAccess, ScopeResource,
+};
+use lib_core::ctx::Ctx;
+use lib_core::db::Db;
+use lib_core::model::access_denial::AccessDenialRepo;
+use lib_core::model::comment::{CommentForSelect, CommentRepo};
+use serde::Serialize;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::ban_service::access_denied;
+use super::chat_service::{find_message, ChatService};
+use super::comment_service::find_post;
+use super::community_member_service::{find_community, CommunityMemberService};
+use super::post_service::{get_role, requester_role};
+use super::report_service::find_report;
+
+use crate::error::{Error, Result};
+
+const DENIALS_LIMIT: i64 = 100;
+
+#[derive(Debug, Serialize)]
+pub struct AccessExplanationDto {
+    pub user_id: Uuid,
+    pub role: String,
+    pub community_role: Option<String>,
+    pub resource: String,
+    pub resource_id: Uuid,
+    pub action: String,
+    pub allowed: bool,
+    pub reason: String,
+    pub matched_rule: Option<RuleDto>,
+    pub rejected_rules: Vec<RuleDto>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RuleDto {
+    /// Путь к правилу в файле политики, например `roles.user.grants[3]`
+    pub rule: String,
+    pub when: Vec<Condition>,
+    /// Условия, которые не выполнились
+    pub failed: Vec<Condition>,
+}
+
+impl RuleDto {
+    fn from_rule(rule: Rule, failed: Vec<Condition>) -> Self {
+        Self {
+            rule: rule.to_string(),
+            when: rule.grant.when,
+            failed,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccessDenialDto {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub role: String,
+    pub resource: String,
+    pub resource_id: Uuid,
+    pub action: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<AccessDenialRepo> for AccessDenialDto {
+    fn from(denial: AccessDenialRepo) -> Self {
+        Self {
+            id: denial.id,
+            user_id: denial.user_id,
+            role: denial.role,
+            resource: denial.resource,
+            resource_id: denial.resource_id,
+            action: denial.action,
+            created_at: denial.created_at,
+        }
+    }
+}
+
+/// Разбор решений системы доступа для администраторов
+pub struct AccessService;
+
+impl AccessService {
+    /// Объясняет, какое правило политики разрешает или запрещает пользователю действие.
+    /// `resource` - `вид:id`, например `post:<uuid>`; без id проверяется новый ресурс этого вида,
+    /// созданный самим пользователем
+    pub async fn explain(
+        db: &Db,
+        ctx: &Ctx,
+        user_id: &Uuid,
+        resource: &str,
+        action: &str,
+    ) -> Result<AccessExplanationDto> {
+        Self::check_admin(db, ctx).await?;
+
+        let action: Action = action
+            .parse()
+            .map_err(|e: lib_core::acs::Error| Error::BadRequest(e.to_string()))?;
+        let (kind, id) = parse_resource(resource)?;
+
+        let role = get_role(db, user_id).await?;
+        let (resource, community_role) = match id {
+            Some(id) => Self::load_resource(db, user_id, kind, &id).await?,
+            None => (new_resource(kind, user_id), None),
+        };
+
+        let explanation = AccessControl::explain(
+            role.clone(),
+            community_role,
+            &resource,
+            action,
+            Some(*user_id),
+        );
+
+        Ok(AccessExplanationDto {
+            user_id: *user_id,
+            role: format!("{role:?}").to_lowercase(),
+            community_role: community_role.map(|role| format!("{role:?}").to_lowercase()),
+            resource: kind.to_string(),
+            resource_id: resource.id(),
+            action: action.as_str().to_string(),
+            allowed: explanation.allowed,
+            reason: explanation.reason(),
+            matched_rule: explanation
+                .matched
+                .map(|rule| RuleDto::from_rule(rule, Vec::new())),
+            rejected_rules: explanation
+                .rejected
+                .into_iter()
+                .map(|(rule, failed)| RuleDto::from_rule(rule, failed))
+                .collect(),
+        })
+    }
+
+    /// Последние отказы в доступе, всех пользователей или одного
+    pub async fn get_denials(
+        db: &Db,
+        ctx: &Ctx,
+        user_id: Option<Uuid>,
+    ) -> Result<Vec<AccessDenialDto>> {
+        Self::check_admin(db, ctx).await?;
+
+        let denials = AccessDenialRepo::find_latest(db, user_id, DENIALS_LIMIT).await?;
+        Ok(denials.into_iter().map(AccessDenialDto::from).collect())
+    }
+
+    /// Ресурс так, как его видит система доступа при запросе от `user_id`
+    async fn load_resource(
+        db: &Db,
+        user_id: &Uuid,
+        kind: ResourceKind,
+        id: &Uuid,
+    ) -> Result<(Resource, Option<CommunityRole>)> {
+        let ctx = Ctx::new(*user_id);
+        let community_role = |community_id: Uuid| async move {
+            CommunityMemberService::get_role(db, &community_id, Some(*user_id)).await
+        };
+
+        let resource = match kind {
+            ResourceKind::User => (Resource::User(*id), None),
+            ResourceKind::Community => {
+                let community = find_community(db, id).await?;
+                (
+                    Resource::Community {
+                        id: community.id,
+                        owner_id: community.user_id,
+                    },
+                    community_role(community.id).await?,
+                )
+            }
+            ResourceKind::Post => {
+                let post = find_post(db, id).await?;
+                (
+                    Resource::Post {
+                        id: post.id,
+                        author_id: post.user_id,
+                    },
+                    community_role(post.community_id).await?,
+                )
+            }
+            ResourceKind::Comment => {
+                let comment = CommentRepo::find(
+                    db,
+                    CommentForSelect {
+                        id: Some(*id),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+                let post = find_post(db, &comment.post_id).await?;
+                (
+                    Resource::Comment {
+                        id: comment.id,
+                        author_id: comment.user_id,
+                    },
+                    community_role(post.community_id).await?,
+                )
+            }
+            ResourceKind::Chat => (ChatService::chat_resource(db, &ctx, id).await?, None),
+            ResourceKind::Message => {
+                let message = find_message(db, id).await?;
+                (
+                    ChatService::message_resource(db, &ctx, &message).await?,
+                    None,
+                )
+            }
+            ResourceKind::Report => {
+                let report = find_report(db, id).await?;
+                (
+                    Resource::Report {
+                        id: report.id,
+                        reporter_id: report.reporter_id,
+                    },
+                    None,
+                )
+            }
+        };
+
+        Ok(resource)
+    }
+
+    async fn check_admin(db: &Db, ctx: &Ctx) -> Result<()> {
+        let requester_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+        let role = requester_role(db, ctx).await?;
+        if role != Role::Admin {
+            return Err(access_denied(ctx, role, requester_id, Action::Read));
+        }
+
+        Ok(())
+    }
+}
+
+/// Скоуп персонального токена для действий, которые не проходят через политику доступа
+pub(crate) fn check_scope(ctx: &Ctx, resource: ScopeResource, access: ScopeAccess) -> Result<()> {
+    AccessControl::check_scope(ctx, Some(Scope::new(resource, access))).map_err(|e| {
+        warn!("{}", e.to_string());
+        Error::Core(e.into())
+    })
+}
+
+fn parse_resource(resource: &str) -> Result<(ResourceKind, Option<Uuid>)> {
+    let (kind, id) = match resource.split_once(':') {
+        Some((kind, id)) => (kind, Some(id)),
+        None => (resource, None),
+    };
+
+    let kind: ResourceKind = kind
+        .parse()
+        .map_err(|e: lib_core::acs::Error| Error::BadRequest(e.to_string()))?;
+    let id = id
+        .map(Uuid::parse_str)
+        .transpose()
+        .map_err(|_| Error::BadRequest(format!("Invalid resource id in '{resource}'")))?;
+
+    Ok((kind, id))
+}
+
+/// Ресурс, который пользователь только собирается создать: он его владелец и участник
+fn new_resource(kind: ResourceKind, user_id: &Uuid) -> Resource {
+    let id = Uuid::nil();
+    match kind {
+        ResourceKind::User => Resource::User(*user_id),
+        ResourceKind::Community => Resource::Community {
+            id,
+            owner_id: *user_id,
+        },
+        ResourceKind::Post => Resource::Post {
+            id,
+            author_id: *user_id,
+        },
+        ResourceKind::Comment => Resource::Comment {
+            id,
+            author_id: *user_id,
+        },
+        ResourceKind::Chat => Resource::Chat {
+            id,
+            owner_id: Some(*user_id),
+            is_member: true,
+        },
+        ResourceKind::Message => Resource::Message {
+            id,
+            sender_id: *user_id,
+            is_member: true,
+        },
+        ResourceKind::Report => Resource::Report {
+            id,
+            reporter_id: *user_id,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_core::model::role::RoleEnum;
+
+    use crate::services::test_utils::{create_user, delete_user, model_manager};
+    use crate::services::user_service::UserService;
+
+    #[tokio::test]
+    async fn test_explain_shows_deciding_rules() -> anyhow::Result<()> {
+        let mm = model_manager().await?;
+        let admin = create_user(&mm).await?;
+        UserService::update(
+            mm.db(),
+            None,
+            &admin.id,
+            None,
+            Some(RoleEnum::Admin),
+            None,
+            None,
+        )
+        .await?;
+        let user = create_user(&mm).await?;
+        let ctx = Ctx::new(admin.id);
+
+        // Автор может править свой пост
+        let explanation = AccessService::explain(mm.db(), &ctx, &user.id, "post", "update").await?;
+        assert!(explanation.allowed);
+        assert!(explanation.matched_rule.is_some());
+
+        let target = format!("user:{}", admin.id);
+        let explanation = AccessService::explain(mm.db(), &ctx, &user.id, &target, "ban").await?;
+        assert!(!explanation.allowed);
+        assert!(explanation.matched_rule.is_none());
+        assert_eq!(explanation.role, "user");
+        assert_eq!(explanation.resource_id, admin.id);
+
+        let result = AccessService::explain(mm.db(), &ctx, &user.id, "post", "fly").await;
+        assert!(matches!(result, Err(Error::BadRequest(_))));
+
+        // Объяснения доступны только администраторам
+        let result =
+            AccessService::explain(mm.db(), &Ctx::new(user.id), &admin.id, "post", "update").await;
+        assert!(matches!(result, Err(Error::Core(_))));
+
+        delete_user(&mm, &user).await?;
+        delete_user(&mm, &admin).await
+    }
+}
@@ -0,0 +1,181 @@
+use std::str::FromStr;
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use lib_auth::token::{generate_api_token, hash_api_token};
+use lib_core::acs::Scope;
+use lib_core::db::Db;
+use lib_core::model::api_token::{
+    ApiTokenForCreate, ApiTokenForDelete, ApiTokenForSelect, ApiTokenRepo,
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+#[derive(Debug, Serialize)]
+pub struct ApiTokenDto {
+    pub id: Uuid,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl ApiTokenDto {
+    pub fn from_api_token(token: ApiTokenRepo) -> Self {
+        Self {
+            id: token.id,
+            name: token.name,
+            scopes: token
+                .scopes
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            expires_at: token.expires_at,
+            last_used_at: token.last_used_at,
+            created_at: token.created_at,
+        }
+    }
+}
+
+/// Обертка для персональных токенов доступа (для ботов и скриптов)
+pub struct ApiTokenService;
+
+impl ApiTokenService {
+    /// Выпуск нового токена, сам токен возвращается только один раз
+    pub async fn create(
+        db: &Db,
+        requester_id: Option<Uuid>,
+        name: &str,
+        scopes: &[String],
+        expires_in_days: Option<i64>,
+    ) -> Result<(ApiTokenDto, String)> {
+        let user_id = requester_id.ok_or(Error::Unauthorized)?;
+
+        let scopes = Self::parse_scopes(scopes)?;
+        if scopes.is_empty() {
+            return Err(Error::BadRequest(
+                "At least one scope is required".to_string(),
+            ));
+        }
+
+        let token = generate_api_token();
+        let api_token = ApiTokenRepo::create(
+            db,
+            ApiTokenForCreate {
+                user_id,
+                name: name.to_string(),
+                token_hash: hash_api_token(&token),
+                scopes: scopes
+                    .iter()
+                    .map(Scope::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                expires_at: expires_in_days
+                    .map(|days| (Utc::now() + Duration::days(days)).naive_utc()),
+            },
+        )
+        .await?;
+
+        Ok((ApiTokenDto::from_api_token(api_token), token))
+    }
+
+    /// Получение всех токенов текущего пользователя
+    pub async fn get_all(db: &Db, requester_id: Option<Uuid>) -> Result<Vec<ApiTokenDto>> {
+        let user_id = requester_id.ok_or(Error::Unauthorized)?;
+
+        let mut tokens = ApiTokenRepo::find_all(
+            db,
+            ApiTokenForSelect {
+                user_id: Some(user_id),
+                ..Default::default()
+            },
+        )
+        .await?;
+        tokens.sort_by_key(|t| std::cmp::Reverse(t.created_at));
+
+        Ok(tokens
+            .into_iter()
+            .map(ApiTokenDto::from_api_token)
+            .collect())
+    }
+
+    /// Отзыв токена текущего пользователя
+    pub async fn delete(db: &Db, requester_id: Option<Uuid>, id: &Uuid) -> Result<()> {
+        let user_id = requester_id.ok_or(Error::Unauthorized)?;
+
+        ApiTokenRepo::delete(
+            db,
+            ApiTokenForDelete {
+                id: Some(*id),
+                user_id: Some(user_id),
+            },
+        )
+        .await
+        .map_err(Error::Core)
+    }
+
+    /// Отзыв всех токенов пользователя
+    pub async fn delete_all(db: &Db, user_id: &Uuid) -> Result<()> {
+        match ApiTokenRepo::delete(
+            db,
+            ApiTokenForDelete {
+                user_id: Some(*user_id),
+                ..Default::default()
+            },
+        )
+        .await
+        {
+            Ok(_) | Err(lib_core::error::Error::EntityNotFound) => Ok(()),
+            Err(e) => Err(Error::Core(e)),
+        }
+    }
+
+    /// Проверка токена из заголовка, возвращает владельца и разрешенные скоупы
+    pub async fn authenticate(db: &Db, token: &str) -> Result<(Uuid, Vec<Scope>)> {
+        let api_token = match ApiTokenRepo::find(
+            db,
+            ApiTokenForSelect {
+                token_hash: Some(hash_api_token(token)),
+                ..Default::default()
+            },
+        )
+        .await
+        {
+            Ok(api_token) => api_token,
+            Err(lib_core::error::Error::EntityNotFound) => return Err(Error::Unauthorized),
+            Err(e) => return Err(Error::Core(e)),
+        };
+
+        if api_token
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now().naive_utc())
+        {
+            return Err(Error::Unauthorized);
+        }
+
+        ApiTokenRepo::touch(db, &api_token.id).await?;
+
+        let scopes = api_token
+            .scopes
+            .split_whitespace()
+            .filter_map(|scope| Scope::from_str(scope).ok())
+            .collect();
+
+        Ok((api_token.user_id, scopes))
+    }
+
+    fn parse_scopes(scopes: &[String]) -> Result<Vec<Scope>> {
+        let mut parsed = Vec::new();
+        for scope in scopes {
+            let scope =
+                Scope::from_str(scope.trim()).map_err(|e| Error::BadRequest(e.to_string()))?;
+            if !parsed.contains(&scope) {
+                parsed.push(scope);
+            }
+        }
+
+        Ok(parsed)
+    }
+}
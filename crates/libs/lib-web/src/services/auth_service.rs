@@ -1,22 +1,48 @@
 use crate::{
     error::{Error, Result},
-    services::user_service::UserService,
+    extractors::ClientInfo,
+    services::{
+        api_token_service::ApiTokenService, ban_service::BanService,
+        login_throttle_service::LoginThrottleService, mail_service::MailService,
+        oidc_service::OidcService, revocation_service::RevocationService,
+        session_service::SessionService, two_factor_service::TwoFactorService,
+        user_service::UserService,
+    },
     utils::token::generate_tokens_for_auth,
 };
 use axum_extra::extract::CookieJar;
+use chrono::{Duration, Utc};
 use lib_auth::{
-    pwd::{hash_password, validate_password},
-    token::{verify_token, TokenType},
+    pwd::{check_password_policy, hash_password, needs_rehash, validate_password},
+    token::{
+        check_rotation, generate_opaque_token, generate_token, verify_token, Rotation, TokenType,
+    },
 };
+use lib_core::ctx::Ctx;
+use lib_core::model::session::{SessionForCreate, SessionForDelete, SessionForSelect, SessionRepo};
 use lib_core::model::token::{
-    Token, TokenForCreate, TokenForDelete, TokenForSelect, TokenForUpdate, TokenTypeEnum,
+    Token, TokenForCreate, TokenForDelete, TokenForSelect, TokenTypeEnum,
 };
 use lib_core::model::ModelManager;
 use std::{str::FromStr as _, sync::Arc};
+use tracing::warn;
 use uuid::Uuid;
 
 use crate::services::user_service::UserDto;
-use crate::utils::cookies::{remove_cookie_from_jar, set_refresh_cookie};
+use crate::utils::cookies::{remove_refresh_cookie, set_refresh_cookie, REFRESH_COOKIE};
+
+const EMAIL_VERIFICATION_TTL_HOURS: i64 = 24;
+const EMAIL_CHANGE_TTL_HOURS: i64 = 24;
+const MAGIC_LINK_TTL_MIN: i64 = 15;
+const MAGIC_LINK_WINDOW_SEC: usize = 15 * 60;
+const MAGIC_LINK_EMAIL_REQUESTS: u64 = 5;
+const MAGIC_LINK_IP_REQUESTS: u64 = 20;
+
+pub enum LoginOutcome {
+    Authenticated(Box<UserDto>, String, CookieJar),
+    /// Пароль верный, но нужен второй фактор: возвращается токен-вызов
+    TwoFactorRequired(String),
+}
 
 pub struct AuthService;
 
@@ -24,6 +50,7 @@ impl AuthService {
     pub async fn register(
         mm: Arc<ModelManager>,
         jar: CookieJar,
+        client: ClientInfo,
         nickname: &str,
         email: &str,
         password: &str,
@@ -35,94 +62,817 @@ impl AuthService {
             return Err(Error::UserAlreadyExists);
         }
 
+        check_password_policy(password, &[nickname, email])?;
+
         let hashed = hash_password(password)?;
         let user = UserService::create(mm.db(), None, nickname, email, &hashed).await?;
 
-        Self::authenticate_user(mm, jar, user).await
+        // Пользователь уже создан: письмо можно запросить повторно, вход не должен падать
+        if let Err(e) = Self::issue_email_verification(&mm, &user).await {
+            warn!(
+                "Failed to send email verification to user {}: {:?}",
+                user.id, e
+            );
+        }
+
+        Self::authenticate_user(mm, jar, &client, user).await
     }
 
     pub async fn login(
         mm: Arc<ModelManager>,
         jar: CookieJar,
+        client: ClientInfo,
         nickname: &str,
         password: &str,
-    ) -> Result<(UserDto, String, CookieJar)> {
-        let user = UserService::get_by_nickname(mm.db(), None, nickname).await?;
+    ) -> Result<LoginOutcome> {
+        let ip = client.ip.as_deref();
+        LoginThrottleService::check(mm.cache(), nickname, ip).await?;
+
+        let user = match UserService::get_by_nickname(mm.db(), None, nickname).await {
+            Ok(user) => user,
+            Err(e) => {
+                if matches!(e, Error::Core(lib_core::error::Error::EntityNotFound)) {
+                    LoginThrottleService::record_failure(mm.cache(), nickname, ip).await?;
+                }
+                return Err(e);
+            }
+        };
 
         if !validate_password(password, &user.hashed_password)? {
+            LoginThrottleService::record_failure(mm.cache(), nickname, ip).await?;
             return Err(Error::WrongPassword);
         }
 
-        Self::authenticate_user(mm, jar, user).await
+        LoginThrottleService::reset(mm.cache(), nickname).await?;
+
+        if needs_rehash(&user.hashed_password) {
+            Self::rehash_password(&mm, &user.id, password).await;
+        }
+
+        Self::complete_login(mm, jar, &client, user).await
+    }
+
+    /// Перехеширование пароля со старыми параметрами argon2, пока известен сам пароль.
+    /// Ошибка не должна ломать вход, поэтому только логируется
+    async fn rehash_password(mm: &ModelManager, user_id: &Uuid, password: &str) {
+        let result = match hash_password(password) {
+            Ok(hashed) => UserService::update(
+                mm.db(),
+                Some(*user_id),
+                user_id,
+                None,
+                None,
+                Some(hashed),
+                None,
+            )
+            .await
+            .map(|_| ()),
+            Err(e) => Err(e.into()),
+        };
+
+        if let Err(e) = result {
+            warn!("Failed to rehash password of user {}: {:?}", user_id, e);
+        }
+    }
+
+    pub async fn oidc_login(
+        mm: Arc<ModelManager>,
+        jar: CookieJar,
+        client: ClientInfo,
+        provider: &str,
+        code: &str,
+        state: &str,
+    ) -> Result<LoginOutcome> {
+        let user = OidcService::authenticate(mm.db(), mm.cache(), provider, code, state).await?;
+
+        Self::complete_login(mm, jar, &client, user).await
+    }
+
+    async fn complete_login(
+        mm: Arc<ModelManager>,
+        jar: CookieJar,
+        client: &ClientInfo,
+        user: UserDto,
+    ) -> Result<LoginOutcome> {
+        BanService::check(&mm, &user).await?;
+
+        if TwoFactorService::is_enabled(mm.db(), &user.id).await? {
+            let challenge_token = TwoFactorService::issue_challenge(mm.cache(), &user.id).await?;
+            return Ok(LoginOutcome::TwoFactorRequired(challenge_token));
+        }
+
+        let (user, access_token, jar) = Self::authenticate_user(mm, jar, client, user).await?;
+        Ok(LoginOutcome::Authenticated(
+            Box::new(user),
+            access_token,
+            jar,
+        ))
+    }
+
+    /// Отправка одноразовой ссылки для входа без пароля
+    pub async fn request_magic_link(
+        mm: Arc<ModelManager>,
+        client: &ClientInfo,
+        email: &str,
+    ) -> Result<()> {
+        // Чтобы через форму нельзя было заваливать почту. Счетчики отдельные от входа
+        // по паролю: иначе запросы ссылок блокировали бы вход всем за тем же IP
+        LoginThrottleService::limit_requests(
+            mm.cache(),
+            &format!("magic:email:{}", email),
+            MAGIC_LINK_EMAIL_REQUESTS,
+            MAGIC_LINK_WINDOW_SEC,
+        )
+        .await?;
+        if let Some(ip) = client.ip.as_deref() {
+            LoginThrottleService::limit_requests(
+                mm.cache(),
+                &format!("magic:ip:{}", ip),
+                MAGIC_LINK_IP_REQUESTS,
+                MAGIC_LINK_WINDOW_SEC,
+            )
+            .await?;
+        }
+
+        // Не раскрываем, зарегистрирован ли email
+        let user = match UserService::get_by_email(mm.db(), None, email).await {
+            Ok(user) => user,
+            Err(Error::Core(lib_core::error::Error::EntityNotFound)) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        match Token::delete(
+            mm.db(),
+            TokenForDelete {
+                user_id: Some(user.id),
+                token_type: Some(TokenTypeEnum::MagicLink),
+                ..Default::default()
+            },
+        )
+        .await
+        {
+            Ok(_) | Err(lib_core::error::Error::EntityNotFound) => {}
+            Err(e) => return Err(Error::Core(e)),
+        }
+
+        let token = Token::create(
+            mm.db(),
+            TokenForCreate {
+                user_id: user.id,
+                token: generate_opaque_token(),
+                token_type: TokenTypeEnum::MagicLink,
+                payload: None,
+            },
+        )
+        .await?;
+
+        MailService::send_magic_link(&mm, &user, &token.token).await
+    }
+
+    /// Проверка ссылки без погашения. Почтовые сканеры открывают ссылки из писем,
+    /// поэтому GET только показывает страницу подтверждения, а входит POST
+    pub async fn check_magic_link(mm: Arc<ModelManager>, token: &str) -> Result<()> {
+        let token = Self::find_magic_link(&mm, token).await?;
+        if magic_link_expired(&token) {
+            return Err(Error::InvalidToken);
+        }
+
+        Ok(())
+    }
+
+    /// Вход по ссылке из письма. Дальше все как при обычном входе, включая второй фактор
+    pub async fn magic_link_login(
+        mm: Arc<ModelManager>,
+        jar: CookieJar,
+        client: ClientInfo,
+        token: &str,
+    ) -> Result<LoginOutcome> {
+        let token = Self::find_magic_link(&mm, token).await?;
+
+        // Токен одноразовый: удаление из хранилища и есть его погашение.
+        // Если параллельный запрос успел раньше, ссылка уже использована
+        Token::delete(
+            mm.db(),
+            TokenForDelete {
+                token: Some(token.token.clone()),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|e| match e {
+            lib_core::error::Error::EntityNotFound => Error::InvalidToken,
+            e => Error::Core(e),
+        })?;
+
+        if magic_link_expired(&token) {
+            return Err(Error::InvalidToken);
+        }
+
+        let mut user = UserService::get_by_id(mm.db(), None, &token.user_id).await?;
+        // Переход по ссылке из письма подтверждает владение адресом
+        if !user.email_verified {
+            user = UserService::set_email_verified(mm.db(), &user.id).await?;
+        }
+
+        Self::complete_login(mm, jar, &client, user).await
+    }
+
+    async fn find_magic_link(mm: &ModelManager, token: &str) -> Result<Token> {
+        Token::find(
+            mm.db(),
+            TokenForSelect {
+                token: Some(token.to_string()),
+                token_type: Some(TokenTypeEnum::MagicLink),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|_| Error::InvalidToken)
+    }
+
+    pub async fn verify_two_factor(
+        mm: Arc<ModelManager>,
+        jar: CookieJar,
+        client: ClientInfo,
+        challenge_token: &str,
+        code: &str,
+    ) -> Result<(UserDto, String, CookieJar)> {
+        let user_id = TwoFactorService::check_challenge(mm.cache(), challenge_token).await?;
+
+        let account = format!("2fa:{}", user_id);
+        let ip = client.ip.as_deref();
+        LoginThrottleService::check(mm.cache(), &account, ip).await?;
+
+        match TwoFactorService::verify(mm.db(), &user_id, code).await {
+            Err(Error::InvalidTwoFactorCode) => {
+                LoginThrottleService::record_failure(mm.cache(), &account, ip).await?;
+                return Err(Error::InvalidTwoFactorCode);
+            }
+            result => result?,
+        }
+        LoginThrottleService::reset(mm.cache(), &account).await?;
+        TwoFactorService::consume_challenge(mm.cache(), challenge_token).await?;
+
+        let user = UserService::get_by_id(mm.db(), Some(user_id), &user_id).await?;
+        BanService::check(&mm, &user).await?;
+
+        Self::authenticate_user(mm, jar, &client, user).await
     }
 
-    pub async fn logout(mm: Arc<ModelManager>, jar: CookieJar) -> Result<CookieJar> {
-        let token_cookie = jar.get("refreshToken").ok_or(Error::MissingTokenCookie)?;
+    pub async fn logout(
+        mm: Arc<ModelManager>,
+        jar: CookieJar,
+        access_token: Option<&str>,
+    ) -> Result<CookieJar> {
+        let token_cookie = jar.get(REFRESH_COOKIE).ok_or(Error::MissingTokenCookie)?;
         let token = token_cookie.value().to_string();
 
-        let new_jar = remove_cookie_from_jar(jar, "refreshToken");
-        Token::delete(mm.db(), TokenForDelete { token }).await?;
+        if let Some(Ok(token_data)) = access_token.map(|t| verify_token(t, TokenType::Access)) {
+            RevocationService::revoke_token(mm.cache(), &token_data.claims).await?;
+        }
+
+        let new_jar = remove_refresh_cookie(jar);
+        SessionRepo::delete(
+            mm.db(),
+            SessionForDelete {
+                refresh_token: Some(token),
+                ..Default::default()
+            },
+        )
+        .await?;
         Ok(new_jar)
     }
 
+    pub async fn logout_all(mm: Arc<ModelManager>, ctx: Ctx, jar: CookieJar) -> Result<CookieJar> {
+        let user_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+
+        SessionService::delete_all(mm.db(), &user_id).await?;
+        RevocationService::revoke_user(mm.cache(), &user_id).await?;
+        Ok(remove_refresh_cookie(jar))
+    }
+
+    pub async fn verify_email(mm: Arc<ModelManager>, token: &str) -> Result<UserDto> {
+        let token = Token::find(
+            mm.db(),
+            TokenForSelect {
+                token: Some(token.to_string()),
+                token_type: Some(TokenTypeEnum::EmailVerification),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|_| Error::InvalidToken)?;
+
+        Token::delete(
+            mm.db(),
+            TokenForDelete {
+                token: Some(token.token),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let expires_at = token.created_at + Duration::hours(EMAIL_VERIFICATION_TTL_HOURS);
+        if expires_at < Utc::now().naive_utc() {
+            return Err(Error::InvalidToken);
+        }
+
+        UserService::set_email_verified(mm.db(), &token.user_id).await
+    }
+
+    pub async fn resend_email_verification(mm: Arc<ModelManager>, ctx: Ctx) -> Result<()> {
+        let user_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+        let user = UserService::get_by_id(mm.db(), ctx.user_id, &user_id).await?;
+
+        if user.email_verified {
+            return Err(Error::BadRequest("Email is already verified".into()));
+        }
+
+        Self::issue_email_verification(&mm, &user).await
+    }
+
+    async fn issue_email_verification(mm: &ModelManager, user: &UserDto) -> Result<()> {
+        match Token::delete(
+            mm.db(),
+            TokenForDelete {
+                user_id: Some(user.id),
+                token_type: Some(TokenTypeEnum::EmailVerification),
+                ..Default::default()
+            },
+        )
+        .await
+        {
+            Ok(_) | Err(lib_core::error::Error::EntityNotFound) => {}
+            Err(e) => return Err(Error::Core(e)),
+        }
+
+        let token = Token::create(
+            mm.db(),
+            TokenForCreate {
+                user_id: user.id,
+                token: generate_opaque_token(),
+                token_type: TokenTypeEnum::EmailVerification,
+                payload: None,
+            },
+        )
+        .await?;
+
+        MailService::send_email_verification(mm, user, &token.token).await
+    }
+
+    pub async fn forgot_password(mm: Arc<ModelManager>, email: &str) -> Result<()> {
+        // Не раскрываем, зарегистрирован ли email
+        let user = match UserService::get_by_email(mm.db(), None, email).await {
+            Ok(user) => user,
+            Err(Error::Core(lib_core::error::Error::EntityNotFound)) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        match Token::delete(
+            mm.db(),
+            TokenForDelete {
+                user_id: Some(user.id),
+                token_type: Some(TokenTypeEnum::ResetPassword),
+                ..Default::default()
+            },
+        )
+        .await
+        {
+            Ok(_) | Err(lib_core::error::Error::EntityNotFound) => {}
+            Err(e) => return Err(Error::Core(e)),
+        }
+
+        let token = Token::create(
+            mm.db(),
+            TokenForCreate {
+                user_id: user.id,
+                token: generate_token(&user.id.to_string(), TokenType::ResetPassword)?,
+                token_type: TokenTypeEnum::ResetPassword,
+                payload: None,
+            },
+        )
+        .await?;
+
+        MailService::send_password_reset(&mm, &user, &token.token).await
+    }
+
+    pub async fn reset_password(mm: Arc<ModelManager>, token: &str, password: &str) -> Result<()> {
+        let token_data =
+            verify_token(token, TokenType::ResetPassword).map_err(|_| Error::InvalidToken)?;
+        let user_id = Uuid::from_str(&token_data.claims.sub).map_err(|_| Error::InvalidToken)?;
+
+        // Проверка до погашения токена, чтобы слабый пароль не сжигал ссылку
+        let user = UserService::get_by_id(mm.db(), Some(user_id), &user_id).await?;
+        check_password_policy(password, &[&user.nickname, &user.email])?;
+
+        // Токен одноразовый: удаление из хранилища и есть его погашение
+        Token::delete(
+            mm.db(),
+            TokenForDelete {
+                token: Some(token.to_string()),
+                user_id: Some(user_id),
+                token_type: Some(TokenTypeEnum::ResetPassword),
+            },
+        )
+        .await
+        .map_err(|e| match e {
+            lib_core::error::Error::EntityNotFound => Error::InvalidToken,
+            e => Error::Core(e),
+        })?;
+
+        Self::set_password(&mm, &user_id, password).await?;
+
+        // Сброс значит, что доступ к аккаунту мог быть утерян, поэтому отзываются и
+        // персональные токены
+        ApiTokenService::delete_all(mm.db(), &user_id).await
+    }
+
+    pub async fn change_password(
+        mm: Arc<ModelManager>,
+        ctx: Ctx,
+        jar: CookieJar,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<CookieJar> {
+        let user_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+        let user = UserService::get_by_id(mm.db(), ctx.user_id, &user_id).await?;
+
+        if !validate_password(current_password, &user.hashed_password)? {
+            return Err(Error::WrongPassword);
+        }
+        if current_password == new_password {
+            return Err(Error::BadRequest(
+                "New password matches the current one".into(),
+            ));
+        }
+        check_password_policy(new_password, &[&user.nickname, &user.email])?;
+
+        Self::set_password(&mm, &user_id, new_password).await?;
+
+        Ok(remove_refresh_cookie(jar))
+    }
+
+    /// Смена хеша пароля с выходом со всех устройств
+    async fn set_password(mm: &ModelManager, user_id: &Uuid, password: &str) -> Result<()> {
+        let hashed = hash_password(password)?;
+        UserService::update(
+            mm.db(),
+            Some(*user_id),
+            user_id,
+            None,
+            None,
+            Some(hashed),
+            None,
+        )
+        .await?;
+
+        SessionService::delete_all(mm.db(), user_id).await?;
+        RevocationService::revoke_user(mm.cache(), user_id).await
+    }
+
+    pub async fn request_email_change(
+        mm: Arc<ModelManager>,
+        ctx: Ctx,
+        new_email: &str,
+        password: &str,
+    ) -> Result<()> {
+        let user_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+        let user = UserService::get_by_id(mm.db(), ctx.user_id, &user_id).await?;
+
+        if !validate_password(password, &user.hashed_password)? {
+            return Err(Error::WrongPassword);
+        }
+        if user.email == new_email {
+            return Err(Error::BadRequest(
+                "New email matches the current one".into(),
+            ));
+        }
+        Self::ensure_email_available(&mm, new_email).await?;
+
+        match Token::delete(
+            mm.db(),
+            TokenForDelete {
+                user_id: Some(user.id),
+                token_type: Some(TokenTypeEnum::ResetEmail),
+                ..Default::default()
+            },
+        )
+        .await
+        {
+            Ok(_) | Err(lib_core::error::Error::EntityNotFound) => {}
+            Err(e) => return Err(Error::Core(e)),
+        }
+
+        let token = Token::create(
+            mm.db(),
+            TokenForCreate {
+                user_id: user.id,
+                token: generate_opaque_token(),
+                token_type: TokenTypeEnum::ResetEmail,
+                payload: Some(new_email.to_string()),
+            },
+        )
+        .await?;
+
+        MailService::send_email_change_confirmation(&mm, &user, new_email, &token.token).await?;
+        MailService::send_email_change_notice(&mm, &user, new_email).await
+    }
+
+    pub async fn confirm_email_change(mm: Arc<ModelManager>, token: &str) -> Result<UserDto> {
+        let token = Token::find(
+            mm.db(),
+            TokenForSelect {
+                token: Some(token.to_string()),
+                token_type: Some(TokenTypeEnum::ResetEmail),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|_| Error::InvalidToken)?;
+
+        Token::delete(
+            mm.db(),
+            TokenForDelete {
+                token: Some(token.token),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let expires_at = token.created_at + Duration::hours(EMAIL_CHANGE_TTL_HOURS);
+        if expires_at < Utc::now().naive_utc() {
+            return Err(Error::InvalidToken);
+        }
+        let new_email = token.payload.ok_or(Error::InvalidToken)?;
+
+        // Адрес мог быть занят, пока письмо ждало подтверждения
+        Self::ensure_email_available(&mm, &new_email).await?;
+
+        UserService::update_email(mm.db(), &token.user_id, &new_email).await
+    }
+
+    async fn ensure_email_available(mm: &ModelManager, email: &str) -> Result<()> {
+        match UserService::get_by_email(mm.db(), None, email).await {
+            Ok(_) => Err(Error::EmailAlreadyTaken),
+            Err(Error::Core(lib_core::error::Error::EntityNotFound)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
     pub async fn refresh(
         mm: Arc<ModelManager>,
         jar: CookieJar,
+        client: ClientInfo,
     ) -> Result<(UserDto, String, CookieJar)> {
-        let token_cookie = jar.get("refreshToken").ok_or(Error::MissingTokenCookie)?;
+        let token_cookie = jar.get(REFRESH_COOKIE).ok_or(Error::MissingTokenCookie)?;
         let token = token_cookie.value().to_string();
 
-        let token_data = verify_token(&token, TokenType::Refresh).map_err(|e| {
-            let _ = remove_cookie_from_jar(jar.clone(), "refreshToken");
-            e
-        })?;
+        let claims = verify_token(&token, TokenType::Refresh)?.claims;
+
+        let user_id = Uuid::from_str(&claims.sub)?;
+        let session_id = claims
+            .sid
+            .as_deref()
+            .and_then(|sid| Uuid::from_str(sid).ok())
+            .ok_or(Error::Unauthorized)?;
+
+        let session = SessionRepo::find(
+            mm.db(),
+            SessionForSelect {
+                id: Some(session_id),
+                user_id: Some(user_id),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|_| Error::Unauthorized)?;
+
+        match check_rotation(&claims, session.generation) {
+            Rotation::Current => {}
+            Rotation::Reused => {
+                // Старый токен предъявлен повторно: считаем семейство скомпрометированным
+                warn!(
+                    "Refresh token reuse detected for session {}, revoking it",
+                    session.id
+                );
+                SessionRepo::delete(
+                    mm.db(),
+                    SessionForDelete {
+                        id: Some(session.id),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+                return Err(Error::TokenReused);
+            }
+            Rotation::Unknown => return Err(Error::Unauthorized),
+        }
 
-        let user_id = Uuid::from_str(&token_data.claims.sub)?;
         let user = UserService::get_by_id(mm.db(), Some(user_id), &user_id).await?;
+        BanService::check(&mm, &user).await?;
+
+        let (access_token, refresh_token) =
+            generate_tokens_for_auth(&user, &session.id, session.generation + 1)?;
+        SessionRepo::rotate(
+            mm.db(),
+            &session.id,
+            session.generation,
+            &refresh_token,
+            client.user_agent.as_deref(),
+            client.ip.as_deref(),
+        )
+        .await
+        .map_err(|e| match e {
+            lib_core::error::Error::EntityNotFound => Error::Unauthorized,
+            e => Error::Core(e),
+        })?;
 
-        Self::authenticate_user(mm, jar, user).await
+        Ok((user, access_token, set_refresh_cookie(jar, &refresh_token)))
     }
 
     async fn authenticate_user(
         mm: Arc<ModelManager>,
         jar: CookieJar,
+        client: &ClientInfo,
         user: UserDto,
     ) -> Result<(UserDto, String, CookieJar)> {
-        let (access_token, refresh_token) = generate_tokens_for_auth(&user)?;
+        let session_id = Uuid::new_v4();
+        let (access_token, refresh_token) = generate_tokens_for_auth(&user, &session_id, 0)?;
+
+        SessionRepo::create(
+            mm.db(),
+            SessionForCreate {
+                id: session_id,
+                user_id: user.id,
+                refresh_token: refresh_token.clone(),
+                user_agent: client.user_agent.clone(),
+                ip: client.ip.clone(),
+            },
+        )
+        .await?;
+
         let new_jar = set_refresh_cookie(jar, &refresh_token);
+        Ok((user, access_token, new_jar))
+    }
+}
+
+fn magic_link_expired(token: &Token) -> bool {
+    token.created_at + Duration::minutes(MAGIC_LINK_TTL_MIN) < Utc::now().naive_utc()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_core::model::totp::{TotpForSelect, TotpRepo};
+
+    use crate::services::test_utils::{
+        create_user, delete_user, model_manager, model_manager_with_mailer,
+    };
 
-        match Token::find(
+    #[tokio::test]
+    async fn test_reused_refresh_token_revokes_family() -> anyhow::Result<()> {
+        let mm = model_manager().await?;
+        let user = create_user(&mm).await?;
+        let client = ClientInfo::default();
+
+        let (_, _, first_jar) =
+            AuthService::authenticate_user(mm.clone(), CookieJar::new(), &client, user.clone())
+                .await?;
+        let (_, _, rotated_jar) =
+            AuthService::refresh(mm.clone(), first_jar.clone(), client.clone()).await?;
+
+        let replay = AuthService::refresh(mm.clone(), first_jar, client.clone()).await;
+        assert!(matches!(replay, Err(Error::TokenReused)));
+
+        // Токен, выданный при ротации, погашен вместе со всем семейством
+        let latest = AuthService::refresh(mm.clone(), rotated_jar, client).await;
+        assert!(matches!(latest, Err(Error::Unauthorized)));
+        let sessions = SessionRepo::find_all(
             mm.db(),
-            TokenForSelect {
+            SessionForSelect {
                 user_id: Some(user.id),
                 ..Default::default()
             },
         )
-        .await
-        {
-            Ok(token) => {
-                let _ = Token::update(
-                    mm.db(),
-                    &token.id,
-                    TokenForUpdate {
-                        token: refresh_token,
-                        token_type: TokenTypeEnum::Refresh,
-                    },
-                )
-                .await;
-            }
-            Err(_) => {
-                let _ = Token::create(
-                    mm.db(),
-                    TokenForCreate {
-                        user_id: user.id,
-                        token: refresh_token,
-                        token_type: TokenTypeEnum::Refresh,
-                    },
-                )
+        .await?;
+        assert!(sessions.is_empty());
+
+        delete_user(&mm, &user).await
+    }
+
+    #[tokio::test]
+    async fn test_two_factor_challenge_is_single_use() -> anyhow::Result<()> {
+        let mm = model_manager().await?;
+        let user = create_user(&mm).await?;
+        let client = ClientInfo::default();
+
+        let setup = TwoFactorService::setup(mm.db(), Some(user.id)).await?;
+        let step = Utc::now().timestamp() as u64 / lib_auth::totp::STEP_SEC;
+        let code = lib_auth::totp::generate_code(&setup.secret, step)?;
+        let recovery_codes = TwoFactorService::confirm(mm.db(), Some(user.id), &code).await?;
+
+        let stored = TotpRepo::find(
+            mm.db(),
+            TotpForSelect {
+                user_id: Some(user.id),
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert!(!stored.secret.contains(&setup.secret));
+
+        let challenge = TwoFactorService::issue_challenge(mm.cache(), &user.id).await?;
+
+        // Опечатка не сжигает вызов
+        let typo = AuthService::verify_two_factor(
+            mm.clone(),
+            CookieJar::new(),
+            client.clone(),
+            &challenge,
+            "00000-00000",
+        )
+        .await;
+        assert!(matches!(typo, Err(Error::InvalidTwoFactorCode)));
+
+        AuthService::verify_two_factor(
+            mm.clone(),
+            CookieJar::new(),
+            client.clone(),
+            &challenge,
+            &recovery_codes[0],
+        )
+        .await?;
+
+        let replay = AuthService::verify_two_factor(
+            mm.clone(),
+            CookieJar::new(),
+            client,
+            &challenge,
+            &recovery_codes[1],
+        )
+        .await;
+        assert!(matches!(replay, Err(Error::InvalidToken)));
+
+        delete_user(&mm, &user).await
+    }
+
+    #[tokio::test]
+    async fn test_magic_link_is_consumed_only_on_confirm() -> anyhow::Result<()> {
+        let (mm, mailer) = model_manager_with_mailer().await?;
+        let user = create_user(&mm).await?;
+        let client = ClientInfo::default();
+
+        AuthService::request_magic_link(mm.clone(), &client, &user.email).await?;
+        let mail = mailer
+            .last_to(&user.email)
+            .expect("sign-in link was not sent");
+        let token = mail
+            .body
+            .split("/magic-link/")
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("no link in the mail")
+            .to_string();
+
+        // Открытие ссылки сканером не должно ее сжигать
+        AuthService::check_magic_link(mm.clone(), &token).await?;
+        AuthService::check_magic_link(mm.clone(), &token).await?;
+
+        let outcome =
+            AuthService::magic_link_login(mm.clone(), CookieJar::new(), client.clone(), &token)
                 .await?;
-            }
+        assert!(matches!(outcome, LoginOutcome::Authenticated(..)));
+
+        let reuse =
+            AuthService::magic_link_login(mm.clone(), CookieJar::new(), client, &token).await;
+        assert!(matches!(reuse, Err(Error::InvalidToken)));
+        let check = AuthService::check_magic_link(mm.clone(), &token).await;
+        assert!(matches!(check, Err(Error::InvalidToken)));
+
+        delete_user(&mm, &user).await
+    }
+
+    #[tokio::test]
+    async fn test_magic_link_requests_are_throttled() -> anyhow::Result<()> {
+        let (mm, mailer) = model_manager_with_mailer().await?;
+        let user = create_user(&mm).await?;
+        let client = ClientInfo {
+            ip: Some(format!("test-{}", Uuid::new_v4())),
+            ..Default::default()
         };
-        Ok((user, access_token, new_jar))
+
+        let mut results = Vec::new();
+        for _ in 0..25 {
+            results.push(AuthService::request_magic_link(mm.clone(), &client, &user.email).await);
+        }
+        assert!(results
+            .iter()
+            .any(|result| matches!(result, Err(Error::TooManyAttempts(_)))));
+        assert_eq!(mailer.sent().len(), MAGIC_LINK_EMAIL_REQUESTS as usize);
+
+        // Вход по паролю с того же IP не заблокирован
+        LoginThrottleService::check(mm.cache(), &user.nickname, client.ip.as_deref()).await?;
+
+        delete_user(&mm, &user).await
     }
 }
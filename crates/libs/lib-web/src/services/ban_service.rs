@@ -0,0 +1,330 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use lib_core::acs::{AccessControl, Action, Resource, Role, ScopeAccess, ScopeResource};
+use lib_core::cache::{redis_fns, Cache};
+use lib_core::ctx::Ctx;
+use lib_core::model::ban::{BanForCreate, BanForSelect, BanRepo};
+use lib_core::model::role::RoleEnum;
+use lib_core::model::ModelManager;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::services::{
+    access_service::check_scope, api_token_service::ApiTokenService, post_service::requester_role,
+    revocation_service::RevocationService, session_service::SessionService, user_service::UserDto,
+    user_service::UserService,
+};
+
+const BANNED_USER_PREFIX: &str = "ban:user:";
+
+#[derive(Debug, Serialize)]
+pub struct BanDto {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub issued_by: Option<Uuid>,
+    pub reason: String,
+    pub starts_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+    pub lifted_at: Option<NaiveDateTime>,
+    pub lifted_by: Option<Uuid>,
+    pub is_active: bool,
+}
+
+impl BanDto {
+    pub fn from_ban(ban: BanRepo) -> Self {
+        let is_active = ban.lifted_at.is_none()
+            && ban
+                .expires_at
+                .is_none_or(|expires_at| expires_at > Utc::now().naive_utc());
+
+        Self {
+            id: ban.id,
+            user_id: ban.user_id,
+            issued_by: ban.issued_by,
+            reason: ban.reason,
+            starts_at: ban.starts_at,
+            expires_at: ban.expires_at,
+            lifted_at: ban.lifted_at,
+            lifted_by: ban.lifted_by,
+            is_active,
+        }
+    }
+}
+
+/// Обертка для блокировок пользователей с причиной и сроком
+pub struct BanService;
+
+impl BanService {
+    /// Блокировка пользователя, без срока блокировка бессрочная
+    pub async fn ban(
+        mm: &ModelManager,
+        ctx: &Ctx,
+        user: &UserDto,
+        reason: &str,
+        duration_hours: Option<i64>,
+    ) -> Result<BanDto> {
+        let requester_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+        if requester_id == user.id {
+            return Err(Error::BadRequest("You cannot ban yourself".to_string()));
+        }
+        Self::check_can_ban(mm, ctx, user).await?;
+
+        let ban = BanRepo::create(
+            mm.db(),
+            BanForCreate {
+                user_id: user.id,
+                issued_by: requester_id,
+                reason: reason.to_string(),
+                expires_at: duration_hours
+                    .map(|hours| (Utc::now() + Duration::hours(hours)).naive_utc()),
+            },
+        )
+        .await?;
+
+        UserService::update(mm.db(), None, &user.id, None, None, None, Some(true)).await?;
+
+        // Кешируется самая длинная из действующих блокировок, ее и проверяет middleware
+        if let Some(active) = BanRepo::find_active(mm.db(), &user.id).await? {
+            Self::cache_ban(mm.cache(), &active).await?;
+        }
+
+        SessionService::delete_all(mm.db(), &user.id).await?;
+        ApiTokenService::delete_all(mm.db(), &user.id).await?;
+        RevocationService::revoke_user(mm.cache(), &user.id).await?;
+
+        Ok(BanDto::from_ban(ban))
+    }
+
+    /// Досрочное снятие всех действующих блокировок пользователя
+    pub async fn lift(mm: &ModelManager, ctx: &Ctx, user: &UserDto) -> Result<()> {
+        let requester_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+        Self::check_can_ban(mm, ctx, user).await?;
+
+        BanRepo::lift(mm.db(), &user.id, &requester_id).await?;
+        Self::clear(mm, &user.id).await
+    }
+
+    /// История блокировок пользователя, доступна только администраторам
+    pub async fn get_history(mm: &ModelManager, ctx: &Ctx, user: &UserDto) -> Result<Vec<BanDto>> {
+        ctx.user_id.ok_or(Error::Unauthorized)?;
+        check_scope(ctx, ScopeResource::Users, ScopeAccess::Read)?;
+        let role = requester_role(mm.db(), ctx).await?;
+        if role != Role::Admin {
+            return Err(access_denied(ctx, role, user.id, Action::Read));
+        }
+
+        let mut bans = BanRepo::find_all(
+            mm.db(),
+            BanForSelect {
+                user_id: Some(user.id),
+            },
+        )
+        .await?;
+        bans.sort_by_key(|ban| std::cmp::Reverse(ban.starts_at));
+
+        Ok(bans.into_iter().map(BanDto::from_ban).collect())
+    }
+
+    /// Проверка при входе и обновлении токенов. Истекшая блокировка снимается здесь же
+    pub async fn check(mm: &ModelManager, user: &UserDto) -> Result<()> {
+        if !user.is_banned {
+            return Ok(());
+        }
+
+        match BanRepo::find_active(mm.db(), &user.id).await? {
+            Some(ban) => Err(Error::Banned(ban_message(&ban))),
+            None => Self::clear(mm, &user.id).await,
+        }
+    }
+
+    /// Быстрая проверка по кешу для каждого запроса
+    pub async fn check_cached(cache: &Cache, user_id: &Uuid) -> Result<()> {
+        let key = format!("{}{}", BANNED_USER_PREFIX, user_id);
+        match redis_fns::get::<String>(cache, &key).await? {
+            Some(message) => Err(Error::Banned(message)),
+            None => Ok(()),
+        }
+    }
+
+    /// Прогрев кеша при старте сервера. Блокировки, перенесенные миграцией или оставшиеся
+    /// после сброса Redis, есть только в базе, а middleware смотрит лишь в кеш
+    pub async fn warm_cache(mm: &ModelManager) -> Result<usize> {
+        let bans = BanRepo::find_all_active(mm.db()).await?;
+        for ban in &bans {
+            Self::cache_ban(mm.cache(), ban).await?;
+        }
+
+        Ok(bans.len())
+    }
+
+    async fn check_can_ban(mm: &ModelManager, ctx: &Ctx, user: &UserDto) -> Result<()> {
+        ctx.user_id.ok_or(Error::Unauthorized)?;
+        let role = requester_role(mm.db(), ctx).await?;
+        AccessControl::check_access(ctx, role.clone(), Resource::User(user.id), Action::Ban)
+            .map_err(|e| Error::Core(lib_core::error::Error::AccessControlSystem(e)))?;
+
+        // Модераторы блокируют только обычных пользователей
+        if role != Role::Admin && !matches!(user.role, RoleEnum::User) {
+            return Err(access_denied(ctx, role, user.id, Action::Ban));
+        }
+
+        Ok(())
+    }
+
+    async fn cache_ban(cache: &Cache, ban: &BanRepo) -> Result<()> {
+        let key = format!("{}{}", BANNED_USER_PREFIX, ban.user_id);
+        let ttl = match ban.expires_at {
+            Some(expires_at) => {
+                let ttl = (expires_at - Utc::now().naive_utc()).num_seconds();
+                if ttl <= 0 {
+                    return Ok(());
+                }
+                Some(ttl as usize)
+            }
+            None => None,
+        };
+        redis_fns::set(cache, &key, ban_message(ban), ttl).await?;
+
+        Ok(())
+    }
+
+    async fn clear(mm: &ModelManager, user_id: &Uuid) -> Result<()> {
+        UserService::update(mm.db(), None, user_id, None, None, None, Some(false)).await?;
+
+        let key = format!("{}{}", BANNED_USER_PREFIX, user_id);
+        redis_fns::delete(mm.cache(), &[&key]).await?;
+
+        Ok(())
+    }
+}
+
+fn ban_message(ban: &BanRepo) -> String {
+    match ban.expires_at {
+        Some(expires_at) => format!(
+            "Account is banned until {} UTC: {}",
+            expires_at.format("%Y-%m-%d %H:%M"),
+            ban.reason
+        ),
+        None => format!("Account is permanently banned: {}", ban.reason),
+    }
+}
+
+pub(crate) fn access_denied(ctx: &Ctx, role: Role, user_id: Uuid, action: Action) -> Error {
+    Error::Core(lib_core::error::Error::AccessControlSystem(
+        AccessControl::deny(ctx, role, Resource::User(user_id), action),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::services::test_utils::{create_user, delete_user, model_manager};
+
+    async fn create_admin(mm: &ModelManager) -> anyhow::Result<UserDto> {
+        let user = create_user(mm).await?;
+        Ok(UserService::update(
+            mm.db(),
+            None,
+            &user.id,
+            None,
+            Some(RoleEnum::Admin),
+            None,
+            None,
+        )
+        .await?)
+    }
+
+    #[tokio::test]
+    async fn test_ban_and_lift() -> anyhow::Result<()> {
+        let mm = model_manager().await?;
+        let admin = create_admin(&mm).await?;
+        let user = create_user(&mm).await?;
+        let ctx = Ctx::new(admin.id);
+
+        let ban = BanService::ban(&mm, &ctx, &user, "spam", Some(1)).await?;
+        assert!(ban.is_active);
+
+        let user = UserService::get_by_id(mm.db(), None, &user.id).await?;
+        assert!(matches!(
+            BanService::check(&mm, &user).await,
+            Err(Error::Banned(_))
+        ));
+        assert!(matches!(
+            BanService::check_cached(mm.cache(), &user.id).await,
+            Err(Error::Banned(_))
+        ));
+
+        BanService::lift(&mm, &ctx, &user).await?;
+        BanService::check_cached(mm.cache(), &user.id).await?;
+        let user = UserService::get_by_id(mm.db(), None, &user.id).await?;
+        assert!(!user.is_banned);
+
+        let history = BanService::get_history(&mm, &ctx, &user).await?;
+        assert_eq!(history.len(), 1);
+        assert!(!history[0].is_active);
+
+        delete_user(&mm, &user).await?;
+        delete_user(&mm, &admin).await
+    }
+
+    #[tokio::test]
+    async fn test_moderator_cannot_ban_staff() -> anyhow::Result<()> {
+        let mm = model_manager().await?;
+        let admin = create_admin(&mm).await?;
+        let moderator = create_user(&mm).await?;
+        let moderator = UserService::update(
+            mm.db(),
+            None,
+            &moderator.id,
+            None,
+            Some(RoleEnum::Moderator),
+            None,
+            None,
+        )
+        .await?;
+        let ctx = Ctx::new(moderator.id);
+
+        let err = BanService::ban(&mm, &ctx, &admin, "spam", None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Core(_)));
+        assert_eq!(err.status_code(), 403);
+        let result = BanService::ban(&mm, &ctx, &moderator, "spam", None).await;
+        assert!(matches!(result, Err(Error::BadRequest(_))));
+
+        delete_user(&mm, &moderator).await?;
+        delete_user(&mm, &admin).await
+    }
+
+    #[tokio::test]
+    async fn test_warm_cache_picks_up_bans_missing_from_cache() -> anyhow::Result<()> {
+        let mm = model_manager().await?;
+        let admin = create_admin(&mm).await?;
+        let user = create_user(&mm).await?;
+
+        // Как после миграции: блокировка есть в базе, но не в кеше
+        BanRepo::create(
+            mm.db(),
+            BanForCreate {
+                user_id: user.id,
+                issued_by: admin.id,
+                reason: "Banned before ban history was recorded".to_string(),
+                expires_at: None,
+            },
+        )
+        .await?;
+        UserService::update(mm.db(), None, &user.id, None, None, None, Some(true)).await?;
+        BanService::check_cached(mm.cache(), &user.id).await?;
+
+        assert!(BanService::warm_cache(&mm).await? >= 1);
+        assert!(matches!(
+            BanService::check_cached(mm.cache(), &user.id).await,
+            Err(Error::Banned(_))
+        ));
+
+        BanService::clear(&mm, &user.id).await?;
+        delete_user(&mm, &user).await?;
+        delete_user(&mm, &admin).await
+    }
+}
@@ -2,7 +2,12 @@ use std::{collections::HashSet, sync::Arc};
 
 use chrono::NaiveDateTime;
 use lib_core::{
+    acs::{AccessControl, Action, Resource},
     ctx::Ctx,
+    db::{
+        pagination::{Page, PageRequest},
+        Db,
+    },
     model::{
         chat::{ChatForCreate, ChatForSelect, ChatForUpdate, ChatRepo},
         chat_member::{
@@ -20,6 +25,7 @@ use uuid::Uuid;
 
 use crate::error::{Error, Result};
 
+use super::post_service::requester_role;
 use super::user_service::{UserDto, UserService};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -128,6 +134,7 @@ impl ChatService {
 
     pub async fn get_chat(mm: Arc<ModelManager>, ctx: Ctx, chat_id: &Uuid) -> Result<ChatDto> {
         let _ = ctx.user_id.ok_or(Error::Unauthorized)?;
+        Self::check_chat_access(mm.db(), &ctx, chat_id, Action::Read).await?;
 
         let chat = ChatRepo::find(
             mm.db(),
@@ -141,11 +148,18 @@ impl ChatService {
         Self::convert_chat_to_dto(mm, ctx, chat).await
     }
 
+    pub async fn check_member(mm: Arc<ModelManager>, ctx: Ctx, chat_id: &Uuid) -> Result<()> {
+        let _ = ctx.user_id.ok_or(Error::Unauthorized)?;
+        Self::check_chat_access(mm.db(), &ctx, chat_id, Action::Read).await
+    }
+
     pub async fn get_chat_owner(
         mm: Arc<ModelManager>,
         ctx: Ctx,
         chat_id: &Uuid,
     ) -> Result<UserDto> {
+        Self::check_chat_access(mm.db(), &ctx, chat_id, Action::Read).await?;
+
         let chat_member = ChatMemberRepo::find(
             mm.db(),
             ChatMemberForSelect {
@@ -159,7 +173,8 @@ impl ChatService {
         Ok(user.into())
     }
 
-    pub async fn get_unread_count(mm: Arc<ModelManager>, ctx: Ctx, chat_id: &Uuid) -> Result<u32> {
+    /// Только для `convert_chat_to_dto`: доступ к чату проверяет вызывающий
+    async fn get_unread_count(mm: Arc<ModelManager>, ctx: Ctx, chat_id: &Uuid) -> Result<u32> {
         let user_id = ctx.user_id.ok_or(Error::Unauthorized)?;
 
         let unread_count = MessageStatusRepo::find_all(
@@ -177,29 +192,36 @@ impl ChatService {
         Ok(unread_count as u32)
     }
 
+    /// Страница - самые новые сообщения до курсора, `next_cursor` ведет к более ранним.
+    /// Внутри страницы порядок прежний, от старых к новым
     pub async fn get_messages(
         mm: Arc<ModelManager>,
         ctx: Ctx,
         chat_id: &Uuid,
-    ) -> Result<Vec<MessageDto>> {
+        page: &PageRequest,
+    ) -> Result<Page<MessageDto>> {
         let _ = ctx.user_id.ok_or(Error::Unauthorized)?;
+        Self::check_chat_access(mm.db(), &ctx, chat_id, Action::Read).await?;
 
-        let messages = MessageRepo::find_all(
+        let Page { items, next_cursor } = MessageRepo::find_page(
             mm.db(),
             MessageForSelect {
                 chat_id: Some(*chat_id),
                 ..Default::default()
             },
+            page,
         )
-        .await?
-        .into_iter()
-        .map(|msg| {
+        .await?;
+        let messages = items.into_iter().rev().map(|msg| {
             let mm = mm.clone();
             let ctx = ctx.clone();
             async move { Self::converte_message_to_dto(mm, ctx, msg).await }
         });
 
-        futures::future::try_join_all(messages).await
+        Ok(Page {
+            items: futures::future::try_join_all(messages).await?,
+            next_cursor,
+        })
     }
 
     pub async fn get_message(mm: Arc<ModelManager>, ctx: Ctx, id: &Uuid) -> Result<MessageDto> {
@@ -213,11 +235,13 @@ impl ChatService {
             },
         )
         .await?;
+        Self::check_message_access(mm.db(), &ctx, &message, Action::Read).await?;
 
         Self::converte_message_to_dto(mm, ctx, message).await
     }
 
-    pub async fn get_last_message(
+    /// Только для `convert_chat_to_dto`: доступ к чату проверяет вызывающий
+    async fn get_last_message(
         mm: Arc<ModelManager>,
         ctx: Ctx,
         chat_id: &Uuid,
@@ -293,6 +317,7 @@ impl ChatService {
 
     pub async fn create_chat(mm: Arc<ModelManager>, ctx: Ctx, name: &str) -> Result<ChatDto> {
         let requester_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+        Self::check_access(mm.db(), &ctx, Self::new_chat_resource(), Action::Create).await?;
 
         let chat = ChatRepo::create(
             mm.db(),
@@ -323,6 +348,7 @@ impl ChatService {
         name: &str,
     ) -> Result<ChatDto> {
         let _ = ctx.user_id.ok_or(Error::Unauthorized)?;
+        Self::check_chat_access(mm.db(), &ctx, id, Action::Update).await?;
 
         let chat = ChatRepo::update(
             mm.db(),
@@ -339,6 +365,7 @@ impl ChatService {
 
     pub async fn delete_chat(mm: Arc<ModelManager>, ctx: Ctx, id: &Uuid) -> Result<()> {
         let _ = ctx.user_id.ok_or(Error::Unauthorized)?;
+        Self::check_chat_access(mm.db(), &ctx, id, Action::Delete).await?;
 
         let _ = ChatRepo::delete(mm.db(), id).await?;
 
@@ -352,6 +379,7 @@ impl ChatService {
         user_id: &Uuid,
     ) -> Result<(ChatDto, UserDto)> {
         let _ = ctx.user_id.ok_or(Error::Unauthorized)?;
+        Self::check_chat_access(mm.db(), &ctx, chat_id, Action::ManageMembers).await?;
 
         let chat = ChatRepo::find(
             mm.db(),
@@ -404,7 +432,14 @@ impl ChatService {
         chat_id: &Uuid,
         user_id: &Uuid,
     ) -> Result<(ChatDto, UserDto)> {
-        let _ = ctx.user_id.ok_or(Error::Unauthorized)?;
+        let requester_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+        // Выйти из чата может любой участник, исключить другого - только владелец
+        let action = if requester_id == *user_id {
+            Action::Read
+        } else {
+            Action::ManageMembers
+        };
+        Self::check_chat_access(mm.db(), &ctx, chat_id, action).await?;
 
         let chat = ChatRepo::find(
             mm.db(),
@@ -456,6 +491,7 @@ impl ChatService {
         user_id: &Uuid,
     ) -> Result<ChatDto> {
         let requester_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+        Self::check_access(mm.db(), &ctx, Self::new_chat_resource(), Action::Create).await?;
 
         let _ = UserService::get_by_id(mm.db(), Some(requester_id), user_id)
             .await
@@ -503,6 +539,13 @@ impl ChatService {
         content: &str,
     ) -> Result<MessageDto> {
         let requester_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+        let resource = Resource::Message {
+            id: Uuid::nil(),
+            sender_id: requester_id,
+            is_member: Self::is_member(mm.db(), &ctx, chat_id).await?,
+        };
+        Self::check_access(mm.db(), &ctx, resource, Action::Create).await?;
+
         let message = MessageRepo::create(
             mm.db(),
             MessageForCreate {
@@ -566,7 +609,9 @@ impl ChatService {
         id: &Uuid,
         content: &str,
     ) -> Result<MessageDto> {
-        let _ = ctx.user_id.ok_or(Error::Unauthorized);
+        let _ = ctx.user_id.ok_or(Error::Unauthorized)?;
+        let message = find_message(mm.db(), id).await?;
+        Self::check_message_access(mm.db(), &ctx, &message, Action::Update).await?;
 
         let message = MessageRepo::update(
             mm.db(),
@@ -583,6 +628,8 @@ impl ChatService {
 
     pub async fn read_message(mm: Arc<ModelManager>, ctx: Ctx, id: &Uuid) -> Result<()> {
         let user_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+        let message = find_message(mm.db(), id).await?;
+        Self::check_message_access(mm.db(), &ctx, &message, Action::Read).await?;
 
         let _ = MessageStatusRepo::read_message(mm.db(), id, &user_id).await?;
 
@@ -590,7 +637,9 @@ impl ChatService {
     }
 
     pub async fn delete_message(mm: Arc<ModelManager>, ctx: Ctx, id: &Uuid) -> Result<MessageDto> {
-        let _ = ctx.user_id.ok_or(Error::Unauthorized);
+        let _ = ctx.user_id.ok_or(Error::Unauthorized)?;
+        let message = find_message(mm.db(), id).await?;
+        Self::check_message_access(mm.db(), &ctx, &message, Action::Delete).await?;
 
         let message = MessageRepo::delete(mm.db(), id).await?;
         Self::converte_message_to_dto(mm, ctx, message).await
@@ -601,8 +650,13 @@ impl ChatService {
         ctx: Ctx,
         chat_id: &Uuid,
     ) -> Result<Vec<UserDto>> {
-        let _ = ctx.user_id.ok_or(Error::Unauthorized);
+        let _ = ctx.user_id.ok_or(Error::Unauthorized)?;
+        Self::check_chat_access(mm.db(), &ctx, chat_id, Action::Read).await?;
 
+        Self::find_members(mm, ctx, chat_id).await
+    }
+
+    async fn find_members(mm: Arc<ModelManager>, ctx: Ctx, chat_id: &Uuid) -> Result<Vec<UserDto>> {
         let chat_members = ChatMemberRepo::find_all(
             mm.db(),
             ChatMemberForSelect {
@@ -621,6 +675,111 @@ impl ChatService {
         futures::future::try_join_all(users).await
     }
 
+    async fn is_member(db: &Db, ctx: &Ctx, chat_id: &Uuid) -> Result<bool> {
+        let Some(user_id) = ctx.user_id else {
+            return Ok(false);
+        };
+
+        match ChatMemberRepo::find(
+            db,
+            ChatMemberForSelect {
+                chat_id: Some(*chat_id),
+                user_id: Some(user_id),
+                ..Default::default()
+            },
+        )
+        .await
+        {
+            Ok(_) => Ok(true),
+            Err(lib_core::error::Error::EntityNotFound) => Ok(false),
+            Err(e) => Err(Error::Core(e)),
+        }
+    }
+
+    /// Создатель нового чата сразу становится его участником
+    fn new_chat_resource() -> Resource {
+        Resource::Chat {
+            id: Uuid::nil(),
+            owner_id: None,
+            is_member: true,
+        }
+    }
+
+    async fn check_chat_access(db: &Db, ctx: &Ctx, chat_id: &Uuid, action: Action) -> Result<()> {
+        let resource = Self::chat_resource(db, ctx, chat_id).await?;
+        Self::check_access(db, ctx, resource, action).await
+    }
+
+    /// Чат в виде ресурса системы доступа, с точки зрения пользователя из `ctx`
+    pub(crate) async fn chat_resource(db: &Db, ctx: &Ctx, chat_id: &Uuid) -> Result<Resource> {
+        let members = ChatMemberRepo::find_all(
+            db,
+            ChatMemberForSelect {
+                chat_id: Some(*chat_id),
+                ..Default::default()
+            },
+        )
+        .await?;
+        // Без участников чат может и не существовать: это 404, а не отказ в доступе
+        if members.is_empty() {
+            ChatRepo::find(
+                db,
+                ChatForSelect {
+                    id: Some(*chat_id),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        }
+
+        Ok(Resource::Chat {
+            id: *chat_id,
+            owner_id: members
+                .iter()
+                .find(|member| member.role == ChatRoleEnum::Owner)
+                .map(|member| member.user_id),
+            is_member: members
+                .iter()
+                .any(|member| Some(member.user_id) == ctx.user_id),
+        })
+    }
+
+    async fn check_message_access(
+        db: &Db,
+        ctx: &Ctx,
+        message: &MessageRepo,
+        action: Action,
+    ) -> Result<()> {
+        let resource = Self::message_resource(db, ctx, message).await?;
+        Self::check_access(db, ctx, resource, action).await
+    }
+
+    pub(crate) async fn message_resource(
+        db: &Db,
+        ctx: &Ctx,
+        message: &MessageRepo,
+    ) -> Result<Resource> {
+        Ok(Resource::Message {
+            id: message.id,
+            sender_id: message.sender_id,
+            is_member: Self::is_member(db, ctx, &message.chat_id).await?,
+        })
+    }
+
+    async fn check_access(db: &Db, ctx: &Ctx, resource: Resource, action: Action) -> Result<()> {
+        let role = requester_role(db, ctx).await?;
+        let is_member = resource.is_chat_member();
+
+        AccessControl::check_access(ctx, role, resource, action).map_err(|e| {
+            warn!("{}", e.to_string());
+            if is_member {
+                Error::Core(e.into())
+            } else {
+                Error::NotChatMember
+            }
+        })
+    }
+
     pub async fn converte_message_to_dto(
         mm: Arc<ModelManager>,
         ctx: Ctx,
@@ -668,7 +827,7 @@ impl ChatService {
         let name = match chat.is_group {
             true => chat.name.expect("Name not specified for chat"),
             false => {
-                let user = Self::get_members(mm.clone(), ctx.clone(), &chat.id)
+                let user = Self::find_members(mm.clone(), ctx.clone(), &chat.id)
                     .await?
                     .into_iter()
                     .filter(|user| user.id.ne(&reqeuster_id))
@@ -683,7 +842,7 @@ impl ChatService {
             }
         };
 
-        let members_count = Some(Self::get_members(mm, ctx, &chat.id).await?.len() as u32);
+        let members_count = Some(Self::find_members(mm, ctx, &chat.id).await?.len() as u32);
 
         Ok(ChatDto {
             id: chat.id,
@@ -697,3 +856,83 @@ impl ChatService {
         })
     }
 }
+
+pub(crate) async fn find_message(db: &Db, id: &Uuid) -> Result<MessageRepo> {
+    MessageRepo::find(
+        db,
+        MessageForSelect {
+            id: Some(*id),
+            ..Default::default()
+        },
+    )
+    .await
+    .map_err(Error::Core)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::services::test_utils::{create_user, delete_user, model_manager};
+
+    #[tokio::test]
+    async fn test_chat_access_errors() -> anyhow::Result<()> {
+        let mm = model_manager().await?;
+        let owner = create_user(&mm).await?;
+        let outsider = create_user(&mm).await?;
+
+        let chat = ChatService::create_chat(mm.clone(), Ctx::new(owner.id), "test chat").await?;
+        ChatService::get_chat(mm.clone(), Ctx::new(owner.id), &chat.id).await?;
+
+        let err = ChatService::get_chat(mm.clone(), Ctx::new(outsider.id), &chat.id)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NotChatMember));
+        assert_eq!(err.status_code(), 403);
+
+        let err = ChatService::check_member(mm.clone(), Ctx::new(owner.id), &Uuid::new_v4())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Core(lib_core::error::Error::EntityNotFound)
+        ));
+        assert_eq!(err.status_code(), 404);
+
+        ChatService::delete_chat(mm.clone(), Ctx::new(owner.id), &chat.id).await?;
+        delete_user(&mm, &outsider).await?;
+        delete_user(&mm, &owner).await
+    }
+
+    #[tokio::test]
+    async fn test_message_pages_keep_chronological_order() -> anyhow::Result<()> {
+        let mm = model_manager().await?;
+        let owner = create_user(&mm).await?;
+        let ctx = Ctx::new(owner.id);
+
+        let chat = ChatService::create_chat(mm.clone(), ctx.clone(), "test chat").await?;
+        for i in 0..3 {
+            ChatService::send_message(mm.clone(), ctx.clone(), &chat.id, &i.to_string()).await?;
+        }
+
+        let page = PageRequest {
+            cursor: None,
+            limit: Some(2),
+        };
+        let newest = ChatService::get_messages(mm.clone(), ctx.clone(), &chat.id, &page).await?;
+        let contents: Vec<_> = newest.items.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["1", "2"]);
+
+        let page = PageRequest {
+            cursor: newest.next_cursor,
+            limit: Some(2),
+        };
+        let earlier = ChatService::get_messages(mm.clone(), ctx.clone(), &chat.id, &page).await?;
+        let contents: Vec<_> = earlier.items.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["0"]);
+        assert!(earlier.next_cursor.is_none());
+
+        ChatService::delete_chat(mm.clone(), ctx, &chat.id).await?;
+        delete_user(&mm, &owner).await
+    }
+}
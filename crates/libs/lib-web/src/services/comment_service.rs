@@ -1,15 +1,21 @@
+use std::collections::{HashMap, HashSet};
+
 use chrono::NaiveDateTime;
-use lib_core::acs::{AccessControl, Action, Resource, Role};
+use lib_core::acs::{AccessControl, Action, Resource};
+use lib_core::ctx::Ctx;
+use lib_core::db::pagination::{Page, PageRequest};
 use lib_core::db::Db;
 use lib_core::model::comment::{
     CommentForCreate, CommentForDelete, CommentForSelect, CommentForUpdate, CommentRepo,
 };
+use lib_core::model::post::{PostForSelect, PostRepo};
 use serde::Serialize;
 use tracing::warn;
 use uuid::Uuid;
 
+use super::community_member_service::CommunityMemberService;
 use super::like_service::LikeService;
-use super::post_service::PostService;
+use super::post_service::{requester_role, PostService};
 use super::user_service::UserService;
 
 use crate::error::{Error, Result};
@@ -44,14 +50,14 @@ pub struct CommentService;
 impl CommentService {
     pub async fn create(
         db: &Db,
-        requester_id: Option<Uuid>,
+        ctx: &Ctx,
         post_id: &Uuid,
         parent_comment_id: Option<Uuid>,
         content: &str,
     ) -> Result<CommentDto> {
         Self::check_access(
             &db,
-            requester_id,
+            ctx,
             Resource::Comment {
                 id: Uuid::nil(),
                 author_id: Uuid::nil(),
@@ -60,8 +66,11 @@ impl CommentService {
         )
         .await?;
 
+        UserService::check_email_verified(db, ctx.user_id).await?;
+        Self::check_read(db, ctx, post_id).await?;
+
         let comment_fc = CommentForCreate {
-            user_id: match requester_id {
+            user_id: match ctx.user_id {
                 Some(id) => id,
                 None => return Err(Error::Unauthorized),
             },
@@ -72,10 +81,10 @@ impl CommentService {
         let comment = CommentRepo::create(db, comment_fc)
             .await
             .map_err(Error::Core)?;
-        Self::convert_to_dto(db, requester_id, comment).await
+        Self::convert_to_dto(db, ctx, comment).await
     }
 
-    pub async fn get_by_id(db: &Db, requester_id: Option<Uuid>, id: &Uuid) -> Result<CommentDto> {
+    pub async fn get_by_id(db: &Db, ctx: &Ctx, id: &Uuid) -> Result<CommentDto> {
         let comment_fs = CommentForSelect {
             id: Some(*id),
             ..Default::default()
@@ -86,7 +95,7 @@ impl CommentService {
 
         Self::check_access(
             &db,
-            requester_id,
+            ctx,
             Resource::Comment {
                 id: comment.id,
                 author_id: comment.user_id,
@@ -94,16 +103,19 @@ impl CommentService {
             Action::Read,
         )
         .await?;
+        Self::check_read(db, ctx, &comment.post_id).await?;
 
-        Self::convert_to_dto(db, requester_id, comment).await
+        Self::convert_to_dto(db, ctx, comment).await
     }
 
+    /// Комментарии из закрытых сообществ отсеиваются после загрузки страницы
     pub async fn get_many_by_user_id(
         db: &Db,
-        requester_id: Option<Uuid>,
+        ctx: &Ctx,
         user_id: &Uuid,
-    ) -> Result<Vec<CommentDto>> {
-        let user_id = UserService::get_by_id(db, requester_id, user_id)
+        page: &PageRequest,
+    ) -> Result<Page<CommentDto>> {
+        let user_id = UserService::get_by_id(db, ctx.user_id, user_id)
             .await
             .map(|user| user.id)
             .ok();
@@ -112,61 +124,71 @@ impl CommentService {
             user_id,
             ..Default::default()
         };
-        let comments = CommentRepo::find_many(db, comment_fs)
+        let Page { items, next_cursor } = CommentRepo::find_page(db, comment_fs, page)
             .await
-            .map_err(Error::Core)?
-            .into_iter()
-            .map(|comment| {
-                let db = db.clone();
-                async move {
-                    Self::check_access(
-                        &db,
-                        requester_id,
-                        Resource::Comment {
-                            id: comment.id,
-                            author_id: comment.user_id,
-                        },
-                        Action::Read,
-                    )
-                    .await?;
-
-                    Self::convert_to_dto(&db, requester_id, comment).await
-                }
-            });
+            .map_err(Error::Core)?;
+        let comments = Self::retain_readable(db, ctx, items).await?;
 
-        futures::future::try_join_all(comments).await
+        Ok(Page {
+            items: Self::to_dtos(db, ctx, comments).await?,
+            next_cursor,
+        })
     }
 
     pub async fn get_many_by_post_id(
         db: &Db,
-        requester_id: Option<Uuid>,
+        ctx: &Ctx,
         post_id: &Uuid,
-    ) -> Result<Vec<CommentDto>> {
+        page: &PageRequest,
+    ) -> Result<Page<CommentDto>> {
+        Self::check_read(db, ctx, post_id).await?;
+
+        let comment_fs = CommentForSelect {
+            post_id: Some(*post_id),
+            ..Default::default()
+        };
+        let Page { items, next_cursor } = CommentRepo::find_page(db, comment_fs, page)
+            .await
+            .map_err(Error::Core)?;
+
+        Ok(Page {
+            items: Self::to_dtos(db, ctx, items).await?,
+            next_cursor,
+        })
+    }
+
+    /// Все комментарии поста сразу, нужны для построения веток
+    pub async fn get_all_by_post_id(db: &Db, ctx: &Ctx, post_id: &Uuid) -> Result<Vec<CommentDto>> {
+        Self::check_read(db, ctx, post_id).await?;
+
         let comment_fs = CommentForSelect {
             post_id: Some(*post_id),
             ..Default::default()
         };
         let comments = CommentRepo::find_many(db, comment_fs)
             .await
-            .map_err(Error::Core)?
-            .into_iter()
-            .map(|comment| {
-                let db = db.clone();
-                async move {
-                    Self::check_access(
-                        &db,
-                        requester_id,
-                        Resource::Comment {
-                            id: comment.id,
-                            author_id: comment.user_id,
-                        },
-                        Action::Read,
-                    )
-                    .await?;
-
-                    Self::convert_to_dto(&db, requester_id, comment).await
-                }
-            });
+            .map_err(Error::Core)?;
+        Self::to_dtos(db, ctx, comments).await
+    }
+
+    async fn to_dtos(db: &Db, ctx: &Ctx, comments: Vec<CommentRepo>) -> Result<Vec<CommentDto>> {
+        let comments = comments.into_iter().map(|comment| {
+            let db = db.clone();
+            async move {
+                Self::check_access(
+                    &db,
+                    ctx,
+                    Resource::Comment {
+                        id: comment.id,
+                        author_id: comment.user_id,
+                    },
+                    Action::Read,
+                )
+                .await?;
+
+                Self::convert_to_dto(&db, ctx, comment).await
+            }
+        });
 
         futures::future::try_join_all(comments).await
     }
@@ -186,11 +208,7 @@ impl CommentService {
             .map_err(Error::Core)
     }
 
-    pub async fn get_replies_count(
-        db: &Db,
-        requester_id: Option<Uuid>,
-        comment_id: &Uuid,
-    ) -> Result<u32> {
+    pub async fn get_replies_count(db: &Db, ctx: &Ctx, comment_id: &Uuid) -> Result<u32> {
         let comment_fs = CommentForSelect {
             id: Some(*comment_id),
             ..Default::default()
@@ -201,7 +219,7 @@ impl CommentService {
 
         Self::check_access(
             &db,
-            requester_id,
+            ctx,
             Resource::Comment {
                 id: comment.id,
                 author_id: comment.user_id,
@@ -222,7 +240,7 @@ impl CommentService {
 
     pub async fn update(
         db: &Db,
-        requester_id: Option<Uuid>,
+        ctx: &Ctx,
         id: &Uuid,
         content: Option<String>,
     ) -> Result<CommentDto> {
@@ -236,7 +254,7 @@ impl CommentService {
 
         Self::check_access(
             &db,
-            requester_id,
+            ctx,
             Resource::Comment {
                 id: comment.id,
                 author_id: comment.user_id,
@@ -249,10 +267,10 @@ impl CommentService {
         let comment = CommentRepo::update(db, id, comment_fu)
             .await
             .map_err(Error::Core)?;
-        Self::convert_to_dto(db, requester_id, comment).await
+        Self::convert_to_dto(db, ctx, comment).await
     }
 
-    pub async fn delete(db: &Db, requester_id: Option<Uuid>, id: &Uuid) -> Result<()> {
+    pub async fn delete(db: &Db, ctx: &Ctx, id: &Uuid) -> Result<()> {
         let comment_fs = CommentForSelect {
             id: Some(*id),
             ..Default::default()
@@ -261,16 +279,25 @@ impl CommentService {
             .await
             .map_err(Error::Core)?;
 
-        Self::check_access(
-            &db,
-            requester_id,
+        let post = find_post(db, &comment.post_id).await?;
+        let community_role =
+            CommunityMemberService::get_role(db, &post.community_id, ctx.user_id).await?;
+
+        let role = requester_role(db, ctx).await?;
+        AccessControl::check_community_access(
+            ctx,
+            role,
+            community_role,
             Resource::Comment {
                 id: comment.id,
                 author_id: comment.user_id,
             },
             Action::Delete,
         )
-        .await?;
+        .map_err(|e| {
+            warn!("{}", e.to_string());
+            Error::Core(e.into())
+        })?;
 
         let comment_fd = CommentForDelete { id: *id };
         CommentRepo::delete(db, comment_fd).await?;
@@ -278,34 +305,55 @@ impl CommentService {
         Ok(())
     }
 
-    async fn check_access(
+    /// Комментарии к постам закрытых сообществ видны только их участникам
+    async fn check_read(db: &Db, ctx: &Ctx, post_id: &Uuid) -> Result<()> {
+        let post = find_post(db, post_id).await?;
+        CommunityMemberService::check_read(db, ctx, &post.community_id).await
+    }
+
+    async fn retain_readable(
         db: &Db,
-        requester_id: Option<Uuid>,
-        resource: Resource,
-        action: Action,
-    ) -> Result<()> {
-        let role = match requester_id {
-            Some(id) => get_role(db, &id).await?,
-            None => Role::Guest,
-        };
+        ctx: &Ctx,
+        comments: Vec<CommentRepo>,
+    ) -> Result<Vec<CommentRepo>> {
+        let post_ids: HashSet<Uuid> = comments.iter().map(|comment| comment.post_id).collect();
+        let mut post_communities = HashMap::new();
+        for post_id in post_ids {
+            let post = find_post(db, &post_id).await?;
+            post_communities.insert(post_id, post.community_id);
+        }
+
+        let readable = CommunityMemberService::readable_communities(
+            db,
+            ctx,
+            post_communities.values().copied(),
+        )
+        .await?;
+
+        Ok(comments
+            .into_iter()
+            .filter(|comment| readable.contains(&post_communities[&comment.post_id]))
+            .collect())
+    }
+
+    async fn check_access(db: &Db, ctx: &Ctx, resource: Resource, action: Action) -> Result<()> {
+        let role = requester_role(db, ctx).await?;
         // Ok(())
-        AccessControl::check_access(role, resource, action, requester_id).map_err(|e| {
+        AccessControl::check_access(ctx, role, resource, action).map_err(|e| {
             warn!("{}", e.to_string());
             Error::Core(e.into())
         })
     }
 
-    async fn convert_to_dto(
-        db: &Db,
-        requester_id: Option<Uuid>,
-        comment: CommentRepo,
-    ) -> Result<CommentDto> {
+    async fn convert_to_dto(db: &Db, ctx: &Ctx, comment: CommentRepo) -> Result<CommentDto> {
+        // Пост показывается вместе с комментарием, отдельный скоуп на него не нужен
+        let post_ctx = ctx.unscoped();
         let (replies_count, rating, requester_like, user, post) = tokio::try_join!(
-            Self::get_replies_count(db, requester_id, &comment.id),
-            LikeService::get_comment_rating(db, requester_id, &comment.id),
-            LikeService::get_comment_like(db, requester_id, &comment.id),
-            UserService::get_by_id(db, requester_id, &comment.user_id),
-            PostService::get_by_id(db, requester_id, &comment.post_id)
+            Self::get_replies_count(db, ctx, &comment.id),
+            LikeService::get_comment_rating(db, ctx.user_id, &comment.id),
+            LikeService::get_comment_like(db, ctx.user_id, &comment.id),
+            UserService::get_by_id(db, ctx.user_id, &comment.user_id),
+            PostService::get_by_id(db, &post_ctx, &comment.post_id)
         )?;
 
         Ok(CommentDto {
@@ -330,11 +378,10 @@ impl CommentService {
     }
 }
 
-pub async fn get_role(db: &Db, user_id: &Uuid) -> Result<Role> {
-    UserService::get_by_id(db, None, user_id)
-        .await?
-        .role
-        .as_str()
-        .parse()
-        .map_err(|e| Error::Core(lib_core::error::Error::AccessControlSystem(e)))
+pub(crate) async fn find_post(db: &Db, post_id: &Uuid) -> Result<PostRepo> {
+    let post_fs = PostForSelect {
+        id: Some(*post_id),
+        ..Default::default()
+    };
+    PostRepo::find(db, post_fs).await.map_err(Error::Core)
 }
@@ -0,0 +1,151 @@
+use chrono::NaiveDateTime;
+use lib_core::acs::{Action, ScopeAccess, ScopeResource};
+use lib_core::ctx::Ctx;
+use lib_core::db::Db;
+use lib_core::model::community_join_request::{CommunityJoinRequestRepo, JoinRequestStatusEnum};
+use serde::Serialize;
+use tracing::info;
+use uuid::Uuid;
+
+use super::access_service::check_scope;
+use super::community_member_service::{find_community, CommunityMemberService};
+use super::user_service::{UserDto, UserService};
+
+use crate::error::{Error, Result};
+
+#[derive(Serialize, Clone)]
+pub struct JoinRequestDto {
+    pub id: Uuid,
+    pub community_id: Uuid,
+    pub message: Option<String>,
+    pub status: JoinRequestStatusEnum,
+    pub created_at: NaiveDateTime,
+    pub decided_at: Option<NaiveDateTime>,
+    pub decided_by: Option<Uuid>,
+    pub user: UserDto,
+}
+
+/// Обертка для заявок на вступление в закрытые сообщества
+pub struct CommunityJoinRequestService;
+
+impl CommunityJoinRequestService {
+    pub async fn create(
+        db: &Db,
+        ctx: &Ctx,
+        community_id: &Uuid,
+        message: Option<String>,
+    ) -> Result<JoinRequestDto> {
+        let user_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+        check_scope(ctx, ScopeResource::Communities, ScopeAccess::Write)?;
+        let community = find_community(db, community_id).await?;
+
+        if !community.is_private {
+            return Err(Error::BadRequest(
+                "Community is public, follow it to join".to_string(),
+            ));
+        }
+        if community.is_invite_only {
+            return Err(Error::CommunityInviteOnly);
+        }
+        if CommunityMemberService::get_role(db, community_id, ctx.user_id)
+            .await?
+            .is_some()
+        {
+            return Err(Error::BadRequest(
+                "You are already a member of this community".to_string(),
+            ));
+        }
+
+        match CommunityJoinRequestRepo::find_pending(db, community_id, &user_id).await {
+            Ok(_) => {
+                return Err(Error::BadRequest(
+                    "Join request is already pending".to_string(),
+                ))
+            }
+            Err(lib_core::error::Error::EntityNotFound) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let request = CommunityJoinRequestRepo::create(db, community_id, &user_id, message).await?;
+        Self::convert_to_dto(db, ctx.user_id, request).await
+    }
+
+    /// Заявки, ожидающие решения модераторов
+    pub async fn get_pending(
+        db: &Db,
+        ctx: &Ctx,
+        community_id: &Uuid,
+    ) -> Result<Vec<JoinRequestDto>> {
+        let community = find_community(db, community_id).await?;
+        CommunityMemberService::check_access(db, ctx, &community, Action::ManageMembers).await?;
+
+        let requests = CommunityJoinRequestRepo::find_all_pending(db, community_id)
+            .await?
+            .into_iter()
+            .map(|request| Self::convert_to_dto(db, ctx.user_id, request));
+        futures::future::try_join_all(requests).await
+    }
+
+    pub async fn approve(
+        db: &Db,
+        ctx: &Ctx,
+        community_id: &Uuid,
+        id: &Uuid,
+    ) -> Result<JoinRequestDto> {
+        let request =
+            Self::decide(db, ctx, community_id, id, JoinRequestStatusEnum::Approved).await?;
+
+        CommunityMemberService::add_to_community(db, community_id, &request.user_id).await?;
+        info!(
+            "User {} joined community {} by request",
+            request.user_id, community_id
+        );
+
+        Self::convert_to_dto(db, ctx.user_id, request).await
+    }
+
+    pub async fn deny(
+        db: &Db,
+        ctx: &Ctx,
+        community_id: &Uuid,
+        id: &Uuid,
+    ) -> Result<JoinRequestDto> {
+        let request =
+            Self::decide(db, ctx, community_id, id, JoinRequestStatusEnum::Denied).await?;
+
+        Self::convert_to_dto(db, ctx.user_id, request).await
+    }
+
+    async fn decide(
+        db: &Db,
+        ctx: &Ctx,
+        community_id: &Uuid,
+        id: &Uuid,
+        status: JoinRequestStatusEnum,
+    ) -> Result<CommunityJoinRequestRepo> {
+        let decided_by = ctx.user_id.ok_or(Error::Unauthorized)?;
+        let community = find_community(db, community_id).await?;
+        CommunityMemberService::check_access(db, ctx, &community, Action::ManageMembers).await?;
+
+        CommunityJoinRequestRepo::decide(db, id, community_id, status, &decided_by)
+            .await
+            .map_err(Error::Core)
+    }
+
+    async fn convert_to_dto(
+        db: &Db,
+        requester_id: Option<Uuid>,
+        request: CommunityJoinRequestRepo,
+    ) -> Result<JoinRequestDto> {
+        Ok(JoinRequestDto {
+            id: request.id,
+            community_id: request.community_id,
+            message: request.message,
+            status: request.status,
+            created_at: request.created_at,
+            decided_at: request.decided_at,
+            decided_by: request.decided_by,
+            user: UserService::get_by_id(db, requester_id, &request.user_id).await?,
+        })
+    }
+}
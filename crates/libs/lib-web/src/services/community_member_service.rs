@@ -0,0 +1,374 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDateTime;
+use lib_core::acs::{AccessControl, Action, CommunityRole, Resource, ScopeAccess, ScopeResource};
+use lib_core::ctx::Ctx;
+use lib_core::db::Db;
+use lib_core::model::community::{CommunityForSelect, CommunityRepo};
+use lib_core::model::community_member::{CommunityMemberForSelect, CommunityMemberRepo};
+use lib_core::model::community_role::CommunityRoleEnum;
+use lib_core::model::follow::{FollowForCreate, FollowForDelete, FollowRepo};
+use serde::Serialize;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::access_service::check_scope;
+use super::follow_service::FollowService;
+use super::post_service::requester_role;
+use super::user_service::{UserDto, UserService};
+
+use crate::error::{Error, Result};
+
+#[derive(Serialize, Clone)]
+pub struct CommunityMemberDto {
+    pub community_id: Uuid,
+    pub role: CommunityRoleEnum,
+    pub joined_at: NaiveDateTime,
+    pub user: UserDto,
+}
+
+/// Обертка для ролей пользователей внутри сообществ (владелец, модератор, участник)
+pub struct CommunityMemberService;
+
+impl CommunityMemberService {
+    /// Роль пользователя в сообществе, `None` если он в нем не состоит
+    pub async fn get_role(
+        db: &Db,
+        community_id: &Uuid,
+        user_id: Option<Uuid>,
+    ) -> Result<Option<CommunityRole>> {
+        let Some(user_id) = user_id else {
+            return Ok(None);
+        };
+
+        let member = CommunityMemberRepo::find(
+            db,
+            CommunityMemberForSelect {
+                community_id: Some(*community_id),
+                user_id: Some(user_id),
+            },
+        )
+        .await;
+
+        match member {
+            Ok(member) => Ok(Some(to_community_role(member.role))),
+            Err(lib_core::error::Error::EntityNotFound) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Владелец и модераторы сообщества
+    pub async fn get_moderators(
+        db: &Db,
+        ctx: &Ctx,
+        community_id: &Uuid,
+    ) -> Result<Vec<CommunityMemberDto>> {
+        let community = find_community(db, community_id).await?;
+        Self::check_access(db, ctx, &community, Action::Read).await?;
+
+        let members = CommunityMemberRepo::find_all_by_roles(
+            db,
+            community_id,
+            &[CommunityRoleEnum::Owner, CommunityRoleEnum::Moderator],
+        )
+        .await?;
+
+        let members = members
+            .into_iter()
+            .map(|member| Self::convert_to_dto(db, ctx.user_id, member));
+        futures::future::try_join_all(members).await
+    }
+
+    /// Назначение модератора, доступно владельцу сообщества
+    pub async fn appoint_moderator(
+        db: &Db,
+        ctx: &Ctx,
+        community_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<CommunityMemberDto> {
+        let community = find_community(db, community_id).await?;
+        Self::check_access(db, ctx, &community, Action::ManageModerators).await?;
+
+        let user = UserService::get_by_id(db, ctx.user_id, user_id).await?;
+        if Self::get_role(db, community_id, Some(user.id)).await? == Some(CommunityRole::Owner) {
+            return Err(Error::BadRequest(
+                "The owner cannot be appointed as a moderator".to_string(),
+            ));
+        }
+
+        let member =
+            CommunityMemberRepo::upsert(db, community_id, &user.id, CommunityRoleEnum::Moderator)
+                .await?;
+
+        Self::convert_to_dto(db, ctx.user_id, member).await
+    }
+
+    /// Снятие модератора, он остается участником сообщества
+    pub async fn remove_moderator(
+        db: &Db,
+        ctx: &Ctx,
+        community_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<()> {
+        let community = find_community(db, community_id).await?;
+        Self::check_access(db, ctx, &community, Action::ManageModerators).await?;
+
+        if Self::get_role(db, community_id, Some(*user_id)).await? != Some(CommunityRole::Moderator)
+        {
+            return Err(Error::BadRequest(
+                "User is not a moderator of this community".to_string(),
+            ));
+        }
+
+        CommunityMemberRepo::upsert(db, community_id, user_id, CommunityRoleEnum::Member).await?;
+
+        Ok(())
+    }
+
+    /// Может ли пользователь читать посты и комментарии сообщества
+    pub async fn can_read(db: &Db, ctx: &Ctx, community: &CommunityRepo) -> Result<bool> {
+        if !community.is_private {
+            return Ok(true);
+        }
+
+        let role = requester_role(db, ctx).await?;
+        let community_role = Self::get_role(db, &community.id, ctx.user_id).await?;
+
+        // Только фильтр: скрытое сообщество в списке - не попытка доступа, в журнал не пишется
+        Ok(AccessControl::can_access(
+            ctx,
+            role,
+            community_role,
+            Resource::Community {
+                id: community.id,
+                owner_id: community.user_id,
+            },
+            Action::ReadPrivate,
+        ))
+    }
+
+    /// Ошибка, если содержимое закрытого сообщества скрыто от пользователя
+    pub async fn check_read(db: &Db, ctx: &Ctx, community_id: &Uuid) -> Result<()> {
+        let community = find_community(db, community_id).await?;
+        if !community.is_private {
+            return Ok(());
+        }
+
+        let role = requester_role(db, ctx).await?;
+        let community_role = Self::get_role(db, &community.id, ctx.user_id).await?;
+
+        AccessControl::check_community_access(
+            ctx,
+            role,
+            community_role,
+            Resource::Community {
+                id: community.id,
+                owner_id: community.user_id,
+            },
+            Action::ReadPrivate,
+        )
+        .map_err(|_| {
+            warn!(
+                "User {:?} is not a member of private community {}",
+                ctx.user_id, community_id
+            );
+            Error::NotCommunityMember
+        })
+    }
+
+    /// Сообщества из списка, содержимое которых пользователь может читать
+    pub async fn readable_communities(
+        db: &Db,
+        ctx: &Ctx,
+        community_ids: impl IntoIterator<Item = Uuid>,
+    ) -> Result<HashSet<Uuid>> {
+        let mut readable = HashSet::new();
+
+        for community_id in community_ids.into_iter().collect::<HashSet<_>>() {
+            let community = find_community(db, &community_id).await?;
+            if Self::can_read(db, ctx, &community).await? {
+                readable.insert(community_id);
+            }
+        }
+
+        Ok(readable)
+    }
+
+    /// Все участники сообщества
+    pub async fn get_members(
+        db: &Db,
+        ctx: &Ctx,
+        community_id: &Uuid,
+    ) -> Result<Vec<CommunityMemberDto>> {
+        check_scope(ctx, ScopeResource::Communities, ScopeAccess::Read)?;
+        Self::check_read(db, ctx, community_id).await?;
+
+        let members = CommunityMemberRepo::find_all(
+            db,
+            CommunityMemberForSelect {
+                community_id: Some(*community_id),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let members = members
+            .into_iter()
+            .map(|member| Self::convert_to_dto(db, ctx.user_id, member));
+        futures::future::try_join_all(members).await
+    }
+
+    /// Приглашение пользователя в сообщество его модераторами
+    pub async fn add_member(
+        db: &Db,
+        ctx: &Ctx,
+        community_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<CommunityMemberDto> {
+        let community = find_community(db, community_id).await?;
+        Self::check_access(db, ctx, &community, Action::ManageMembers).await?;
+
+        let user = UserService::get_by_id(db, ctx.user_id, user_id).await?;
+        if Self::get_role(db, community_id, Some(user.id))
+            .await?
+            .is_some()
+        {
+            return Err(Error::BadRequest(
+                "User is already a member of this community".to_string(),
+            ));
+        }
+
+        Self::add_to_community(db, community_id, &user.id).await?;
+
+        let member = CommunityMemberRepo::find(
+            db,
+            CommunityMemberForSelect {
+                community_id: Some(*community_id),
+                user_id: Some(user.id),
+            },
+        )
+        .await?;
+        Self::convert_to_dto(db, ctx.user_id, member).await
+    }
+
+    /// Исключение участника, модераторов сначала нужно снять
+    pub async fn remove_member(
+        db: &Db,
+        ctx: &Ctx,
+        community_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<()> {
+        let community = find_community(db, community_id).await?;
+        Self::check_access(db, ctx, &community, Action::ManageMembers).await?;
+
+        if Self::get_role(db, community_id, Some(*user_id)).await? != Some(CommunityRole::Member) {
+            return Err(Error::BadRequest(
+                "Only plain members can be removed from a community".to_string(),
+            ));
+        }
+
+        if FollowService::is_followed(db, Some(*user_id), community_id).await? {
+            let follow_fd = FollowForDelete {
+                user_id: *user_id,
+                community_id: *community_id,
+            };
+            FollowRepo::delete(db, follow_fd).await?;
+        }
+
+        Self::leave(db, community_id, user_id).await
+    }
+
+    /// Участник сообщества сразу становится его подписчиком
+    pub(crate) async fn add_to_community(
+        db: &Db,
+        community_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<()> {
+        Self::join(db, community_id, user_id).await?;
+
+        if !FollowService::is_followed(db, Some(*user_id), community_id).await? {
+            let follow_fc = FollowForCreate {
+                user_id: *user_id,
+                community_id: *community_id,
+            };
+            FollowRepo::create(db, follow_fc).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn add_owner(db: &Db, community_id: &Uuid, user_id: &Uuid) -> Result<()> {
+        CommunityMemberRepo::upsert(db, community_id, user_id, CommunityRoleEnum::Owner).await?;
+        Ok(())
+    }
+
+    pub async fn join(db: &Db, community_id: &Uuid, user_id: &Uuid) -> Result<()> {
+        CommunityMemberRepo::join(db, community_id, user_id)
+            .await
+            .map_err(Error::Core)
+    }
+
+    pub async fn leave(db: &Db, community_id: &Uuid, user_id: &Uuid) -> Result<()> {
+        CommunityMemberRepo::leave(db, community_id, user_id)
+            .await
+            .map_err(Error::Core)
+    }
+
+    pub(crate) async fn check_access(
+        db: &Db,
+        ctx: &Ctx,
+        community: &CommunityRepo,
+        action: Action,
+    ) -> Result<()> {
+        let role = requester_role(db, ctx).await?;
+        let community_role = Self::get_role(db, &community.id, ctx.user_id).await?;
+
+        AccessControl::check_community_access(
+            ctx,
+            role,
+            community_role,
+            Resource::Community {
+                id: community.id,
+                owner_id: community.user_id,
+            },
+            action,
+        )
+        .map_err(|e| {
+            warn!("{}", e.to_string());
+            Error::Core(e.into())
+        })
+    }
+
+    async fn convert_to_dto(
+        db: &Db,
+        requester_id: Option<Uuid>,
+        member: CommunityMemberRepo,
+    ) -> Result<CommunityMemberDto> {
+        Ok(CommunityMemberDto {
+            community_id: member.community_id,
+            role: member.role,
+            joined_at: member.joined_at,
+            user: UserService::get_by_id(db, requester_id, &member.user_id).await?,
+        })
+    }
+}
+
+fn to_community_role(role: CommunityRoleEnum) -> CommunityRole {
+    match role {
+        CommunityRoleEnum::Owner => CommunityRole::Owner,
+        CommunityRoleEnum::Moderator => CommunityRole::Moderator,
+        CommunityRoleEnum::Member => CommunityRole::Member,
+    }
+}
+
+pub(crate) async fn find_community(db: &Db, community_id: &Uuid) -> Result<CommunityRepo> {
+    CommunityRepo::find(
+        db,
+        CommunityForSelect {
+            id: Some(*community_id),
+            ..Default::default()
+        },
+    )
+    .await
+    .map_err(Error::Core)
+}
@@ -1,5 +1,7 @@
 use chrono::NaiveDateTime;
-use lib_core::acs::{AccessControl, Action, Resource, Role};
+use lib_core::acs::{AccessControl, Action, Resource};
+use lib_core::ctx::Ctx;
+use lib_core::db::pagination::{Page, PageRequest};
 use lib_core::db::Db;
 use lib_core::model::community::{
     CommunityForCreate, CommunityForDelete, CommunityForSelect, CommunityForUpdate, CommunityRepo,
@@ -8,7 +10,9 @@ use serde::Serialize;
 use tracing::{instrument, warn};
 use uuid::Uuid;
 
+use super::community_member_service::CommunityMemberService;
 use super::follow_service::FollowService;
+use super::post_service::requester_role;
 use super::user_service::UserService;
 
 use crate::error::{Error, Result};
@@ -21,21 +25,19 @@ pub struct CommunityDto {
     pub name: String,
     pub description: String,
     pub is_private: bool,
+    pub is_invite_only: bool,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub followers_count: u32,
     pub is_followed: bool,
+    pub is_member: bool,
     pub user: UserDto,
 }
 
 pub struct CommunityService;
 
 impl CommunityService {
-    pub async fn get_meny_by_query(
-        db: &Db,
-        requester_id: Option<Uuid>,
-        query: &str,
-    ) -> Result<Vec<CommunityDto>> {
+    pub async fn get_meny_by_query(db: &Db, ctx: &Ctx, query: &str) -> Result<Vec<CommunityDto>> {
         let communities = CommunityRepo::find_many_by_query(db, query)
             .await?
             .into_iter()
@@ -44,7 +46,7 @@ impl CommunityService {
                 async move {
                     Self::check_access(
                         &db,
-                        requester_id,
+                        ctx,
                         Resource::Community {
                             id: community.id,
                             owner_id: community.user_id,
@@ -53,7 +55,7 @@ impl CommunityService {
                     )
                     .await?;
 
-                    Self::convert_to_dto(&db, community, requester_id).await
+                    Self::convert_to_dto(&db, community, ctx.user_id).await
                 }
             });
 
@@ -63,14 +65,15 @@ impl CommunityService {
     #[instrument(skip(db))]
     pub async fn create(
         db: &Db,
-        requester_id: Option<Uuid>,
+        ctx: &Ctx,
         name: &str,
         description: &str,
         is_private: &bool,
+        is_invite_only: &bool,
     ) -> Result<CommunityDto> {
         Self::check_access(
             &db,
-            requester_id,
+            ctx,
             Resource::Community {
                 id: Uuid::nil(),
                 owner_id: Uuid::nil(),
@@ -80,20 +83,22 @@ impl CommunityService {
         .await?;
 
         let community_fc = CommunityForCreate {
-            user_id: match requester_id {
+            user_id: match ctx.user_id {
                 Some(id) => id,
                 None => return Err(Error::Unauthorized),
             },
             name: name.to_string(),
             description: description.to_string(),
             is_private: *is_private,
+            is_invite_only: *is_invite_only,
         };
         let community = CommunityRepo::create(db, community_fc).await?;
-        Self::convert_to_dto(db, community, requester_id).await
+        CommunityMemberService::add_owner(db, &community.id, &community.user_id).await?;
+        Self::convert_to_dto(db, community, ctx.user_id).await
     }
 
     #[instrument(skip(db))]
-    pub async fn get_by_id(db: &Db, requester_id: Option<Uuid>, id: &Uuid) -> Result<CommunityDto> {
+    pub async fn get_by_id(db: &Db, ctx: &Ctx, id: &Uuid) -> Result<CommunityDto> {
         let community_fs = CommunityForSelect {
             id: Some(*id),
             ..Default::default()
@@ -102,7 +107,7 @@ impl CommunityService {
 
         Self::check_access(
             &db,
-            requester_id,
+            ctx,
             Resource::Community {
                 id: community.id,
                 owner_id: community.user_id,
@@ -111,15 +116,11 @@ impl CommunityService {
         )
         .await?;
 
-        Self::convert_to_dto(db, community, requester_id).await
+        Self::convert_to_dto(db, community, ctx.user_id).await
     }
 
     #[instrument(skip(db))]
-    pub async fn get_by_name(
-        db: &Db,
-        requester_id: Option<Uuid>,
-        name: &str,
-    ) -> Result<CommunityDto> {
+    pub async fn get_by_name(db: &Db, ctx: &Ctx, name: &str) -> Result<CommunityDto> {
         let community_fs = CommunityForSelect {
             name: Some(name.to_string()),
             ..Default::default()
@@ -128,7 +129,7 @@ impl CommunityService {
 
         Self::check_access(
             &db,
-            requester_id,
+            ctx,
             Resource::Community {
                 id: community.id,
                 owner_id: community.user_id,
@@ -137,80 +138,84 @@ impl CommunityService {
         )
         .await?;
 
-        Self::convert_to_dto(db, community, requester_id).await
+        Self::convert_to_dto(db, community, ctx.user_id).await
     }
 
     #[instrument(skip(db))]
-    pub async fn get_many(db: &Db, requester_id: Option<Uuid>) -> Result<Vec<CommunityDto>> {
+    pub async fn get_many(db: &Db, ctx: &Ctx, page: &PageRequest) -> Result<Page<CommunityDto>> {
         let community_fs = CommunityForSelect {
             ..Default::default()
         };
-        let communities = CommunityRepo::find_all(db, community_fs)
-            .await?
-            .into_iter()
-            .map(|community| {
-                let db = db.clone();
-                async move {
-                    Self::check_access(
-                        &db,
-                        requester_id,
-                        Resource::Community {
-                            id: community.id,
-                            owner_id: community.user_id,
-                        },
-                        Action::Read,
-                    )
-                    .await?;
-
-                    Self::convert_to_dto(&db, community, requester_id).await
-                }
-            });
-
-        futures::future::try_join_all(communities).await
+        let Page { items, next_cursor } = CommunityRepo::find_page(db, community_fs, page).await?;
+        let communities = items.into_iter().map(|community| {
+            let db = db.clone();
+            async move {
+                Self::check_access(
+                    &db,
+                    ctx,
+                    Resource::Community {
+                        id: community.id,
+                        owner_id: community.user_id,
+                    },
+                    Action::Read,
+                )
+                .await?;
+
+                Self::convert_to_dto(&db, community, ctx.user_id).await
+            }
+        });
+
+        Ok(Page {
+            items: futures::future::try_join_all(communities).await?,
+            next_cursor,
+        })
     }
 
     #[instrument(skip(db))]
     pub async fn get_many_by_user_id(
         db: &Db,
-        requester_id: Option<Uuid>,
+        ctx: &Ctx,
         user_id: &Uuid,
-    ) -> Result<Vec<CommunityDto>> {
+        page: &PageRequest,
+    ) -> Result<Page<CommunityDto>> {
         let community_fs = CommunityForSelect {
             user_id: Some(*user_id),
             ..Default::default()
         };
-        let communities = CommunityRepo::find_all(db, community_fs)
-            .await?
-            .into_iter()
-            .map(|community| {
-                let db = db.clone();
-                async move {
-                    Self::check_access(
-                        &db,
-                        requester_id,
-                        Resource::Community {
-                            id: community.id,
-                            owner_id: community.user_id,
-                        },
-                        Action::Read,
-                    )
-                    .await?;
-
-                    Self::convert_to_dto(&db, community, requester_id).await
-                }
-            });
-
-        futures::future::try_join_all(communities).await
+        let Page { items, next_cursor } = CommunityRepo::find_page(db, community_fs, page).await?;
+        let communities = items.into_iter().map(|community| {
+            let db = db.clone();
+            async move {
+                Self::check_access(
+                    &db,
+                    ctx,
+                    Resource::Community {
+                        id: community.id,
+                        owner_id: community.user_id,
+                    },
+                    Action::Read,
+                )
+                .await?;
+
+                Self::convert_to_dto(&db, community, ctx.user_id).await
+            }
+        });
+
+        Ok(Page {
+            items: futures::future::try_join_all(communities).await?,
+            next_cursor,
+        })
     }
 
     #[instrument(skip(db))]
     pub async fn update(
         db: &Db,
-        requester_id: Option<Uuid>,
+        ctx: &Ctx,
         id: &Uuid,
         name: Option<String>,
         description: Option<String>,
         is_private: Option<bool>,
+        is_invite_only: Option<bool>,
     ) -> Result<CommunityDto> {
         let community_fs = CommunityForSelect {
             id: Some(*id),
@@ -220,7 +225,7 @@ impl CommunityService {
 
         Self::check_access(
             &db,
-            requester_id,
+            ctx,
             Resource::Community {
                 id: community.id,
                 owner_id: community.user_id,
@@ -233,13 +238,14 @@ impl CommunityService {
             name,
             description,
             is_private,
+            is_invite_only,
         };
         let community = CommunityRepo::update(db, id, community_fu).await?;
-        Self::convert_to_dto(db, community, requester_id).await
+        Self::convert_to_dto(db, community, ctx.user_id).await
     }
 
     #[instrument(skip(db))]
-    pub async fn delete(db: &Db, requester_id: Option<Uuid>, id: &Uuid) -> Result<()> {
+    pub async fn delete(db: &Db, ctx: &Ctx, id: &Uuid) -> Result<()> {
         let community_fs = CommunityForSelect {
             id: Some(*id),
             ..Default::default()
@@ -248,7 +254,7 @@ impl CommunityService {
 
         Self::check_access(
             &db,
-            requester_id,
+            ctx,
             Resource::Community {
                 id: community.id,
                 owner_id: community.user_id,
@@ -266,11 +272,12 @@ impl CommunityService {
     #[instrument(skip(db))]
     pub async fn update_by_name(
         db: &Db,
-        requester_id: Option<Uuid>,
+        ctx: &Ctx,
         name_ident: &str,
         name: Option<String>,
         description: Option<String>,
         is_private: Option<bool>,
+        is_invite_only: Option<bool>,
     ) -> Result<CommunityDto> {
         let community_fs = CommunityForSelect {
             name: Some(name_ident.to_string()),
@@ -280,7 +287,7 @@ impl CommunityService {
 
         Self::check_access(
             &db,
-            requester_id,
+            ctx,
             Resource::Community {
                 id: community.id,
                 owner_id: community.user_id,
@@ -293,13 +300,14 @@ impl CommunityService {
             name,
             description,
             is_private,
+            is_invite_only,
         };
         let community = CommunityRepo::update(db, &community.id, community_fu).await?;
-        Self::convert_to_dto(db, community, requester_id).await
+        Self::convert_to_dto(db, community, ctx.user_id).await
     }
 
     #[instrument(skip(db))]
-    pub async fn delete_by_name(db: &Db, requester_id: Option<Uuid>, name: &str) -> Result<()> {
+    pub async fn delete_by_name(db: &Db, ctx: &Ctx, name: &str) -> Result<()> {
         let community_fs = CommunityForSelect {
             name: Some(name.to_string()),
             ..Default::default()
@@ -308,7 +316,7 @@ impl CommunityService {
 
         Self::check_access(
             &db,
-            requester_id,
+            ctx,
             Resource::Community {
                 id: community.id,
                 owner_id: community.user_id,
@@ -323,21 +331,22 @@ impl CommunityService {
             .map_err(Error::Core)
     }
 
-    async fn check_access(
-        db: &Db,
-        requester_id: Option<Uuid>,
-        resource: Resource,
-        action: Action,
-    ) -> Result<()> {
-        let role = match requester_id {
-            Some(id) => get_role(db, &id).await?,
-            None => Role::Guest,
+    async fn check_access(db: &Db, ctx: &Ctx, resource: Resource, action: Action) -> Result<()> {
+        let role = requester_role(db, ctx).await?;
+        // Роль в сообществе нужна только для управления им
+        let community_role = match &resource {
+            Resource::Community { id, .. } if matches!(action, Action::Update | Action::Delete) => {
+                CommunityMemberService::get_role(db, id, ctx.user_id).await?
+            }
+            _ => None,
         };
-        // Ok(())
-        AccessControl::check_access(role, resource, action, requester_id).map_err(|e| {
-            warn!("{}", e.to_string());
-            Error::Core(e.into())
-        })
+
+        AccessControl::check_community_access(ctx, role, community_role, resource, action).map_err(
+            |e| {
+                warn!("{}", e.to_string());
+                Error::Core(e.into())
+            },
+        )
     }
 
     async fn convert_to_dto(
@@ -351,21 +360,16 @@ impl CommunityService {
             name: community.name,
             description: community.description,
             is_private: community.is_private,
+            is_invite_only: community.is_invite_only,
             created_at: community.created_at,
             updated_at: community.updated_at,
             followers_count: FollowService::get_followers_count(db, requester_id, &community.id)
                 .await?,
             is_followed: FollowService::is_followed(db, requester_id, &community.id).await?,
+            is_member: CommunityMemberService::get_role(db, &community.id, requester_id)
+                .await?
+                .is_some(),
             user: UserService::get_by_id(db, requester_id, &community.user_id).await?,
         })
     }
 }
-
-pub async fn get_role(db: &Db, user_id: &Uuid) -> Result<Role> {
-    UserService::get_by_id(db, None, user_id)
-        .await?
-        .role
-        .as_str()
-        .parse()
-        .map_err(|e| Error::Core(lib_core::error::Error::AccessControlSystem(e)))
-}
@@ -1,10 +1,13 @@
-use lib_core::acs::{AccessControl, Action, Resource, Role};
+use lib_core::acs::{AccessControl, Action, Resource};
+use lib_core::ctx::Ctx;
 use lib_core::db::Db;
 use lib_core::model::follow::{FollowForCreate, FollowForDelete, FollowForSelect, FollowRepo};
 use tracing::warn;
 use uuid::Uuid;
 
+use super::community_member_service::CommunityMemberService;
 use super::community_service::CommunityService;
+use super::post_service::requester_role;
 use super::user_service::UserService;
 
 use crate::error::{Error, Result};
@@ -12,15 +15,11 @@ use crate::error::{Error, Result};
 pub struct FollowService;
 
 impl FollowService {
-    pub async fn follow(
-        db: &Db,
-        requester_id: Option<Uuid>,
-        community_id: &Uuid,
-    ) -> Result<FollowRepo> {
-        let community = CommunityService::get_by_id(db, requester_id, community_id).await?;
+    pub async fn follow(db: &Db, ctx: &Ctx, community_id: &Uuid) -> Result<FollowRepo> {
+        let community = CommunityService::get_by_id(db, ctx, community_id).await?;
         Self::check_access(
             db,
-            requester_id,
+            ctx,
             Resource::Community {
                 id: community.id,
                 owner_id: community.user_id,
@@ -29,22 +28,34 @@ impl FollowService {
         )
         .await?;
 
+        // В закрытые сообщества вступают по заявке или приглашению
+        if community.is_private
+            && CommunityMemberService::get_role(db, community_id, ctx.user_id)
+                .await?
+                .is_none()
+        {
+            return Err(Error::NotCommunityMember);
+        }
+
         let follow_fc = FollowForCreate {
-            user_id: requester_id.unwrap(),
+            user_id: ctx.user_id.unwrap(),
             community_id: *community_id,
         };
-        FollowRepo::create(db, follow_fc).await.map_err(Error::Core)
+        let follow = FollowRepo::create(db, follow_fc)
+            .await
+            .map_err(Error::Core)?;
+
+        // Подписчики считаются участниками сообщества
+        CommunityMemberService::join(db, community_id, &follow.user_id).await?;
+
+        Ok(follow)
     }
 
-    pub async fn get_followers(
-        db: &Db,
-        requester_id: Option<Uuid>,
-        community_id: &Uuid,
-    ) -> Result<Vec<FollowRepo>> {
-        let community = CommunityService::get_by_id(db, requester_id, community_id).await?;
+    pub async fn get_followers(db: &Db, ctx: &Ctx, community_id: &Uuid) -> Result<Vec<FollowRepo>> {
+        let community = CommunityService::get_by_id(db, ctx, community_id).await?;
         Self::check_access(
             db,
-            requester_id,
+            ctx,
             Resource::Community {
                 id: community.id,
                 owner_id: community.user_id,
@@ -62,13 +73,9 @@ impl FollowService {
             .map_err(Error::Core)
     }
 
-    pub async fn get_followings(
-        db: &Db,
-        requester_id: Option<Uuid>,
-        user_id: &Uuid,
-    ) -> Result<Vec<FollowRepo>> {
-        let user = UserService::get_by_id(db, requester_id, user_id).await?;
-        Self::check_access(db, requester_id, Resource::User(user.id), Action::Follow).await?;
+    pub async fn get_followings(db: &Db, ctx: &Ctx, user_id: &Uuid) -> Result<Vec<FollowRepo>> {
+        let user = UserService::get_by_id(db, ctx.user_id, user_id).await?;
+        Self::check_access(db, ctx, Resource::User(user.id), Action::Follow).await?;
 
         let follow_fs = FollowForSelect {
             user_id: Some(*user_id),
@@ -119,11 +126,11 @@ impl FollowService {
         }
     }
 
-    pub async fn unfollow(db: &Db, requester_id: Option<Uuid>, community_id: &Uuid) -> Result<()> {
-        let community = CommunityService::get_by_id(db, requester_id, community_id).await?;
+    pub async fn unfollow(db: &Db, ctx: &Ctx, community_id: &Uuid) -> Result<()> {
+        let community = CommunityService::get_by_id(db, ctx, community_id).await?;
         Self::check_access(
             db,
-            requester_id,
+            ctx,
             Resource::Community {
                 id: community.id,
                 owner_id: community.user_id,
@@ -133,35 +140,22 @@ impl FollowService {
         .await?;
 
         let follow_fd = FollowForDelete {
-            user_id: requester_id.unwrap(),
+            user_id: ctx.user_id.unwrap(),
             community_id: *community_id,
         };
-        FollowRepo::delete(db, follow_fd).await.map_err(Error::Core)
+        FollowRepo::delete(db, follow_fd)
+            .await
+            .map_err(Error::Core)?;
+
+        CommunityMemberService::leave(db, community_id, &ctx.user_id.unwrap()).await
     }
 
-    async fn check_access(
-        db: &Db,
-        requester_id: Option<Uuid>,
-        resource: Resource,
-        action: Action,
-    ) -> Result<()> {
-        let role = match requester_id {
-            Some(id) => get_role(db, &id).await?,
-            None => Role::Guest,
-        };
+    async fn check_access(db: &Db, ctx: &Ctx, resource: Resource, action: Action) -> Result<()> {
+        let role = requester_role(db, ctx).await?;
         // Ok(())
-        AccessControl::check_access(role, resource, action, requester_id).map_err(|e| {
+        AccessControl::check_access(ctx, role, resource, action).map_err(|e| {
             warn!("{}", e.to_string());
             Error::Core(e.into())
         })
     }
 }
-
-pub async fn get_role(db: &Db, user_id: &Uuid) -> Result<Role> {
-    UserService::get_by_id(db, None, user_id)
-        .await?
-        .role
-        .as_str()
-        .parse()
-        .map_err(|e| Error::Core(lib_core::error::Error::AccessControlSystem(e)))
-}
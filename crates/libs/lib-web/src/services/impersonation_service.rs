@@ -0,0 +1,178 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use lib_auth::token::{generate_impersonation_token, IMPERSONATION_TOKEN_TTL_MIN};
+use lib_core::acs::{AccessControl, Action, Role};
+use lib_core::cache::{redis_fns, Cache};
+use lib_core::ctx::Ctx;
+use lib_core::model::impersonation::{
+    ImpersonationForCreate, ImpersonationForSelect, ImpersonationRepo,
+};
+use lib_core::model::role::RoleEnum;
+use lib_core::model::ModelManager;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::extractors::ClientInfo;
+use crate::services::{
+    ban_service::access_denied, post_service::requester_role, user_service::UserDto,
+};
+
+const ACTIVE_IMPERSONATION_PREFIX: &str = "impersonation:";
+
+#[derive(Debug, Serialize)]
+pub struct ImpersonationDto {
+    pub id: Uuid,
+    pub admin_id: Option<Uuid>,
+    pub user_id: Uuid,
+    pub reason: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub started_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub ended_at: Option<NaiveDateTime>,
+    pub ended_by: Option<Uuid>,
+    pub is_active: bool,
+}
+
+impl ImpersonationDto {
+    pub fn from_impersonation(impersonation: ImpersonationRepo) -> Self {
+        let is_active =
+            impersonation.ended_at.is_none() && impersonation.expires_at > Utc::now().naive_utc();
+
+        Self {
+            id: impersonation.id,
+            admin_id: impersonation.admin_id,
+            user_id: impersonation.user_id,
+            reason: impersonation.reason,
+            user_agent: impersonation.user_agent,
+            ip: impersonation.ip,
+            started_at: impersonation.started_at,
+            expires_at: impersonation.expires_at,
+            ended_at: impersonation.ended_at,
+            ended_by: impersonation.ended_by,
+            is_active,
+        }
+    }
+}
+
+/// Обертка для входа администратора под другим пользователем.
+/// Каждая сессия записывается в журнал, токен действует недолго и только на чтение
+pub struct ImpersonationService;
+
+impl ImpersonationService {
+    /// Начало сессии, возвращает запись журнала и access-токен пользователя
+    pub async fn start(
+        mm: &ModelManager,
+        ctx: &Ctx,
+        user: &UserDto,
+        reason: &str,
+        client: ClientInfo,
+    ) -> Result<(ImpersonationDto, String)> {
+        let requester_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+        if requester_id == user.id {
+            return Err(Error::BadRequest(
+                "You cannot impersonate yourself".to_string(),
+            ));
+        }
+        Self::check_admin(mm, ctx, user.id).await?;
+
+        // Токен администратора дал бы его права любому, кто его увидит
+        if matches!(user.role, RoleEnum::Admin) {
+            return Err(Error::BadRequest(
+                "Administrators cannot be impersonated".to_string(),
+            ));
+        }
+
+        let impersonation = ImpersonationRepo::create(
+            mm.db(),
+            ImpersonationForCreate {
+                admin_id: requester_id,
+                user_id: user.id,
+                reason: reason.to_string(),
+                user_agent: client.user_agent,
+                ip: client.ip,
+                expires_at: (Utc::now() + Duration::minutes(IMPERSONATION_TOKEN_TTL_MIN))
+                    .naive_utc(),
+            },
+        )
+        .await?;
+
+        let token = generate_impersonation_token(
+            &user.id.to_string(),
+            user.role.as_str(),
+            &requester_id.to_string(),
+            &impersonation.id.to_string(),
+        )?;
+
+        let key = format!("{}{}", ACTIVE_IMPERSONATION_PREFIX, impersonation.id);
+        let ttl = (IMPERSONATION_TOKEN_TTL_MIN * 60) as usize;
+        redis_fns::set(mm.cache(), &key, requester_id, Some(ttl)).await?;
+
+        Ok((ImpersonationDto::from_impersonation(impersonation), token))
+    }
+
+    /// Досрочное завершение сессии, ее токен сразу перестает приниматься
+    pub async fn end(mm: &ModelManager, ctx: &Ctx, id: &Uuid) -> Result<()> {
+        let requester_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+
+        let impersonation = ImpersonationRepo::find(
+            mm.db(),
+            ImpersonationForSelect {
+                id: Some(*id),
+                ..Default::default()
+            },
+        )
+        .await?;
+        Self::check_admin(mm, ctx, impersonation.user_id).await?;
+
+        ImpersonationRepo::end(mm.db(), id, &requester_id).await?;
+
+        let key = format!("{}{}", ACTIVE_IMPERSONATION_PREFIX, id);
+        redis_fns::delete(mm.cache(), &[&key]).await?;
+
+        Ok(())
+    }
+
+    /// Журнал сессий, в которых администраторы входили под пользователем
+    pub async fn get_history(
+        mm: &ModelManager,
+        ctx: &Ctx,
+        user: &UserDto,
+    ) -> Result<Vec<ImpersonationDto>> {
+        Self::check_admin(mm, ctx, user.id).await?;
+
+        let mut impersonations = ImpersonationRepo::find_all(
+            mm.db(),
+            ImpersonationForSelect {
+                user_id: Some(user.id),
+                ..Default::default()
+            },
+        )
+        .await?;
+        impersonations.sort_by_key(|impersonation| std::cmp::Reverse(impersonation.started_at));
+
+        Ok(impersonations
+            .into_iter()
+            .map(ImpersonationDto::from_impersonation)
+            .collect())
+    }
+
+    /// Проверка для каждого запроса с токеном имперсонации
+    pub async fn is_active(cache: &Cache, id: &str) -> Result<bool> {
+        let key = format!("{}{}", ACTIVE_IMPERSONATION_PREFIX, id);
+        Ok(redis_fns::exists(cache, &key).await?)
+    }
+
+    /// Персональные токены не дают входить под другими пользователями
+    async fn check_admin(mm: &ModelManager, ctx: &Ctx, user_id: Uuid) -> Result<()> {
+        AccessControl::check_scope(ctx, None).map_err(|e| Error::Core(e.into()))?;
+
+        ctx.user_id.ok_or(Error::Unauthorized)?;
+        let role = requester_role(mm.db(), ctx).await?;
+        if role != Role::Admin {
+            return Err(access_denied(ctx, role, user_id, Action::Read));
+        }
+
+        Ok(())
+    }
+}
@@ -1,20 +1,21 @@
+use lib_core::acs::{ScopeAccess, ScopeResource};
+use lib_core::ctx::Ctx;
 use lib_core::db::Db;
 use lib_core::model::like::{LikeForCreate, LikeForDelete, LikeForSelect, LikeRepo};
 use uuid::Uuid;
 
+use super::access_service::check_scope;
+
 use crate::error::{Error, Result};
 
 pub struct LikeService;
 
 impl LikeService {
-    pub async fn like_post(
-        db: &Db,
-        requester_id: Option<Uuid>,
-        post_id: &Uuid,
-        like_type: i16,
-    ) -> Result<LikeRepo> {
+    pub async fn like_post(db: &Db, ctx: &Ctx, post_id: &Uuid, like_type: i16) -> Result<LikeRepo> {
+        check_scope(ctx, ScopeResource::Likes, ScopeAccess::Write)?;
+
         let like_fc = LikeForCreate {
-            user_id: match requester_id {
+            user_id: match ctx.user_id {
                 Some(id) => id,
                 None => return Err(Error::Unauthorized),
             },
@@ -27,12 +28,14 @@ impl LikeService {
 
     pub async fn like_comment(
         db: &Db,
-        requester_id: Option<Uuid>,
+        ctx: &Ctx,
         comment_id: &Uuid,
         like_type: i16,
     ) -> Result<LikeRepo> {
+        check_scope(ctx, ScopeResource::Likes, ScopeAccess::Write)?;
+
         let like_fc = LikeForCreate {
-            user_id: match requester_id {
+            user_id: match ctx.user_id {
                 Some(id) => id,
                 None => return Err(Error::Unauthorized),
             },
@@ -106,9 +109,11 @@ impl LikeService {
         }
     }
 
-    pub async fn unlike_post(db: &Db, requester_id: Option<Uuid>, post_id: &Uuid) -> Result<()> {
+    pub async fn unlike_post(db: &Db, ctx: &Ctx, post_id: &Uuid) -> Result<()> {
+        check_scope(ctx, ScopeResource::Likes, ScopeAccess::Write)?;
+
         let like_fd = LikeForDelete {
-            user_id: match requester_id {
+            user_id: match ctx.user_id {
                 Some(id) => id,
                 None => return Err(Error::Unauthorized),
             },
@@ -118,13 +123,11 @@ impl LikeService {
         LikeRepo::delete(db, like_fd).await.map_err(Error::Core)
     }
 
-    pub async fn unlike_comment(
-        db: &Db,
-        requester_id: Option<Uuid>,
-        comment_id: &Uuid,
-    ) -> Result<()> {
+    pub async fn unlike_comment(db: &Db, ctx: &Ctx, comment_id: &Uuid) -> Result<()> {
+        check_scope(ctx, ScopeResource::Likes, ScopeAccess::Write)?;
+
         let like_fd = LikeForDelete {
-            user_id: match requester_id {
+            user_id: match ctx.user_id {
                 Some(id) => id,
                 None => return Err(Error::Unauthorized),
             },
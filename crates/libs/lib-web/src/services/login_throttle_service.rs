@@ -0,0 +1,139 @@
+use lib_core::cache::{redis_fns, Cache};
+
+use crate::error::{Error, Result};
+
+const FAILURES_PREFIX: &str = "login:fail:";
+const LOCK_PREFIX: &str = "login:lock:";
+const REQUESTS_PREFIX: &str = "login:req:";
+
+/// Неудачные попытки забываются через час после последней
+const FAILURE_WINDOW_SEC: usize = 60 * 60;
+const ACCOUNT_FREE_ATTEMPTS: u64 = 5;
+const IP_FREE_ATTEMPTS: u64 = 20;
+const MAX_LOCKOUT_SEC: u64 = 15 * 60;
+
+/// Защита входа от перебора: счетчики неудач по аккаунту и IP с растущей блокировкой
+pub struct LoginThrottleService;
+
+impl LoginThrottleService {
+    /// Проверка, не заблокирован ли вход для аккаунта или IP
+    pub async fn check(cache: &Cache, account: &str, ip: Option<&str>) -> Result<()> {
+        for key in Self::keys(account, ip) {
+            if let Some(retry_after) = redis_fns::ttl(cache, &format!("{LOCK_PREFIX}{key}")).await?
+            {
+                return Err(Error::TooManyAttempts(retry_after.max(1)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Учет неудачной попытки, после бесплатных попыток блокировка удваивается
+    pub async fn record_failure(cache: &Cache, account: &str, ip: Option<&str>) -> Result<()> {
+        for key in Self::keys(account, ip) {
+            let failures = redis_fns::incr(
+                cache,
+                &format!("{FAILURES_PREFIX}{key}"),
+                FAILURE_WINDOW_SEC,
+            )
+            .await?;
+
+            let free_attempts = if key.starts_with("ip:") {
+                IP_FREE_ATTEMPTS
+            } else {
+                ACCOUNT_FREE_ATTEMPTS
+            };
+
+            if let Some(lockout) = lockout_sec(failures, free_attempts) {
+                let lock_key = format!("{LOCK_PREFIX}{key}");
+                redis_fns::set(cache, &lock_key, failures, Some(lockout as usize)).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Сброс счетчиков аккаунта после успешного входа. Счетчик IP не сбрасывается,
+    /// иначе вход в свой аккаунт позволял бы продолжать перебор чужих.
+    pub async fn reset(cache: &Cache, account: &str) -> Result<()> {
+        let key = Self::account_key(account);
+        redis_fns::delete(
+            cache,
+            &[
+                &format!("{FAILURES_PREFIX}{key}"),
+                &format!("{LOCK_PREFIX}{key}"),
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Ограничение частоты запросов, которые не бывают неудачными, например писем со ссылкой
+    /// для входа. Счетчики свои, с неудачными входами они не смешиваются. Окно сдвигается
+    /// с каждым запросом, так что непрерывный поток остается заблокированным
+    pub async fn limit_requests(
+        cache: &Cache,
+        key: &str,
+        max_requests: u64,
+        window_sec: usize,
+    ) -> Result<()> {
+        let key = format!("{REQUESTS_PREFIX}{}", key.to_lowercase());
+        let requests = redis_fns::incr(cache, &key, window_sec).await?;
+        if requests > max_requests {
+            let retry_after = redis_fns::ttl(cache, &key)
+                .await?
+                .unwrap_or(window_sec as u64);
+            return Err(Error::TooManyAttempts(retry_after.max(1)));
+        }
+
+        Ok(())
+    }
+
+    fn account_key(account: &str) -> String {
+        format!("account:{}", account.to_lowercase())
+    }
+
+    fn keys(account: &str, ip: Option<&str>) -> Vec<String> {
+        let mut keys = vec![Self::account_key(account)];
+        keys.extend(ip.map(|ip| format!("ip:{ip}")));
+        keys
+    }
+}
+
+/// Длительность блокировки: 1, 2, 4... секунд после исчерпания бесплатных попыток
+fn lockout_sec(failures: u64, free_attempts: u64) -> Option<u64> {
+    let excess = failures.checked_sub(free_attempts)?.min(63);
+
+    Some((1u64 << excess).min(MAX_LOCKOUT_SEC))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lockout_sec() {
+        let cases = [
+            (0, 5, None),
+            (4, 5, None),
+            (5, 5, Some(1)),
+            (6, 5, Some(2)),
+            (8, 5, Some(8)),
+            (14, 5, Some(512)),
+            (15, 5, Some(MAX_LOCKOUT_SEC)),
+            (100, 5, Some(MAX_LOCKOUT_SEC)),
+            (u64::MAX, 5, Some(MAX_LOCKOUT_SEC)),
+            (19, 20, None),
+            (20, 20, Some(1)),
+        ];
+
+        for (failures, free_attempts, expected) in cases {
+            assert_eq!(
+                lockout_sec(failures, free_attempts),
+                expected,
+                "failures={failures}, free_attempts={free_attempts}"
+            );
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use lib_core::mail::{self, Mail};
+use lib_core::model::ModelManager;
+
+use crate::config::web_config;
+use crate::error::Result;
+
+use super::user_service::UserDto;
+
+pub struct MailService;
+
+impl MailService {
+    pub async fn send_email_verification(
+        mm: &ModelManager,
+        user: &UserDto,
+        token: &str,
+    ) -> Result<()> {
+        let link = format!("{}/verify-email?token={}", web_config().app_url(), token);
+
+        let mail = Mail {
+            to: user.email.clone(),
+            subject: "Confirm your email".to_string(),
+            body: format!(
+                "Hi {},\n\nPlease confirm your email address by opening the link below:\n{}\n\nThe link is valid for 24 hours.",
+                user.nickname, link
+            ),
+        };
+
+        Self::send(mm, mail).await
+    }
+
+    pub async fn send_password_reset(mm: &ModelManager, user: &UserDto, token: &str) -> Result<()> {
+        let link = format!("{}/reset-password?token={}", web_config().app_url(), token);
+
+        let mail = Mail {
+            to: user.email.clone(),
+            subject: "Reset your password".to_string(),
+            body: format!(
+                "Hi {},\n\nSomeone requested a password reset for your account. To choose a new password open the link below:\n{}\n\nThe link is valid for 60 minutes. If you did not request a reset, just ignore this email.",
+                user.nickname, link
+            ),
+        };
+
+        Self::send(mm, mail).await
+    }
+
+    pub async fn send_magic_link(mm: &ModelManager, user: &UserDto, token: &str) -> Result<()> {
+        let link = format!("{}/magic-link/{}", web_config().app_url(), token);
+
+        let mail = Mail {
+            to: user.email.clone(),
+            subject: "Your sign-in link".to_string(),
+            body: format!(
+                "Hi {},\n\nOpen the link below to sign in to your account:\n{}\n\nThe link works once and is valid for 15 minutes. If you did not request it, just ignore this email.",
+                user.nickname, link
+            ),
+        };
+
+        Self::send(mm, mail).await
+    }
+
+    pub async fn send_email_change_confirmation(
+        mm: &ModelManager,
+        user: &UserDto,
+        new_email: &str,
+        token: &str,
+    ) -> Result<()> {
+        let link = format!(
+            "{}/confirm-email-change?token={}",
+            web_config().app_url(),
+            token
+        );
+
+        let mail = Mail {
+            to: new_email.to_string(),
+            subject: "Confirm your new email".to_string(),
+            body: format!(
+                "Hi {},\n\nTo start using this address for your account open the link below:\n{}\n\nThe link is valid for 24 hours.",
+                user.nickname, link
+            ),
+        };
+
+        Self::send(mm, mail).await
+    }
+
+    pub async fn send_email_change_notice(
+        mm: &ModelManager,
+        user: &UserDto,
+        new_email: &str,
+    ) -> Result<()> {
+        let mail = Mail {
+            to: user.email.clone(),
+            subject: "Email change requested".to_string(),
+            body: format!(
+                "Hi {},\n\nA request was made to change the email of your account to {}. The change takes effect only after it is confirmed from the new address.\n\nIf this was not you, change your password right away.",
+                user.nickname, new_email
+            ),
+        };
+
+        Self::send(mm, mail).await
+    }
+
+    /// Транспорт может писать в файл, поэтому отправка уходит из async-рантайма
+    async fn send(mm: &ModelManager, mail: Mail) -> Result<()> {
+        let mailer = mm.mailer();
+
+        tokio::task::spawn_blocking(move || mailer.send(&mail))
+            .await
+            .map_err(|e| mail::Error::from(std::io::Error::from(e)))
+            .and_then(|result| result)
+            .map_err(lib_core::error::Error::from)?;
+
+        Ok(())
+    }
+}
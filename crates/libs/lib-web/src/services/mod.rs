@@ -1,10 +1,25 @@
+pub mod access_service;
+pub mod api_token_service;
 pub mod auth_service;
+pub mod ban_service;
 pub mod chat_service;
 pub mod comment_service;
+pub mod community_join_request_service;
+pub mod community_member_service;
 pub mod community_service;
 pub mod follow_service;
+pub mod impersonation_service;
 pub mod like_service;
+pub mod login_throttle_service;
+pub mod mail_service;
+pub mod oidc_service;
 pub mod post_service;
 pub mod profile_service;
 pub mod report_service;
+pub mod revocation_service;
+pub mod session_service;
+#[cfg(test)]
+pub(crate) mod test_utils;
+pub mod two_factor_service;
 pub mod user_service;
+pub mod ws_ticket_service;
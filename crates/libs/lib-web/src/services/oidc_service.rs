@@ -0,0 +1,177 @@
+use lib_auth::oidc::{oidc_config, random_token, IdTokenClaims, OidcClient, Pkce};
+use lib_auth::pwd::hash_password;
+use lib_auth::token::generate_opaque_token;
+use lib_core::cache::{redis_fns, Cache};
+use lib_core::db::Db;
+use lib_core::model::identity::{IdentityForCreate, IdentityRepo};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::error::{Error, Result};
+
+use super::user_service::{UserDto, UserService};
+
+const STATE_PREFIX: &str = "oidc:state:";
+const STATE_TTL_SEC: usize = 600;
+const NICKNAME_MAX_LEN: usize = 16;
+
+#[derive(Serialize, Deserialize)]
+struct PendingLogin {
+    provider: String,
+    pkce_verifier: String,
+    nonce: String,
+}
+
+/// Обертка для входа через внешних OIDC-провайдеров
+pub struct OidcService;
+
+impl OidcService {
+    /// Список настроенных провайдеров
+    pub fn providers() -> Vec<String> {
+        oidc_config().provider_names().map(str::to_string).collect()
+    }
+
+    /// Начало входа: сохраняем state/PKCE и отдаем ссылку на провайдера
+    pub async fn authorization_url(cache: &Cache, provider: &str) -> Result<String> {
+        let client = Self::client(provider).await?;
+
+        let pkce = Pkce::generate();
+        let state = random_token();
+        let nonce = random_token();
+
+        redis_fns::set(
+            cache,
+            &format!("{}{}", STATE_PREFIX, state),
+            PendingLogin {
+                provider: provider.to_string(),
+                pkce_verifier: pkce.verifier,
+                nonce: nonce.clone(),
+            },
+            Some(STATE_TTL_SEC),
+        )
+        .await?;
+
+        Ok(client.authorization_url(&state, &nonce, &pkce.challenge))
+    }
+
+    /// Завершение входа: обмен кода, проверка ID-токена и поиск/привязка пользователя
+    pub async fn authenticate(
+        db: &Db,
+        cache: &Cache,
+        provider: &str,
+        code: &str,
+        state: &str,
+    ) -> Result<UserDto> {
+        let pending: PendingLogin = redis_fns::take(cache, &format!("{}{}", STATE_PREFIX, state))
+            .await?
+            .filter(|pending: &PendingLogin| pending.provider == provider)
+            .ok_or(Error::InvalidToken)?;
+
+        let client = Self::client(provider).await?;
+        let claims = client
+            .authenticate(code, &pending.pkce_verifier, &pending.nonce)
+            .await?;
+
+        Self::find_or_link_user(db, provider, claims).await
+    }
+
+    /// Клиент провайдера создается один раз: discovery и ключи не запрашиваются на каждый вход.
+    /// Неудачный discovery не кешируется и повторится при следующем входе
+    async fn client(provider: &str) -> Result<Arc<OidcClient>> {
+        static CLIENTS: OnceLock<Mutex<HashMap<String, Arc<OidcClient>>>> = OnceLock::new();
+        let clients = CLIENTS.get_or_init(Default::default);
+
+        if let Some(client) = clients
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(provider)
+        {
+            return Ok(client.clone());
+        }
+
+        let config = oidc_config()
+            .provider(provider)
+            .ok_or_else(|| lib_auth::oidc::Error::UnknownProvider(provider.to_string()))?;
+        let client = Arc::new(OidcClient::discover(config.clone()).await?);
+
+        Ok(clients
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(provider.to_string())
+            .or_insert(client)
+            .clone())
+    }
+
+    async fn find_or_link_user(db: &Db, provider: &str, claims: IdTokenClaims) -> Result<UserDto> {
+        match IdentityRepo::find_by_subject(db, provider, &claims.sub).await {
+            Ok(identity) => return UserService::get_by_id(db, None, &identity.user_id).await,
+            Err(lib_core::error::Error::EntityNotFound) => {}
+            Err(e) => return Err(Error::Core(e)),
+        }
+
+        // Привязываем только по подтвержденному у провайдера email
+        let email = match (claims.email.as_deref(), claims.email_verified) {
+            (Some(email), true) => email.to_string(),
+            _ => {
+                return Err(Error::BadRequest(
+                    "Provider did not share a verified email address".into(),
+                ))
+            }
+        };
+
+        let user = match UserService::get_by_email(db, None, &email).await {
+            // Иначе чужой аккаунт, заранее зарегистрированный на этот email, достался бы владельцу email
+            Ok(user) if !user.email_verified => return Err(Error::OidcAccountConflict),
+            Ok(user) => user,
+            Err(Error::Core(lib_core::error::Error::EntityNotFound)) => {
+                Self::create_user(db, &claims, &email).await?
+            }
+            Err(e) => return Err(e),
+        };
+
+        IdentityRepo::create(
+            db,
+            IdentityForCreate {
+                user_id: user.id,
+                provider: provider.to_string(),
+                subject: claims.sub,
+                email: Some(email),
+            },
+        )
+        .await?;
+
+        Ok(user)
+    }
+
+    async fn create_user(db: &Db, claims: &IdTokenClaims, email: &str) -> Result<UserDto> {
+        let base = claims
+            .preferred_username
+            .as_deref()
+            .unwrap_or_else(|| email.split('@').next().unwrap_or_default());
+        let base: String = base
+            .chars()
+            .filter(|c| c.is_alphanumeric() || *c == '_')
+            .take(NICKNAME_MAX_LEN - 5)
+            .collect();
+        let base = if base.is_empty() {
+            "user".to_string()
+        } else {
+            base
+        };
+
+        let mut nickname = base.clone();
+        while UserService::get_by_nickname(db, None, &nickname)
+            .await
+            .is_ok()
+        {
+            nickname = format!("{}_{}", base, &generate_opaque_token()[..4]);
+        }
+
+        // Пароль неизвестен никому, при желании его можно задать через сброс пароля
+        let hashed = hash_password(&generate_opaque_token())?;
+        let user = UserService::create(db, None, &nickname, email, &hashed).await?;
+
+        UserService::set_email_verified(db, &user.id).await
+    }
+}
@@ -1,6 +1,8 @@
 use crate::services::user_service::UserDto;
 use chrono::NaiveDateTime;
-use lib_core::acs::{AccessControl, Action, Resource, Role};
+use lib_core::acs::{AccessControl, Action, CommunityRole, Resource, Role};
+use lib_core::ctx::Ctx;
+use lib_core::db::pagination::{Page, PageRequest, Paginated};
 use lib_core::db::Db;
 use lib_core::model::post::{PostForCreate, PostForDelete, PostForSelect, PostForUpdate, PostRepo};
 use serde::Serialize;
@@ -8,6 +10,7 @@ use tracing::warn;
 use uuid::Uuid;
 
 use super::comment_service::CommentService;
+use super::community_member_service::CommunityMemberService;
 use super::community_service::{CommunityDto, CommunityService};
 use super::like_service::LikeService;
 use super::profile_service::ProfileService;
@@ -25,6 +28,7 @@ pub struct PostDto {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub is_deleted: bool,
+    pub is_pinned: bool,
     pub comments_count: u32,
     pub rating: i64,
     pub requester_like: Option<i16>,
@@ -36,29 +40,28 @@ pub struct PostDto {
 pub struct PostService;
 
 impl PostService {
-    pub async fn get_meny_by_query(
-        db: &Db,
-        requester_id: Option<Uuid>,
-        query: &str,
-    ) -> Result<Vec<PostDto>> {
-        let posts = PostRepo::find_many_by_query(db, query)
+    pub async fn get_meny_by_query(db: &Db, ctx: &Ctx, query: &str) -> Result<Vec<PostDto>> {
+        let role = requester_role(db, ctx).await?;
+
+        let posts = PostRepo::find_many_by_query(db, query).await?;
+        let posts = Self::retain_readable(db, ctx, posts)
             .await?
             .into_iter()
             .map(|post| {
                 let db = db.clone();
+                let role = role.clone();
                 async move {
                     Self::check_access(
-                        &db,
-                        requester_id,
+                        ctx,
+                        role,
                         Resource::Post {
                             id: post.id,
                             author_id: post.user_id,
                         },
                         Action::Read,
-                    )
-                    .await?;
+                    )?;
 
-                    Self::convert_to_dto(&db, requester_id, post).await
+                    Self::convert_to_dto(&db, ctx, post).await
                 }
             });
 
@@ -67,21 +70,26 @@ impl PostService {
 
     pub async fn create(
         db: &Db,
-        requester_id: Option<Uuid>,
+        ctx: &Ctx,
         community_id: &Uuid,
         title: &str,
         content: &str,
     ) -> Result<PostDto> {
+        let requester_id = ctx.user_id;
+        let role = requester_role(db, ctx).await?;
+
         Self::check_access(
-            &db,
-            requester_id,
+            ctx,
+            role,
             Resource::Post {
                 id: Uuid::nil(),
                 author_id: Uuid::nil(),
             },
             Action::Create,
-        )
-        .await?;
+        )?;
+
+        UserService::check_email_verified(db, requester_id).await?;
+        CommunityMemberService::check_read(db, ctx, community_id).await?;
 
         let post_fc = PostForCreate {
             user_id: match requester_id {
@@ -93,10 +101,12 @@ impl PostService {
             content: content.to_string(),
         };
         let post = PostRepo::create(db, post_fc).await.map_err(Error::Core)?;
-        Self::convert_to_dto(db, requester_id, post).await
+        Self::convert_to_dto(db, ctx, post).await
     }
 
-    pub async fn get_by_id(db: &Db, requester_id: Option<Uuid>, id: &Uuid) -> Result<PostDto> {
+    pub async fn get_by_id(db: &Db, ctx: &Ctx, id: &Uuid) -> Result<PostDto> {
+        let role = requester_role(db, ctx).await?;
+
         let post_fs = PostForSelect {
             id: Some(*id),
             ..Default::default()
@@ -104,136 +114,204 @@ impl PostService {
         let post = PostRepo::find(db, post_fs).await.map_err(Error::Core)?;
 
         Self::check_access(
-            &db,
-            requester_id,
+            ctx,
+            role,
             Resource::Post {
                 id: post.id,
                 author_id: post.user_id,
             },
             Action::Read,
-        )
-        .await?;
+        )?;
+        CommunityMemberService::check_read(db, ctx, &post.community_id).await?;
 
-        Self::convert_to_dto(db, requester_id, post).await
+        Self::convert_to_dto(db, ctx, post).await
     }
 
-    pub async fn get_many(db: &Db, requester_id: Option<Uuid>) -> Result<Vec<PostDto>> {
+    /// Закрытые сообщества отсеиваются после загрузки страницы, поэтому она может оказаться
+    /// короче запрошенной. Конец списка - только `next_cursor == None`
+    pub async fn get_many(db: &Db, ctx: &Ctx, page: &PageRequest) -> Result<Page<PostDto>> {
+        let role = requester_role(db, ctx).await?;
         let post_fs = PostForSelect {
             is_deleted: Some(false),
             ..Default::default()
         };
-        let posts = PostRepo::find_many(db, post_fs)
+        let Page { items, next_cursor } = PostRepo::find_page(db, post_fs, page)
             .await
-            .map_err(Error::Core)?
+            .map_err(Error::Core)?;
+        let posts = Self::retain_readable(db, ctx, items)
+            .await?
             .into_iter()
             .map(|post| {
                 let db = db.clone();
+                let role = role.clone();
                 async move {
                     Self::check_access(
-                        &db,
-                        requester_id,
+                        ctx,
+                        role,
                         Resource::Post {
                             id: post.id,
                             author_id: post.user_id,
                         },
                         Action::Read,
-                    )
-                    .await?;
+                    )?;
 
-                    Self::convert_to_dto(&db, requester_id, post).await
+                    Self::convert_to_dto(&db, ctx, post).await
                 }
             });
 
-        futures::future::try_join_all(posts).await
+        Ok(Page {
+            items: futures::future::try_join_all(posts).await?,
+            next_cursor,
+        })
     }
 
     pub async fn get_many_by_user_id(
         db: &Db,
-        requester_id: Option<Uuid>,
+        ctx: &Ctx,
         user_id: &Uuid,
-    ) -> Result<Vec<PostDto>> {
-        let user_id = UserService::get_by_id(db, requester_id, user_id)
-            .await
-            .map(|user| user.id)
-            .ok();
+        page: &PageRequest,
+    ) -> Result<Page<PostDto>> {
+        let requester_id = ctx.user_id;
+        let role = requester_role(db, ctx).await?;
+
+        let user = UserService::get_by_id(db, requester_id, user_id).await?;
 
         let post_fs = PostForSelect {
-            user_id,
+            user_id: Some(user.id),
             is_deleted: Some(false),
             ..Default::default()
         };
-        let posts = PostRepo::find_many(db, post_fs)
+        let Page { items, next_cursor } = PostRepo::find_page(db, post_fs, page)
             .await
-            .map_err(Error::Core)?
+            .map_err(Error::Core)?;
+        let posts = Self::retain_readable(db, ctx, items)
+            .await?
             .into_iter()
             .map(|post| {
                 let db = db.clone();
+                let role = role.clone();
                 async move {
                     Self::check_access(
-                        &db,
-                        requester_id,
+                        ctx,
+                        role,
                         Resource::Post {
                             id: post.id,
                             author_id: post.user_id,
                         },
                         Action::Read,
-                    )
-                    .await?;
+                    )?;
 
-                    Self::convert_to_dto(&db, requester_id, post).await
+                    Self::convert_to_dto(&db, ctx, post).await
                 }
             });
 
-        futures::future::try_join_all(posts).await
+        Ok(Page {
+            items: futures::future::try_join_all(posts).await?,
+            next_cursor,
+        })
     }
 
+    /// Сначала страницами идут закрепленные посты, за ними обычные. Курсор на закрепленный
+    /// пост продолжает закрепленные, на обычный - обычные
     pub async fn get_many_by_community_id(
         db: &Db,
-        requester_id: Option<Uuid>,
+        ctx: &Ctx,
         community_id: &Uuid,
-    ) -> Result<Vec<PostDto>> {
-        let community_id = CommunityService::get_by_id(db, requester_id, community_id)
-            .await
-            .map(|community| community.id)
-            .ok();
+        page: &PageRequest,
+    ) -> Result<Page<PostDto>> {
+        let role = requester_role(db, ctx).await?;
 
-        let post_fs = PostForSelect {
+        let community = CommunityService::get_by_id(db, ctx, community_id).await?;
+        CommunityMemberService::check_read(db, ctx, &community.id).await?;
+        let community_id = Some(community.id);
+
+        let pinned_fs = |id| PostForSelect {
+            id,
             community_id,
             is_deleted: Some(false),
+            is_pinned: Some(true),
             ..Default::default()
         };
-        let posts = PostRepo::find_many(db, post_fs)
-            .await
-            .map_err(Error::Core)?
-            .into_iter()
-            .map(|post| {
-                let db = db.clone();
-                async move {
-                    Self::check_access(
-                        &db,
-                        requester_id,
-                        Resource::Post {
-                            id: post.id,
-                            author_id: post.user_id,
-                        },
-                        Action::Read,
-                    )
-                    .await?;
-
-                    Self::convert_to_dto(&db, requester_id, post).await
-                }
-            });
+        let in_pinned = match page.cursor {
+            None => true,
+            Some(cursor) => !PostRepo::find_many(db, pinned_fs(Some(cursor.id)))
+                .await
+                .map_err(Error::Core)?
+                .is_empty(),
+        };
 
-        futures::future::try_join_all(posts).await
+        let mut posts = Vec::new();
+        let mut next_cursor = None;
+        if in_pinned {
+            let pinned = PostRepo::find_page(db, pinned_fs(None), page)
+                .await
+                .map_err(Error::Core)?;
+            posts = pinned.items;
+            next_cursor = pinned.next_cursor;
+        }
+
+        // Закрепленные кончились, остаток страницы занимают обычные посты
+        if next_cursor.is_none() {
+            let post_fs = PostForSelect {
+                community_id,
+                is_deleted: Some(false),
+                is_pinned: Some(false),
+                ..Default::default()
+            };
+            let rest = page.limit() - posts.len() as u64;
+            let rest_page = PageRequest {
+                cursor: if in_pinned { None } else { page.cursor },
+                limit: Some(rest.max(1)),
+            };
+            let regular = PostRepo::find_page(db, post_fs, &rest_page)
+                .await
+                .map_err(Error::Core)?;
+
+            // Места не осталось: обычные начнутся со следующей страницы, если они есть
+            if rest == 0 {
+                next_cursor = match (regular.items.is_empty(), posts.last()) {
+                    (false, Some(last)) => Some(last.cursor()),
+                    _ => None,
+                };
+            } else {
+                next_cursor = regular.next_cursor;
+                posts.extend(regular.items);
+            }
+        }
+
+        let posts = posts.into_iter().map(|post| {
+            let db = db.clone();
+            let role = role.clone();
+            async move {
+                Self::check_access(
+                    ctx,
+                    role,
+                    Resource::Post {
+                        id: post.id,
+                        author_id: post.user_id,
+                    },
+                    Action::Read,
+                )?;
+
+                Self::convert_to_dto(&db, ctx, post).await
+            }
+        });
+
+        Ok(Page {
+            items: futures::future::try_join_all(posts).await?,
+            next_cursor,
+        })
     }
 
     pub async fn update(
         db: &Db,
-        requester_id: Option<Uuid>,
+        ctx: &Ctx,
         id: &Uuid,
         title: Option<String>,
         content: Option<String>,
     ) -> Result<PostDto> {
+        let role = requester_role(db, ctx).await?;
+
         let post_fs = PostForSelect {
             id: Some(*id),
             ..Default::default()
@@ -241,68 +319,118 @@ impl PostService {
         let post = PostRepo::find(db, post_fs).await.map_err(Error::Core)?;
 
         Self::check_access(
-            db,
-            requester_id,
+            ctx,
+            role,
             Resource::Post {
                 id: post.id,
                 author_id: post.user_id,
             },
             Action::Update,
-        )
-        .await?;
+        )?;
 
         let post_fu = PostForUpdate { title, content };
         let post = PostRepo::update(db, id, post_fu)
             .await
             .map_err(Error::Core)?;
-        Self::convert_to_dto(db, requester_id, post).await
+        Self::convert_to_dto(db, ctx, post).await
     }
 
-    pub async fn delete(db: &Db, requester_id: Option<Uuid>, id: &Uuid) -> Result<()> {
+    pub async fn delete(db: &Db, ctx: &Ctx, id: &Uuid) -> Result<()> {
+        let requester_id = ctx.user_id;
+        let role = requester_role(db, ctx).await?;
+
         let post_fs = PostForSelect {
             id: Some(*id),
             ..Default::default()
         };
         let post = PostRepo::find(db, post_fs).await.map_err(Error::Core)?;
+        let community_role =
+            CommunityMemberService::get_role(db, &post.community_id, requester_id).await?;
 
-        Self::check_access(
-            db,
-            requester_id,
+        Self::check_community_access(
+            ctx,
+            role,
+            community_role,
             Resource::Post {
                 id: post.id,
                 author_id: post.user_id,
             },
             Action::Delete,
-        )
-        .await?;
+        )?;
 
         let post_fd = PostForDelete { id: *id };
         let _ = PostRepo::delete(db, post_fd).await.map_err(Error::Core);
         Ok(())
     }
 
-    async fn check_access(
-        db: &Db,
-        requester_id: Option<Uuid>,
-        resource: Resource,
-        action: Action,
-    ) -> Result<()> {
-        let role = match requester_id {
-            Some(id) => get_role(db, &id).await?,
-            None => Role::Guest,
+    /// Закрепление поста в сообществе, доступно его модераторам
+    pub async fn set_pinned(db: &Db, ctx: &Ctx, id: &Uuid, is_pinned: bool) -> Result<PostDto> {
+        let requester_id = ctx.user_id;
+        let role = requester_role(db, ctx).await?;
+
+        let post_fs = PostForSelect {
+            id: Some(*id),
+            ..Default::default()
         };
-        // Ok(())
-        AccessControl::check_access(role, resource, action, requester_id).map_err(|e| {
+        let post = PostRepo::find(db, post_fs).await.map_err(Error::Core)?;
+        let community_role =
+            CommunityMemberService::get_role(db, &post.community_id, requester_id).await?;
+
+        Self::check_community_access(
+            ctx,
+            role,
+            community_role,
+            Resource::Post {
+                id: post.id,
+                author_id: post.user_id,
+            },
+            Action::Pin,
+        )?;
+
+        let post = PostRepo::set_pinned(db, id, is_pinned)
+            .await
+            .map_err(Error::Core)?;
+        Self::convert_to_dto(db, ctx, post).await
+    }
+
+    /// Убирает посты закрытых сообществ, в которых пользователь не состоит
+    async fn retain_readable(db: &Db, ctx: &Ctx, posts: Vec<PostRepo>) -> Result<Vec<PostRepo>> {
+        let readable = CommunityMemberService::readable_communities(
+            db,
+            ctx,
+            posts.iter().map(|post| post.community_id),
+        )
+        .await?;
+
+        Ok(posts
+            .into_iter()
+            .filter(|post| readable.contains(&post.community_id))
+            .collect())
+    }
+
+    fn check_access(ctx: &Ctx, role: Role, resource: Resource, action: Action) -> Result<()> {
+        AccessControl::check_access(ctx, role, resource, action).map_err(|e| {
             warn!("{}", e.to_string());
             Error::Core(e.into())
         })
     }
 
-    async fn convert_to_dto(
-        db: &Db,
-        requester_id: Option<Uuid>,
-        post: PostRepo,
-    ) -> Result<PostDto> {
+    fn check_community_access(
+        ctx: &Ctx,
+        role: Role,
+        community_role: Option<CommunityRole>,
+        resource: Resource,
+        action: Action,
+    ) -> Result<()> {
+        AccessControl::check_community_access(ctx, role, community_role, resource, action).map_err(
+            |e| {
+                warn!("{}", e.to_string());
+                Error::Core(e.into())
+            },
+        )
+    }
+
+    async fn convert_to_dto(db: &Db, ctx: &Ctx, post: PostRepo) -> Result<PostDto> {
         Ok(PostDto {
             id: post.id,
             user_id: post.user_id,
@@ -312,16 +440,26 @@ impl PostService {
             created_at: post.created_at,
             updated_at: post.updated_at,
             is_deleted: post.is_deleted,
-            comments_count: CommentService::get_comments_count(db, requester_id, &post.id).await?,
-            rating: LikeService::get_post_rating(db, requester_id, &post.id).await?,
-            requester_like: LikeService::get_post_like(db, requester_id, &post.id).await?,
-            is_saved: ProfileService::is_saved(db, requester_id, &post.id).await?,
-            user: UserService::get_by_id(db, requester_id, &post.user_id).await?,
-            community: CommunityService::get_by_id(db, requester_id, &post.community_id).await?,
+            is_pinned: post.is_pinned,
+            comments_count: CommentService::get_comments_count(db, ctx.user_id, &post.id).await?,
+            rating: LikeService::get_post_rating(db, ctx.user_id, &post.id).await?,
+            requester_like: LikeService::get_post_like(db, ctx.user_id, &post.id).await?,
+            is_saved: ProfileService::is_saved(db, ctx.user_id, &post.id).await?,
+            user: UserService::get_by_id(db, ctx.user_id, &post.user_id).await?,
+            community: CommunityService::get_by_id(db, &ctx.unscoped(), &post.community_id).await?,
         })
     }
 }
 
+/// Роль из access-токена, а если ее там нет (например, для персональных токенов) - из базы
+pub(crate) async fn requester_role(db: &Db, ctx: &Ctx) -> Result<Role> {
+    match (&ctx.role, ctx.user_id) {
+        (Some(role), _) => Ok(role.clone()),
+        (None, Some(user_id)) => get_role(db, &user_id).await,
+        (None, None) => Ok(Role::Guest),
+    }
+}
+
 pub async fn get_role(db: &Db, user_id: &Uuid) -> Result<Role> {
     UserService::get_by_id(db, None, user_id)
         .await?
@@ -330,3 +468,114 @@ pub async fn get_role(db: &Db, user_id: &Uuid) -> Result<Role> {
         .parse()
         .map_err(|e| Error::Core(lib_core::error::Error::AccessControlSystem(e)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::services::test_utils::{create_user, delete_user, model_manager};
+
+    #[tokio::test]
+    async fn test_private_community_posts_are_hidden_from_non_members() -> anyhow::Result<()> {
+        let mm = model_manager().await?;
+        let owner = create_user(&mm).await?;
+        let outsider = create_user(&mm).await?;
+        let owner_ctx = Ctx::new(owner.id);
+        let outsider_ctx = Ctx::new(outsider.id);
+
+        let community = CommunityService::create(
+            mm.db(),
+            &owner_ctx,
+            &format!("private_{}", &Uuid::new_v4().simple().to_string()[..12]),
+            "test",
+            &true,
+            &false,
+        )
+        .await?;
+        PostService::create(mm.db(), &owner_ctx, &community.id, "title", "content").await?;
+
+        let page = PageRequest::default();
+        let posts =
+            PostService::get_many_by_community_id(mm.db(), &owner_ctx, &community.id, &page)
+                .await?;
+        assert_eq!(posts.items.len(), 1);
+
+        let result =
+            PostService::get_many_by_community_id(mm.db(), &outsider_ctx, &community.id, &page)
+                .await;
+        assert!(matches!(result, Err(Error::NotCommunityMember)));
+
+        // Несуществующее сообщество не превращается в ленту всех постов
+        let result =
+            PostService::get_many_by_community_id(mm.db(), &outsider_ctx, &Uuid::new_v4(), &page)
+                .await;
+        assert!(matches!(
+            result,
+            Err(Error::Core(lib_core::error::Error::EntityNotFound))
+        ));
+
+        let posts =
+            PostService::get_many_by_user_id(mm.db(), &outsider_ctx, &owner.id, &page).await?;
+        assert!(posts.items.is_empty());
+        let result =
+            PostService::get_many_by_user_id(mm.db(), &outsider_ctx, &Uuid::new_v4(), &page).await;
+        assert!(result.is_err());
+
+        CommunityService::delete(mm.db(), &owner_ctx, &community.id).await?;
+        delete_user(&mm, &outsider).await?;
+        delete_user(&mm, &owner).await
+    }
+
+    #[tokio::test]
+    async fn test_pinned_posts_are_paged_before_the_rest() -> anyhow::Result<()> {
+        let mm = model_manager().await?;
+        let owner = create_user(&mm).await?;
+        let ctx = Ctx::new(owner.id);
+
+        let community = CommunityService::create(
+            mm.db(),
+            &ctx,
+            &format!("public_{}", &Uuid::new_v4().simple().to_string()[..12]),
+            "test",
+            &false,
+            &false,
+        )
+        .await?;
+        let mut posts = Vec::new();
+        for i in 0..5 {
+            let post = PostService::create(mm.db(), &ctx, &community.id, &i.to_string(), "content")
+                .await?;
+            posts.push(post.id);
+        }
+        for id in &posts[..3] {
+            PostRepo::set_pinned(mm.db(), id, true).await?;
+        }
+
+        for (limit, sizes) in [(2, vec![2, 2, 1]), (3, vec![3, 2])] {
+            let mut page = PageRequest {
+                cursor: None,
+                limit: Some(limit),
+            };
+            let mut seen = Vec::new();
+            for (i, size) in sizes.iter().enumerate() {
+                let result =
+                    PostService::get_many_by_community_id(mm.db(), &ctx, &community.id, &page)
+                        .await?;
+                assert_eq!(result.items.len(), *size);
+                assert_eq!(result.next_cursor.is_none(), i == sizes.len() - 1);
+                seen.extend(result.items);
+                page.cursor = result.next_cursor;
+            }
+
+            let pinned: Vec<_> = seen.iter().map(|post| post.is_pinned).collect();
+            assert_eq!(pinned, [true, true, true, false, false]);
+            let mut ids: Vec<_> = seen.iter().map(|post| post.id).collect();
+            ids.sort();
+            ids.dedup();
+            assert_eq!(ids.len(), posts.len());
+        }
+
+        CommunityService::delete(mm.db(), &ctx, &community.id).await?;
+        delete_user(&mm, &owner).await
+    }
+}
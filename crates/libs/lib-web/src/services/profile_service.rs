@@ -1,9 +1,13 @@
 use chrono::NaiveDateTime;
+use lib_core::acs::{ScopeAccess, ScopeResource};
+use lib_core::ctx::Ctx;
+use lib_core::db::pagination::{Page, PageRequest};
 use lib_core::db::Db;
 use lib_core::model::save::{SaveForCreate, SaveForDelete, SaveForSelect, SaveRepo};
 use serde::Serialize;
 use uuid::Uuid;
 
+use super::access_service::check_scope;
 use super::post_service::{PostDto, PostService};
 use super::user_service::UserDto;
 
@@ -23,8 +27,10 @@ pub struct SaveDto {
 pub struct ProfileService;
 
 impl ProfileService {
-    pub async fn get_saves(db: &Db, requester_id: Option<Uuid>) -> Result<Vec<SaveDto>> {
-        let user_id = match requester_id {
+    pub async fn get_saves(db: &Db, ctx: &Ctx, page: &PageRequest) -> Result<Page<SaveDto>> {
+        check_scope(ctx, ScopeResource::Posts, ScopeAccess::Read)?;
+
+        let user_id = match ctx.user_id {
             Some(id) => id,
             None => return Err(Error::Unauthorized),
         };
@@ -33,35 +39,43 @@ impl ProfileService {
             ..Default::default()
         };
 
-        let saves = SaveRepo::find_many(db, save_fs)
-            .await?
-            .into_iter()
-            .map(|save| {
-                let db = db.clone();
-                async move {
-                    let post = PostService::get_by_id(&db, requester_id, &save.post_id).await?;
-                    let user = UserService::get_by_id(&db, requester_id, &user_id).await?;
-                    Ok(SaveDto {
-                        id: save.id,
-                        user_id,
-                        user,
-                        post,
-                        post_id: save.post_id,
-                        created_at: save.created_at,
-                    })
-                }
-            });
-
-        futures::future::try_join_all(saves).await
+        let Page { items, next_cursor } = SaveRepo::find_page(db, save_fs, page).await?;
+        let saves = items.into_iter().map(|save| {
+            let db = db.clone();
+            async move {
+                // Посты закрытых сообществ, из которых пользователь вышел, пропускаются
+                let post = match PostService::get_by_id(&db, ctx, &save.post_id).await {
+                    Ok(post) => post,
+                    Err(Error::NotCommunityMember) => return Ok(None),
+                    Err(e) => return Err(e),
+                };
+                let user = UserService::get_by_id(&db, ctx.user_id, &user_id).await?;
+                Ok(Some(SaveDto {
+                    id: save.id,
+                    user_id,
+                    user,
+                    post,
+                    post_id: save.post_id,
+                    created_at: save.created_at,
+                }))
+            }
+        });
+
+        Ok(Page {
+            items: futures::future::try_join_all(saves)
+                .await?
+                .into_iter()
+                .flatten()
+                .collect(),
+            next_cursor,
+        })
     }
 
-    pub async fn create_save(
-        db: &Db,
-        requester_id: Option<Uuid>,
-        post_id: &Uuid,
-    ) -> Result<SaveDto> {
+    pub async fn create_save(db: &Db, ctx: &Ctx, post_id: &Uuid) -> Result<SaveDto> {
+        check_scope(ctx, ScopeResource::Posts, ScopeAccess::Write)?;
+
         let save_fc = SaveForCreate {
-            user_id: match requester_id {
+            user_id: match ctx.user_id {
                 Some(id) => id,
                 None => return Err(Error::Unauthorized),
             },
@@ -69,8 +83,8 @@ impl ProfileService {
         };
 
         let save = SaveRepo::create(db, save_fc).await?;
-        let post = PostService::get_by_id(&db, requester_id, &save.post_id).await?;
-        let user = UserService::get_by_id(&db, requester_id, &save.user_id).await?;
+        let post = PostService::get_by_id(&db, ctx, &save.post_id).await?;
+        let user = UserService::get_by_id(&db, ctx.user_id, &save.user_id).await?;
         Ok(SaveDto {
             id: save.id,
             user_id: save.user_id,
@@ -81,9 +95,11 @@ impl ProfileService {
         })
     }
 
-    pub async fn delete_save(db: &Db, requester_id: Option<Uuid>, post_id: &Uuid) -> Result<()> {
+    pub async fn delete_save(db: &Db, ctx: &Ctx, post_id: &Uuid) -> Result<()> {
+        check_scope(ctx, ScopeResource::Posts, ScopeAccess::Write)?;
+
         let save_fd = SaveForDelete {
-            user_id: match requester_id {
+            user_id: match ctx.user_id {
                 Some(id) => id,
                 None => return Err(Error::Unauthorized),
             },
@@ -1,14 +1,19 @@
 use chrono::NaiveDateTime;
+use lib_core::acs::{AccessControl, AccessDenied, Action, Resource, ScopeAccess, ScopeResource};
+use lib_core::ctx::Ctx;
+use lib_core::db::pagination::{Page, PageRequest};
 use lib_core::db::Db;
 use lib_core::model::report::{
     ReportForCreate, ReportForDelete, ReportForSelect, ReportForUpdate, ReportRepo,
     ReportStatusType, ReportTargetType,
 };
 use serde::Serialize;
+use tracing::warn;
 use uuid::Uuid;
 
+use super::access_service::check_scope;
 use super::comment_service::{CommentDto, CommentService};
-use super::post_service::{PostDto, PostService};
+use super::post_service::{requester_role, PostDto, PostService};
 use super::user_service::{UserDto, UserService};
 
 use crate::error::{Error, Result};
@@ -34,108 +39,130 @@ pub struct ReportService;
 impl ReportService {
     pub async fn create(
         db: &Db,
+        ctx: &Ctx,
         report_type: ReportTargetType,
         reported_id: &Uuid,
-        reporter_id: &Uuid,
         reason: Option<String>,
     ) -> Result<ReportDto> {
+        let reporter_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+        Self::check_access(
+            db,
+            ctx,
+            Resource::Report {
+                id: Uuid::nil(),
+                reporter_id,
+            },
+            Action::Create,
+        )
+        .await?;
+
         let report_fc = ReportForCreate {
             report_type,
             reported_id: *reported_id,
-            reporter_id: *reporter_id,
+            reporter_id,
             reason,
         };
 
         let report = ReportRepo::create(db, report_fc)
             .await
             .map_err(Error::Core)?;
-        Self::convert_to_dto(db, report).await
+        Self::convert_to_dto(db, ctx, report).await
+    }
+
+    pub async fn get_by_id(db: &Db, ctx: &Ctx, id: &Uuid) -> Result<ReportDto> {
+        let report = find_report(db, id).await?;
+        Self::check_access(db, ctx, resource(&report), Action::Read).await?;
+
+        Self::convert_to_dto(db, ctx, report).await
     }
 
-    pub async fn get_by_id(db: &Db, id: &Uuid) -> Result<ReportDto> {
+    /// Все жалобы, доступно модераторам
+    pub async fn get_many(db: &Db, ctx: &Ctx, page: &PageRequest) -> Result<Page<ReportDto>> {
+        Self::check_can_list(db, ctx).await?;
+
         let report_fs = ReportForSelect {
-            id: Some(*id),
             ..Default::default()
         };
-        let report = ReportRepo::find(db, report_fs).await.map_err(Error::Core)?;
-        Self::convert_to_dto(db, report).await
+        let reports = ReportRepo::find_page(db, report_fs, page)
+            .await
+            .map_err(Error::Core)?;
+        Self::convert_page(db, ctx, reports).await
     }
 
-    pub async fn get_many(db: &Db) -> Result<Vec<ReportDto>> {
+    pub async fn get_many_by_reported_id(
+        db: &Db,
+        ctx: &Ctx,
+        reported_id: &Uuid,
+        page: &PageRequest,
+    ) -> Result<Page<ReportDto>> {
+        Self::check_can_list(db, ctx).await?;
+
         let report_fs = ReportForSelect {
+            reported_id: Some(*reported_id),
             ..Default::default()
         };
-        let reports = ReportRepo::find_many(db, report_fs)
+        let reports = ReportRepo::find_page(db, report_fs, page)
             .await
-            .map_err(Error::Core)?
-            .into_iter()
-            .map(|report| {
-                let db = db.clone();
-                async move { Self::convert_to_dto(&db, report).await }
-            });
-
-        futures::future::try_join_all(reports).await
+            .map_err(Error::Core)?;
+        Self::convert_page(db, ctx, reports).await
     }
 
-    pub async fn get_many_by_reported_id(db: &Db, reported_id: &Uuid) -> Result<Vec<ReportDto>> {
-        let reports = ReportRepo::find_many(
+    /// Пользователь видит свои жалобы, модераторы - жалобы любого пользователя
+    pub async fn get_many_by_reporter_id(
+        db: &Db,
+        ctx: &Ctx,
+        reporter_id: &Uuid,
+        page: &PageRequest,
+    ) -> Result<Page<ReportDto>> {
+        Self::check_access(
             db,
-            ReportForSelect {
-                reported_id: Some(*reported_id),
-                ..Default::default()
+            ctx,
+            Resource::Report {
+                id: Uuid::nil(),
+                reporter_id: *reporter_id,
             },
+            Action::Read,
         )
-        .await
-        .map_err(Error::Core)?
-        .into_iter()
-        .map(|report| {
-            let db = db.clone();
-            async move { Self::convert_to_dto(&db, report).await }
-        });
-
-        futures::future::try_join_all(reports).await
-    }
+        .await?;
 
-    pub async fn get_many_by_reporter_id(db: &Db, reporter_id: &Uuid) -> Result<Vec<ReportDto>> {
         let report_fs = ReportForSelect {
             reporter_id: Some(*reporter_id),
             ..Default::default()
         };
-        let reports = ReportRepo::find_many(db, report_fs)
+        let reports = ReportRepo::find_page(db, report_fs, page)
             .await
-            .map_err(Error::Core)?
-            .into_iter()
-            .map(|report| {
-                let db = db.clone();
-                async move { Self::convert_to_dto(&db, report).await }
-            });
-
-        futures::future::try_join_all(reports).await
+            .map_err(Error::Core)?;
+        Self::convert_page(db, ctx, reports).await
     }
 
-    pub async fn get_many_by_status(db: &Db, status: ReportStatusType) -> Result<Vec<ReportDto>> {
+    pub async fn get_many_by_status(
+        db: &Db,
+        ctx: &Ctx,
+        status: ReportStatusType,
+        page: &PageRequest,
+    ) -> Result<Page<ReportDto>> {
+        Self::check_can_list(db, ctx).await?;
+
         let report_fs = ReportForSelect {
             status: Some(status),
             ..Default::default()
         };
-        let reports = ReportRepo::find_many(db, report_fs)
+        let reports = ReportRepo::find_page(db, report_fs, page)
             .await
-            .map_err(Error::Core)?
-            .into_iter()
-            .map(|report| {
-                let db = db.clone();
-                async move { Self::convert_to_dto(&db, report).await }
-            });
-
-        futures::future::try_join_all(reports).await
+            .map_err(Error::Core)?;
+        Self::convert_page(db, ctx, reports).await
     }
 
     pub async fn update_status(
         db: &Db,
+        ctx: &Ctx,
         id: &Uuid,
         status: ReportStatusType,
         reason: Option<String>,
     ) -> Result<ReportDto> {
+        let report = find_report(db, id).await?;
+        Self::check_access(db, ctx, resource(&report), Action::Update).await?;
+
         let report_fu = ReportForUpdate {
             status: Some(status),
             reason,
@@ -144,32 +171,82 @@ impl ReportService {
         let report = ReportRepo::update(db, id, report_fu)
             .await
             .map_err(Error::Core)?;
-        Self::convert_to_dto(db, report).await
+        Self::convert_to_dto(db, ctx, report).await
     }
 
-    pub async fn delete(db: &Db, id: &Uuid) -> Result<()> {
+    pub async fn delete(db: &Db, ctx: &Ctx, id: &Uuid) -> Result<()> {
+        let report = find_report(db, id).await?;
+        Self::check_access(db, ctx, resource(&report), Action::Delete).await?;
+
         let report_fd = ReportForDelete { id: *id };
         ReportRepo::delete(db, report_fd).await.map_err(Error::Core)
     }
 
-    async fn convert_to_dto(db: &Db, report: ReportRepo) -> Result<ReportDto> {
-        let reporter = UserService::get_by_id(db, None, &report.reporter_id).await?;
+    async fn check_access(db: &Db, ctx: &Ctx, resource: Resource, action: Action) -> Result<()> {
+        let role = requester_role(db, ctx).await?;
+
+        AccessControl::check_access(ctx, role, resource, action).map_err(|e| {
+            warn!("{}", e.to_string());
+            Error::Core(e.into())
+        })
+    }
+
+    /// Доступ ко всем жалобам сразу. Проверяется пробной жалобой без id, поэтому отказ
+    /// в журнал не пишется: указать в нем нечего
+    async fn check_can_list(db: &Db, ctx: &Ctx) -> Result<()> {
+        check_scope(ctx, ScopeResource::Reports, ScopeAccess::Read)?;
+        let role = requester_role(db, ctx).await?;
+
+        if AccessControl::can_access(ctx, role.clone(), None, any_report(), Action::Read) {
+            Ok(())
+        } else {
+            warn!("User {:?} cannot list all reports", ctx.user_id);
+            Err(Error::Core(
+                lib_core::acs::Error::from(AccessDenied {
+                    role,
+                    resource: any_report(),
+                    action: Action::Read,
+                })
+                .into(),
+            ))
+        }
+    }
 
+    async fn convert_page(db: &Db, ctx: &Ctx, page: Page<ReportRepo>) -> Result<Page<ReportDto>> {
+        let reports = page
+            .items
+            .into_iter()
+            .map(|report| Self::convert_to_dto(db, ctx, report));
+
+        Ok(Page {
+            items: futures::future::try_join_all(reports).await?,
+            next_cursor: page.next_cursor,
+        })
+    }
+
+    async fn convert_to_dto(db: &Db, ctx: &Ctx, report: ReportRepo) -> Result<ReportDto> {
+        let reporter = UserService::get_by_id(db, ctx.user_id, &report.reporter_id).await?;
+
+        // Содержимое закрытых сообществ видно только тем, кто может его читать
         let (reported_post, reported_comment, reported_user) = match report.report_type {
             ReportTargetType::Post => (
-                Some(PostService::get_by_id(db, None, &report.reported_id).await?),
+                unless_private(
+                    PostService::get_by_id(db, &ctx.unscoped(), &report.reported_id).await,
+                )?,
                 None,
                 None,
             ),
             ReportTargetType::Comment => (
                 None,
-                Some(CommentService::get_by_id(db, None, &report.reported_id).await?),
+                unless_private(
+                    CommentService::get_by_id(db, &ctx.unscoped(), &report.reported_id).await,
+                )?,
                 None,
             ),
             ReportTargetType::User => (
                 None,
                 None,
-                Some(UserService::get_by_id(db, None, &report.reported_id).await?),
+                Some(UserService::get_by_id(db, ctx.user_id, &report.reported_id).await?),
             ),
         };
 
@@ -189,3 +266,34 @@ impl ReportService {
         })
     }
 }
+
+fn resource(report: &ReportRepo) -> Resource {
+    Resource::Report {
+        id: report.id,
+        reporter_id: report.reporter_id,
+    }
+}
+
+/// Жалоба без автора: проходит проверку только у тех, кто видит все жалобы
+fn any_report() -> Resource {
+    Resource::Report {
+        id: Uuid::nil(),
+        reporter_id: Uuid::nil(),
+    }
+}
+
+pub(crate) async fn find_report(db: &Db, id: &Uuid) -> Result<ReportRepo> {
+    let report_fs = ReportForSelect {
+        id: Some(*id),
+        ..Default::default()
+    };
+    ReportRepo::find(db, report_fs).await.map_err(Error::Core)
+}
+
+fn unless_private<T>(result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::NotCommunityMember) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,91 @@
+use chrono::NaiveDateTime;
+use lib_core::db::Db;
+use lib_core::model::session::{SessionForDelete, SessionForSelect, SessionRepo};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+#[derive(Debug, Serialize)]
+pub struct SessionDto {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub is_current: bool,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: NaiveDateTime,
+}
+
+impl SessionDto {
+    pub fn from_session(session: SessionRepo, current_token: Option<&str>) -> Self {
+        Self {
+            is_current: current_token == Some(session.refresh_token.as_str()),
+            id: session.id,
+            user_agent: session.user_agent,
+            ip: session.ip,
+            created_at: session.created_at,
+            last_used_at: session.last_used_at,
+        }
+    }
+}
+
+/// Обертка для работы с сессиями (устройствами) пользователя
+pub struct SessionService;
+
+impl SessionService {
+    /// Получение всех активных сессий текущего пользователя
+    pub async fn get_all(
+        db: &Db,
+        requester_id: Option<Uuid>,
+        current_token: Option<&str>,
+    ) -> Result<Vec<SessionDto>> {
+        let user_id = requester_id.ok_or(Error::Unauthorized)?;
+
+        let mut sessions = SessionRepo::find_all(
+            db,
+            SessionForSelect {
+                user_id: Some(user_id),
+                ..Default::default()
+            },
+        )
+        .await?;
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.last_used_at));
+
+        Ok(sessions
+            .into_iter()
+            .map(|s| SessionDto::from_session(s, current_token))
+            .collect())
+    }
+
+    /// Завершение одной сессии текущего пользователя
+    pub async fn delete(db: &Db, requester_id: Option<Uuid>, id: &Uuid) -> Result<()> {
+        let user_id = requester_id.ok_or(Error::Unauthorized)?;
+
+        SessionRepo::delete(
+            db,
+            SessionForDelete {
+                id: Some(*id),
+                user_id: Some(user_id),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(Error::Core)
+    }
+
+    /// Завершение всех сессий пользователя
+    pub async fn delete_all(db: &Db, user_id: &Uuid) -> Result<()> {
+        match SessionRepo::delete(
+            db,
+            SessionForDelete {
+                user_id: Some(*user_id),
+                ..Default::default()
+            },
+        )
+        .await
+        {
+            Ok(_) | Err(lib_core::error::Error::EntityNotFound) => Ok(()),
+            Err(e) => Err(Error::Core(e)),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+//! Fixtures for service tests. They run against the database and cache from `.env`.
+
+use std::sync::Arc;
+
+use lib_core::mail::MemoryMailer;
+use lib_core::model::user::UserRepo;
+use lib_core::model::ModelManager;
+use uuid::Uuid;
+
+use super::user_service::{UserDto, UserService};
+
+pub async fn model_manager() -> anyhow::Result<Arc<ModelManager>> {
+    dotenvy::from_path(std::path::Path::new("../../.env")).ok();
+
+    Ok(Arc::new(ModelManager::new().await?))
+}
+
+/// Same as `model_manager`, but sent mail is kept so tests can follow links from it
+pub async fn model_manager_with_mailer() -> anyhow::Result<(Arc<ModelManager>, Arc<MemoryMailer>)> {
+    dotenvy::from_path(std::path::Path::new("../../.env")).ok();
+
+    let mailer = Arc::new(MemoryMailer::new());
+    let mm = ModelManager::with_mailer(mailer.clone()).await?;
+    Ok((Arc::new(mm), mailer))
+}
+
+/// A fresh user with a verified email and a random nickname
+pub async fn create_user(mm: &ModelManager) -> anyhow::Result<UserDto> {
+    let nickname = format!("test_{}", &Uuid::new_v4().simple().to_string()[..12]);
+    let email = format!("{nickname}@example.com");
+
+    let user = UserService::create(mm.db(), None, &nickname, &email, "not-a-hash").await?;
+    Ok(UserService::set_email_verified(mm.db(), &user.id).await?)
+}
+
+pub async fn delete_user(mm: &ModelManager, user: &UserDto) -> anyhow::Result<()> {
+    Ok(UserRepo::delete(mm.db(), &user.id).await?)
+}
@@ -0,0 +1,264 @@
+use chrono::Utc;
+use lib_auth::pwd::validate_password;
+use lib_auth::token::{generate_token, verify_token, TokenClaims, TokenType};
+use lib_auth::totp::{
+    generate_recovery_codes, generate_secret, hash_recovery_code, is_sealed, open_secret,
+    otpauth_uri, seal_secret, verify_code,
+};
+use lib_core::cache::{redis_fns, Cache};
+use lib_core::db::Db;
+use lib_core::model::recovery_code::{
+    RecoveryCodeForCreate, RecoveryCodeForDelete, RecoveryCodeRepo,
+};
+use lib_core::model::totp::{TotpForCreate, TotpForDelete, TotpForSelect, TotpForUpdate, TotpRepo};
+use lib_utils::time::utc_now_plus_sec_usize;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::config::web_config;
+use crate::error::{Error, Result};
+
+use super::user_service::UserService;
+
+const RECOVERY_CODES_COUNT: usize = 10;
+const CHALLENGE_PREFIX: &str = "2fa:challenge:";
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorSetupDto {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+/// Обертка для работы с двухфакторной аутентификацией (TOTP)
+pub struct TwoFactorService;
+
+impl TwoFactorService {
+    /// Включена ли двухфакторная аутентификация у пользователя
+    pub async fn is_enabled(db: &Db, user_id: &Uuid) -> Result<bool> {
+        match Self::find_enabled(db, user_id).await {
+            Ok(_) => Ok(true),
+            Err(Error::Core(lib_core::error::Error::EntityNotFound)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Создание нового секрета, 2FA включается только после подтверждения кодом
+    pub async fn setup(db: &Db, requester_id: Option<Uuid>) -> Result<TwoFactorSetupDto> {
+        let user_id = requester_id.ok_or(Error::Unauthorized)?;
+        let user = UserService::get_by_id(db, requester_id, &user_id).await?;
+
+        if Self::is_enabled(db, &user_id).await? {
+            return Err(Error::BadRequest(
+                "Two-factor authentication is already enabled".into(),
+            ));
+        }
+        Self::delete_totp(db, &user_id).await?;
+
+        let secret = generate_secret();
+        TotpRepo::create(
+            db,
+            TotpForCreate {
+                user_id,
+                secret: seal_secret(&secret, user_id.as_bytes())?,
+            },
+        )
+        .await?;
+
+        let otpauth_uri = otpauth_uri(&secret, &user.email, web_config().app_name());
+        Ok(TwoFactorSetupDto {
+            secret,
+            otpauth_uri,
+        })
+    }
+
+    /// Подтверждение секрета первым кодом, возвращает одноразовые коды восстановления
+    pub async fn confirm(db: &Db, requester_id: Option<Uuid>, code: &str) -> Result<Vec<String>> {
+        let user_id = requester_id.ok_or(Error::Unauthorized)?;
+
+        let totp = TotpRepo::find(
+            db,
+            TotpForSelect {
+                user_id: Some(user_id),
+                enabled: Some(false),
+            },
+        )
+        .await
+        .map_err(|_| Error::BadRequest("Two-factor setup was not started".into()))?;
+
+        Self::check_code(db, &totp, code).await?;
+
+        TotpRepo::update(
+            db,
+            &totp.id,
+            TotpForUpdate {
+                enabled: Some(true),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Self::regenerate_recovery_codes(db, &user_id).await
+    }
+
+    /// Отключение 2FA, требует пароль и действующий код
+    pub async fn disable(
+        db: &Db,
+        requester_id: Option<Uuid>,
+        password: &str,
+        code: &str,
+    ) -> Result<()> {
+        let user_id = requester_id.ok_or(Error::Unauthorized)?;
+        let user = UserService::get_by_id(db, requester_id, &user_id).await?;
+
+        if !validate_password(password, &user.hashed_password)? {
+            return Err(Error::WrongPassword);
+        }
+        Self::verify(db, &user_id, code).await?;
+
+        Self::delete_totp(db, &user_id).await?;
+        Self::delete_recovery_codes(db, &user_id).await
+    }
+
+    /// Токен-вызов после верного пароля. Он действует, пока его jti лежит в кеше
+    pub async fn issue_challenge(cache: &Cache, user_id: &Uuid) -> Result<String> {
+        let token = generate_token(&user_id.to_string(), TokenType::TwoFactor)?;
+        let claims = verify_token(&token, TokenType::TwoFactor)?.claims;
+
+        let ttl = claims.exp.saturating_sub(utc_now_plus_sec_usize(0)).max(1);
+        redis_fns::set(cache, &challenge_key(&claims), true, Some(ttl)).await?;
+
+        Ok(token)
+    }
+
+    /// Проверка токена-вызова без погашения, чтобы опечатка в коде не сжигала вход
+    pub async fn check_challenge(cache: &Cache, token: &str) -> Result<Uuid> {
+        let claims = verify_token(token, TokenType::TwoFactor)
+            .map_err(|_| Error::InvalidToken)?
+            .claims;
+
+        if !redis_fns::exists(cache, &challenge_key(&claims)).await? {
+            return Err(Error::InvalidToken);
+        }
+
+        claims.sub.parse().map_err(|_| Error::InvalidToken)
+    }
+
+    /// Погашение токена-вызова после верного кода, второй раз он не примется
+    pub async fn consume_challenge(cache: &Cache, token: &str) -> Result<()> {
+        let claims = verify_token(token, TokenType::TwoFactor)
+            .map_err(|_| Error::InvalidToken)?
+            .claims;
+
+        redis_fns::take::<bool>(cache, &challenge_key(&claims))
+            .await?
+            .ok_or(Error::InvalidToken)?;
+
+        Ok(())
+    }
+
+    /// Проверка второго фактора: TOTP-код или код восстановления
+    pub async fn verify(db: &Db, user_id: &Uuid, code: &str) -> Result<()> {
+        let totp = Self::find_enabled(db, user_id).await?;
+
+        if code.trim().len() == lib_auth::totp::DIGITS as usize {
+            return Self::check_code(db, &totp, code.trim()).await;
+        }
+
+        match RecoveryCodeRepo::delete(
+            db,
+            RecoveryCodeForDelete {
+                user_id: Some(*user_id),
+                code_hash: Some(hash_recovery_code(code)),
+            },
+        )
+        .await
+        {
+            Ok(_) => Ok(()),
+            Err(lib_core::error::Error::EntityNotFound) => Err(Error::InvalidTwoFactorCode),
+            Err(e) => Err(Error::Core(e)),
+        }
+    }
+
+    async fn check_code(db: &Db, totp: &TotpRepo, code: &str) -> Result<()> {
+        let secret = open_secret(&totp.secret, totp.user_id.as_bytes())?;
+
+        let now = Utc::now().timestamp() as u64;
+        let step = verify_code(&secret, code, now)?.ok_or(Error::InvalidTwoFactorCode)?;
+
+        if !TotpRepo::use_step(db, &totp.id, step as i64).await? {
+            return Err(Error::InvalidTwoFactorCode);
+        }
+
+        // Секреты, сохраненные до шифрования, шифруются при первом использовании
+        if !is_sealed(&totp.secret) {
+            TotpRepo::update(
+                db,
+                &totp.id,
+                TotpForUpdate {
+                    secret: Some(seal_secret(&secret, totp.user_id.as_bytes())?),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn find_enabled(db: &Db, user_id: &Uuid) -> Result<TotpRepo> {
+        let totp = TotpRepo::find(
+            db,
+            TotpForSelect {
+                user_id: Some(*user_id),
+                enabled: Some(true),
+            },
+        )
+        .await?;
+
+        Ok(totp)
+    }
+
+    async fn regenerate_recovery_codes(db: &Db, user_id: &Uuid) -> Result<Vec<String>> {
+        Self::delete_recovery_codes(db, user_id).await?;
+
+        let codes = generate_recovery_codes(RECOVERY_CODES_COUNT);
+        for code in &codes {
+            RecoveryCodeRepo::create(
+                db,
+                RecoveryCodeForCreate {
+                    user_id: *user_id,
+                    code_hash: hash_recovery_code(code),
+                },
+            )
+            .await?;
+        }
+
+        Ok(codes)
+    }
+
+    async fn delete_totp(db: &Db, user_id: &Uuid) -> Result<()> {
+        match TotpRepo::delete(db, TotpForDelete { user_id: *user_id }).await {
+            Ok(_) | Err(lib_core::error::Error::EntityNotFound) => Ok(()),
+            Err(e) => Err(Error::Core(e)),
+        }
+    }
+
+    async fn delete_recovery_codes(db: &Db, user_id: &Uuid) -> Result<()> {
+        match RecoveryCodeRepo::delete(
+            db,
+            RecoveryCodeForDelete {
+                user_id: Some(*user_id),
+                ..Default::default()
+            },
+        )
+        .await
+        {
+            Ok(_) | Err(lib_core::error::Error::EntityNotFound) => Ok(()),
+            Err(e) => Err(Error::Core(e)),
+        }
+    }
+}
+
+fn challenge_key(claims: &TokenClaims) -> String {
+    format!("{}{}", CHALLENGE_PREFIX, claims.jti)
+}
@@ -1,4 +1,7 @@
 use chrono::NaiveDateTime;
+use lib_core::acs::{AccessControl, Action, Resource, Role};
+use lib_core::ctx::Ctx;
+use lib_core::db::pagination::{Page, PageRequest};
 use lib_core::db::Db;
 use lib_core::model::role::RoleEnum;
 use lib_core::model::user::{UserForCreate, UserForSelect, UserForUpdate, UserRepo};
@@ -6,6 +9,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::error::{Error, Result};
+use crate::services::{ban_service::access_denied, post_service::requester_role};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UserDto {
@@ -14,6 +18,7 @@ pub struct UserDto {
     pub role: RoleEnum,
     pub email: String,
     pub is_banned: bool,
+    pub email_verified: bool,
     #[serde(skip)]
     pub hashed_password: String,
     pub created_at: NaiveDateTime,
@@ -31,6 +36,7 @@ impl UserDto {
             updated_at: user.updated_at,
             hashed_password: user.hashed_password,
             is_banned: user.is_banned,
+            email_verified: user.email_verified,
         }
     }
 }
@@ -83,6 +89,18 @@ impl UserService {
         Ok(UserDto::from_user(user))
     }
 
+    /// Создавать посты и комментарии могут только пользователи с подтвержденным email
+    pub async fn check_email_verified(db: &Db, requester_id: Option<Uuid>) -> Result<()> {
+        let user_id = requester_id.ok_or(Error::Unauthorized)?;
+        let user = Self::get_by_id(db, requester_id, &user_id).await?;
+
+        if !user.email_verified {
+            return Err(Error::EmailNotVerified);
+        }
+
+        Ok(())
+    }
+
     /// Получение пользователя по email
     pub async fn get_by_email(
         db: &Db,
@@ -125,7 +143,6 @@ impl UserService {
         _requester_id: Option<Uuid>,
         id: &Uuid,
         nickname: Option<String>,
-        email: Option<String>,
         role: Option<RoleEnum>,
         hashed_password: Option<String>,
         is_banned: Option<bool>,
@@ -135,10 +152,65 @@ impl UserService {
             id,
             UserForUpdate {
                 nickname,
-                email,
                 role,
                 hashed_password,
                 is_banned,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Ok(UserDto::from_user(user))
+    }
+
+    /// Обновление профиля по запросу пользователя: менять роль может только администратор
+    pub async fn update_profile(
+        db: &Db,
+        ctx: &Ctx,
+        id: &Uuid,
+        nickname: Option<String>,
+        role: Option<RoleEnum>,
+    ) -> Result<UserDto> {
+        let requester_role = requester_role(db, ctx).await?;
+        AccessControl::check_access(
+            ctx,
+            requester_role.clone(),
+            Resource::User(*id),
+            Action::Update,
+        )
+        .map_err(|e| Error::Core(lib_core::error::Error::AccessControlSystem(e)))?;
+
+        if role.is_some() && requester_role != Role::Admin {
+            return Err(access_denied(ctx, requester_role, *id, Action::Update));
+        }
+
+        Self::update(db, ctx.user_id, id, nickname, role, None, None).await
+    }
+
+    /// Подтверждение email пользователя
+    pub async fn set_email_verified(db: &Db, id: &Uuid) -> Result<UserDto> {
+        let user = UserRepo::update(
+            db,
+            id,
+            UserForUpdate {
+                email_verified: Some(true),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Ok(UserDto::from_user(user))
+    }
+
+    /// Смена email пользователя после подтверждения нового адреса
+    pub async fn update_email(db: &Db, id: &Uuid, email: &str) -> Result<UserDto> {
+        let user = UserRepo::update(
+            db,
+            id,
+            UserForUpdate {
+                email: Some(email.to_string()),
+                email_verified: Some(true),
+                ..Default::default()
             },
         )
         .await?;
@@ -152,9 +224,16 @@ impl UserService {
     }
 
     /// Получение списка всех пользователей
-    pub async fn get_all(db: &Db, _requester_id: Option<Uuid>) -> Result<Vec<UserDto>> {
-        let users = UserRepo::find_all(db, UserForSelect::default()).await?;
-        Ok(users.into_iter().map(UserDto::from_user).collect())
+    pub async fn get_all(
+        db: &Db,
+        _requester_id: Option<Uuid>,
+        page: &PageRequest,
+    ) -> Result<Page<UserDto>> {
+        let users = UserRepo::find_page(db, UserForSelect::default(), page).await?;
+        Ok(Page {
+            items: users.items.into_iter().map(UserDto::from_user).collect(),
+            next_cursor: users.next_cursor,
+        })
     }
 
     /// Получение пользователей по роли
@@ -175,16 +254,71 @@ impl UserService {
         Ok(users.into_iter().map(UserDto::from_user).collect())
     }
 
-    pub async fn get_banned(db: &Db, _requester_id: Option<Uuid>) -> Result<Vec<UserDto>> {
-        let users = UserRepo::find_all(
+    pub async fn get_banned(
+        db: &Db,
+        _requester_id: Option<Uuid>,
+        page: &PageRequest,
+    ) -> Result<Page<UserDto>> {
+        let users = UserRepo::find_page(
             db,
             UserForSelect {
                 is_banned: Some(true),
                 ..Default::default()
             },
+            page,
         )
         .await?;
 
-        Ok(users.into_iter().map(UserDto::from_user).collect())
+        Ok(Page {
+            items: users.items.into_iter().map(UserDto::from_user).collect(),
+            next_cursor: users.next_cursor,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::services::test_utils::{create_user, delete_user, model_manager};
+
+    #[tokio::test]
+    async fn test_only_admin_changes_roles() -> anyhow::Result<()> {
+        let mm = model_manager().await?;
+        let user = create_user(&mm).await?;
+        let other = create_user(&mm).await?;
+        let ctx = Ctx::new(user.id);
+
+        let result =
+            UserService::update_profile(mm.db(), &ctx, &user.id, None, Some(RoleEnum::Admin)).await;
+        assert!(matches!(result, Err(Error::Core(_))));
+        let result =
+            UserService::update_profile(mm.db(), &ctx, &other.id, Some("x".into()), None).await;
+        assert!(matches!(result, Err(Error::Core(_))));
+        let unchanged = UserService::get_by_id(mm.db(), None, &user.id).await?;
+        assert!(matches!(unchanged.role, RoleEnum::User));
+
+        let admin = UserService::update(
+            mm.db(),
+            None,
+            &other.id,
+            None,
+            Some(RoleEnum::Admin),
+            None,
+            None,
+        )
+        .await?;
+        let promoted = UserService::update_profile(
+            mm.db(),
+            &Ctx::new(admin.id),
+            &user.id,
+            None,
+            Some(RoleEnum::Moderator),
+        )
+        .await?;
+        assert!(matches!(promoted.role, RoleEnum::Moderator));
+
+        delete_user(&mm, &user).await?;
+        delete_user(&mm, &admin).await
     }
 }
@@ -0,0 +1,62 @@
+use lib_auth::token::generate_opaque_token;
+use lib_core::acs::{ScopeAccess, ScopeResource};
+use lib_core::cache::{redis_fns, Cache};
+use lib_core::ctx::Ctx;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::services::access_service::check_scope;
+use crate::services::ban_service::BanService;
+
+const WS_TICKET_PREFIX: &str = "ws:ticket:";
+const WS_TICKET_TTL_SEC: usize = 30;
+
+/// Обертка для одноразовых билетов на подключение к WebSocket,
+/// т.к. браузер не умеет передавать заголовок Authorization при апгрейде
+pub struct WsTicketService;
+
+impl WsTicketService {
+    /// Выпуск билета для текущего пользователя. Сокеты по билету не ограничены скоупами,
+    /// поэтому персональному токену нужен `chats:write`
+    pub async fn issue(cache: &Cache, ctx: &Ctx) -> Result<String> {
+        let user_id = ctx.user_id.ok_or(Error::Unauthorized)?;
+        check_scope(ctx, ScopeResource::Chats, ScopeAccess::Write)?;
+
+        let ticket = generate_opaque_token();
+        let key = format!("{}{}", WS_TICKET_PREFIX, ticket);
+        redis_fns::set(cache, &key, &user_id, Some(WS_TICKET_TTL_SEC)).await?;
+
+        Ok(ticket)
+    }
+
+    /// Погашение билета, второй раз он уже не сработает
+    pub async fn redeem(cache: &Cache, ticket: &str) -> Result<Option<Uuid>> {
+        let key = format!("{}{}", WS_TICKET_PREFIX, ticket);
+        let user_id = redis_fns::take::<Uuid>(cache, &key).await?;
+
+        Ok(user_id)
+    }
+
+    /// Пользователь сокета: из токена (заголовок или подпротокол), иначе из билета.
+    /// Заблокированным пользователям подключение запрещено
+    pub async fn resolve_user(
+        cache: &Cache,
+        ctx: &Ctx,
+        ticket: Option<&str>,
+        access: ScopeAccess,
+    ) -> Result<Uuid> {
+        let user_id = match (ctx.user_id, ticket) {
+            (Some(user_id), _) => {
+                check_scope(ctx, ScopeResource::Chats, access)?;
+                user_id
+            }
+            (None, Some(ticket)) => Self::redeem(cache, ticket)
+                .await?
+                .ok_or(Error::InvalidToken)?,
+            (None, None) => return Err(Error::Unauthorized),
+        };
+        BanService::check_cached(cache, &user_id).await?;
+
+        Ok(user_id)
+    }
+}
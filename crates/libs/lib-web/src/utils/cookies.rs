@@ -1,18 +1,49 @@
 use axum_extra::extract::cookie::Cookie;
 use axum_extra::extract::CookieJar;
+use lib_auth::token::generate_csrf_token;
 use time::Duration;
 
+use crate::config::web_config;
+
+pub const REFRESH_COOKIE: &str = "refreshToken";
+/// Readable by the frontend, which echoes it in `CSRF_HEADER` on cookie-authenticated requests
+pub const CSRF_COOKIE: &str = "csrfToken";
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Sets the refresh token together with a fresh CSRF token bound to it
 pub fn set_refresh_cookie(jar: CookieJar, token: &str) -> CookieJar {
-    let cookie = Cookie::build(("refreshToken", token.to_string()))
-        .path("/")
-        .max_age(Duration::days(30))
-        .http_only(true)
-        .secure(false)
-        .build();
-    jar.add(cookie)
+    let mut refresh_cookie = cookie(REFRESH_COOKIE, token.to_string());
+    refresh_cookie.set_max_age(Duration::days(30));
+    refresh_cookie.set_http_only(true);
+
+    let mut csrf_cookie = cookie(CSRF_COOKIE, generate_csrf_token());
+    csrf_cookie.set_max_age(Duration::days(30));
+    csrf_cookie.set_http_only(false);
+
+    jar.add(refresh_cookie).add(csrf_cookie)
+}
+
+pub fn remove_refresh_cookie(jar: CookieJar) -> CookieJar {
+    let jar = remove_cookie_from_jar(jar, REFRESH_COOKIE);
+    remove_cookie_from_jar(jar, CSRF_COOKIE)
 }
 
 pub fn remove_cookie_from_jar(jar: CookieJar, name: &str) -> CookieJar {
-    let name = name.to_string();
-    jar.remove(Cookie::build((name, "")).path("/").build())
+    // Removal only works with the same path and domain the cookie was set with
+    jar.remove(cookie(name, String::new()))
+}
+
+fn cookie(name: &str, value: String) -> Cookie<'static> {
+    let config = web_config();
+
+    let mut cookie = Cookie::build((name.to_string(), value))
+        .path("/")
+        .secure(config.cookie_secure())
+        .same_site(config.cookie_same_site())
+        .build();
+    if let Some(domain) = config.cookie_domain() {
+        cookie.set_domain(domain.to_string());
+    }
+
+    cookie
 }
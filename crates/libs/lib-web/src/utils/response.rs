@@ -1,4 +1,8 @@
-use axum::{http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
 use axum_extra::extract::CookieJar;
 use serde::Serialize;
 
@@ -16,6 +20,9 @@ pub struct ApiError {
     status: u16,
     message: String,
     error: Option<String>,
+    /// Значение заголовка Retry-After для ответов 429
+    #[serde(skip)]
+    retry_after: Option<u64>,
 }
 
 pub enum ApiResponse<T> {
@@ -45,10 +52,16 @@ impl<T> ApiResponse<T> {
     }
 
     pub fn error(message: &str, error: Error) -> Self {
+        let retry_after = match error {
+            Error::TooManyAttempts(secs) => Some(secs),
+            _ => None,
+        };
+
         ApiResponse::Error(ApiError {
             status: error.status_code(),
             message: message.to_string(),
             error: Some(error.to_string()),
+            retry_after,
         })
     }
 }
@@ -68,8 +81,30 @@ impl<T: Serialize> IntoResponse for ApiResponse<T> {
             ApiResponse::Error(error) => {
                 let status_code =
                     StatusCode::from_u16(error.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
-                (status_code, Json(error)).into_response()
+                match error.retry_after {
+                    Some(secs) => (
+                        status_code,
+                        [(header::RETRY_AFTER, secs.to_string())],
+                        Json(error),
+                    )
+                        .into_response(),
+                    None => (status_code, Json(error)).into_response(),
+                }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_too_many_attempts_sets_retry_after() {
+        let response =
+            ApiResponse::<()>::error("Login failed", Error::TooManyAttempts(42)).into_response();
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "42");
+    }
+}
@@ -1,11 +1,43 @@
 use crate::services::user_service::UserDto;
-use lib_auth::token::{generate_token, TokenType};
+use axum::http::{
+    header::{AUTHORIZATION, SEC_WEBSOCKET_PROTOCOL},
+    HeaderMap,
+};
+use lib_auth::token::{generate_access_token, generate_refresh_token};
+use uuid::Uuid;
 
 use crate::error::Result;
 
-pub(crate) fn generate_tokens_for_auth(user: &UserDto) -> Result<(String, String)> {
-    let access_token = generate_token(&user.id.to_string(), TokenType::Access)?;
-    let refresh_token = generate_token(&user.id.to_string(), TokenType::Refresh)?;
+pub(crate) fn generate_tokens_for_auth(
+    user: &UserDto,
+    session_id: &Uuid,
+    generation: i32,
+) -> Result<(String, String)> {
+    let access_token = generate_access_token(&user.id.to_string(), user.role.as_str())?;
+    let refresh_token =
+        generate_refresh_token(&user.id.to_string(), &session_id.to_string(), generation)?;
 
     Ok((access_token, refresh_token))
 }
+
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+}
+
+/// Subprotocol marking the next one as an access token: `Sec-WebSocket-Protocol: access_token, <token>`.
+/// Browsers cannot set `Authorization` on a WebSocket upgrade, but they can list subprotocols.
+pub const WS_TOKEN_PROTOCOL: &str = "access_token";
+
+pub(crate) fn ws_protocol_token(headers: &HeaderMap) -> Option<&str> {
+    let mut protocols = headers
+        .get(SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|h| h.to_str().ok())?
+        .split(',')
+        .map(str::trim);
+
+    protocols.find(|protocol| *protocol == WS_TOKEN_PROTOCOL)?;
+    protocols.next()
+}
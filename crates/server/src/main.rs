@@ -1,19 +1,23 @@
-use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
 
 use axum::{
-    http::{self, header, HeaderValue},
+    http::{self, header, HeaderName, HeaderValue},
     Router,
 };
 use lib_core::model::ModelManager;
-use lib_web::handlers::AppState;
+use lib_web::config::web_config;
+use lib_web::handlers::{handlers_auth, AppState};
+use lib_web::services::ban_service::BanService;
+use lib_web::utils::cookies::CSRF_HEADER;
 use routes::{
-    routes_auth, routes_chat, routes_comment, routes_community, routes_like, routes_post,
-    routes_profile, routes_report, routes_search, routes_user, routes_ws,
+    routes_admin, routes_auth, routes_chat, routes_comment, routes_community, routes_like,
+    routes_post, routes_profile, routes_report, routes_search, routes_user, routes_ws,
 };
 use tokio::sync::Mutex;
-use tower_http::cors::CorsLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 
 static PORT: u16 = 3030;
+const ACS_POLICY_RELOAD_SEC: u64 = 5;
 
 mod routes;
 
@@ -24,7 +28,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with_max_level(tracing::Level::DEBUG)
         .init();
 
+    lib_auth::token::init_signing_keys()?;
+    lib_auth::pwd::init_breached_list()?;
+    lib_core::acs::init_policy()?;
+    lib_core::acs::watch_policy(Duration::from_secs(ACS_POLICY_RELOAD_SEC));
+
     let mm = Arc::new(ModelManager::new().await?);
+    lib_core::acs::record_denials(mm.db().clone());
+    BanService::warm_cache(&mm).await?;
 
     let state = Arc::new(AppState {
         mm: mm.clone(),
@@ -32,6 +43,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         chat_conns: Arc::new(Mutex::new(HashMap::new())),
     });
 
+    let admin_app = routes_admin::routes(mm.clone()).await;
     let auth_app = routes_auth::routes(mm.clone()).await;
     let user_app = routes_user::routes(mm.clone()).await;
     let community_app = routes_community::routes(mm.clone()).await;
@@ -46,6 +58,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let app = Router::new()
         .route("/", axum::routing::get(ping))
+        .route(
+            "/.well-known/jwks.json",
+            axum::routing::get(handlers_auth::get_jwks),
+        )
+        .nest("/api/admin", admin_app)
         .nest("/api/auth", auth_app)
         .nest("/api/users", user_app)
         .nest("/api/communities", community_app)
@@ -59,7 +76,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .nest("/api/reports", report_app)
         .layer(
             CorsLayer::new()
-                .allow_origin(HeaderValue::from_static("http://localhost:5173"))
+                .allow_origin(AllowOrigin::list(
+                    web_config()
+                        .cors_allowed_origins()
+                        .iter()
+                        .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+                ))
                 .allow_credentials(true)
                 .allow_methods([
                     http::Method::GET,
@@ -68,7 +90,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     http::Method::PATCH,
                     http::Method::DELETE,
                 ])
-                .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION]),
+                .allow_headers([
+                    header::CONTENT_TYPE,
+                    header::AUTHORIZATION,
+                    HeaderName::from_static(CSRF_HEADER),
+                ]),
         );
 
     let addr = std::net::SocketAddr::from(([0, 0, 0, 0], PORT));
@@ -1,3 +1,4 @@
+pub mod routes_admin;
 pub mod routes_auth;
 pub mod routes_chat;
 pub mod routes_comment;
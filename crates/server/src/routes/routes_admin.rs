@@ -0,0 +1,17 @@
+use std::sync::Arc;
+
+use axum::{middleware, routing::get, Router};
+use lib_core::model::ModelManager;
+use lib_web::{handlers::handlers_admin, middlewares};
+
+pub async fn routes(mm: Arc<ModelManager>) -> Router {
+    Router::new()
+        .route("/access/explain", get(handlers_admin::explain_access))
+        .route("/access/denials", get(handlers_admin::get_access_denials))
+        .with_state(mm.clone())
+        .layer(middleware::from_fn(middlewares::require_session))
+        .layer(middleware::from_fn_with_state(
+            mm.clone(),
+            middlewares::require_auth,
+        ))
+}
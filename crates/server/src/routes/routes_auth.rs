@@ -2,21 +2,118 @@ use std::sync::Arc;
 
 use axum::{
     middleware,
-    routing::{get, post},
+    routing::{delete, get, post},
     Router,
 };
 use lib_core::model::ModelManager;
 use lib_web::{handlers::handlers_auth, middlewares};
 
 pub async fn routes(mm: Arc<ModelManager>) -> Router {
+    let require_auth = middleware::from_fn_with_state(mm.clone(), middlewares::require_auth);
+    let require_csrf = middleware::from_fn(middlewares::require_csrf);
+    // Personal access tokens never reach account and session management
+    let require_session = middleware::from_fn(middlewares::require_session);
+
     Router::new()
         .route("/register", post(handlers_auth::register))
         .route("/login", post(handlers_auth::login))
-        .route("/logout", post(handlers_auth::logout))
-        .route("/refresh", post(handlers_auth::refresh))
+        .route(
+            "/logout",
+            post(handlers_auth::logout).layer(require_csrf.clone()),
+        )
+        .route(
+            "/refresh",
+            post(handlers_auth::refresh).layer(require_csrf.clone()),
+        )
+        .route("/forgot-password", post(handlers_auth::forgot_password))
+        .route("/reset-password", post(handlers_auth::reset_password))
+        .route(
+            "/change-password",
+            post(handlers_auth::change_password)
+                .layer(require_session.clone())
+                .layer(require_auth.clone()),
+        )
+        .route("/magic-link", post(handlers_auth::request_magic_link))
+        .route(
+            "/magic-link/{token}",
+            get(handlers_auth::check_magic_link).post(handlers_auth::magic_link_login),
+        )
+        .route("/verify-email", post(handlers_auth::verify_email))
+        .route(
+            "/verify-email/resend",
+            post(handlers_auth::resend_email_verification)
+                .layer(require_session.clone())
+                .layer(require_auth.clone()),
+        )
+        .route(
+            "/change-email",
+            post(handlers_auth::request_email_change)
+                .layer(require_session.clone())
+                .layer(require_auth.clone()),
+        )
+        .route(
+            "/change-email/confirm",
+            post(handlers_auth::confirm_email_change),
+        )
+        .route("/oidc/providers", get(handlers_auth::get_oidc_providers))
+        .route(
+            "/oidc/{provider}/authorize",
+            get(handlers_auth::oidc_authorize),
+        )
+        .route(
+            "/oidc/{provider}/callback",
+            post(handlers_auth::oidc_callback),
+        )
+        .route("/2fa/verify", post(handlers_auth::verify_two_factor))
+        .route(
+            "/2fa/setup",
+            post(handlers_auth::setup_two_factor)
+                .layer(require_session.clone())
+                .layer(require_auth.clone()),
+        )
+        .route(
+            "/2fa/confirm",
+            post(handlers_auth::confirm_two_factor)
+                .layer(require_session.clone())
+                .layer(require_auth.clone()),
+        )
+        .route(
+            "/2fa/disable",
+            post(handlers_auth::disable_two_factor)
+                .layer(require_session.clone())
+                .layer(require_auth.clone()),
+        )
+        .route(
+            "/sessions",
+            get(handlers_auth::get_sessions)
+                .delete(handlers_auth::logout_all)
+                .layer(require_session.clone())
+                .layer(require_auth.clone()),
+        )
+        .route(
+            "/sessions/{id}",
+            delete(handlers_auth::delete_session)
+                .layer(require_session.clone())
+                .layer(require_auth.clone()),
+        )
+        .route(
+            "/tokens",
+            get(handlers_auth::get_api_tokens)
+                .post(handlers_auth::create_api_token)
+                .layer(require_session.clone())
+                .layer(require_auth.clone()),
+        )
+        .route(
+            "/tokens/{id}",
+            delete(handlers_auth::delete_api_token)
+                .layer(require_session.clone())
+                .layer(require_auth.clone()),
+        )
         .route(
             "/me",
-            get(handlers_auth::auth_me).layer(middleware::from_fn(middlewares::require_auth)),
+            get(handlers_auth::auth_me)
+                .layer(require_session.clone())
+                .layer(require_auth.clone()),
         )
         .with_state(mm)
 }
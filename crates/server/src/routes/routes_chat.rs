@@ -37,6 +37,9 @@ pub async fn routes(state: Arc<AppState>) -> Router {
         .route("/messages/{id}", put(handlers_messages::update_message))
         .route("/messages/{id}", delete(handlers_messages::delete_message))
         .route("/messages/{id}/read", post(handlers_messages::read_message))
-        .with_state(state)
-        .layer(middleware::from_fn(middlewares::require_auth))
+        .with_state(state.clone())
+        .layer(middleware::from_fn_with_state(
+            state.mm.clone(),
+            middlewares::require_auth,
+        ))
 }
@@ -18,6 +18,9 @@ pub async fn routes(state: Arc<AppState>) -> Router {
         .route("/{id}/thread", get(handlers_comment::get_comment_thread))
         .route("/{id}", put(handlers_comment::update_comment))
         .route("/{id}", delete(handlers_comment::delete_comment))
-        .with_state(state)
-        .layer(middleware::from_fn(middlewares::require_auth))
+        .with_state(state.clone())
+        .layer(middleware::from_fn_with_state(
+            state.mm.clone(),
+            middlewares::require_auth,
+        ))
 }
@@ -18,10 +18,46 @@ pub async fn routes(mm: Arc<ModelManager>) -> Router {
         .route("/{name}", get(handlers_community::get))
         .route("/{name}", delete(handlers_community::delete))
         .route("/{name}", put(handlers_community::update))
+        // moderators
+        .route("/{id}/moderators", get(handlers_community::get_moderators))
+        .route(
+            "/{id}/moderators",
+            post(handlers_community::appoint_moderator),
+        )
+        .route(
+            "/{id}/moderators/{user_id}",
+            delete(handlers_community::remove_moderator),
+        )
+        // members
+        .route("/{id}/members", get(handlers_community::get_members))
+        .route("/{id}/members", post(handlers_community::add_member))
+        .route(
+            "/{id}/members/{user_id}",
+            delete(handlers_community::remove_member),
+        )
+        .route(
+            "/{id}/join-requests",
+            get(handlers_community::get_join_requests),
+        )
+        .route(
+            "/{id}/join-requests",
+            post(handlers_community::create_join_request),
+        )
+        .route(
+            "/{id}/join-requests/{request_id}/approve",
+            post(handlers_community::approve_join_request),
+        )
+        .route(
+            "/{id}/join-requests/{request_id}/deny",
+            post(handlers_community::deny_join_request),
+        )
         // follows
         .route("/{id}/follow", post(handlers_follow::follow))
         .route("/{id}/unfollow", delete(handlers_follow::unfollow))
         .route("/{id}/follow/check", get(handlers_follow::is_followd))
-        .layer(middleware::from_fn(middlewares::require_auth))
+        .layer(middleware::from_fn_with_state(
+            mm.clone(),
+            middlewares::require_auth,
+        ))
         .with_state(mm)
 }
@@ -15,6 +15,9 @@ pub async fn routes(state: Arc<AppState>) -> Router {
         .route("/like", post(handlers_like::like))
         .route("/dislike", post(handlers_like::dislike))
         .route("/unlike", delete(handlers_like::unlike))
-        .with_state(state)
-        .layer(middleware::from_fn(middlewares::require_auth))
+        .with_state(state.clone())
+        .layer(middleware::from_fn_with_state(
+            state.mm.clone(),
+            middlewares::require_auth,
+        ))
 }
@@ -17,6 +17,8 @@ pub async fn routes(state: Arc<AppState>) -> Router {
         .route("/{id}", get(handlers_post::get_post))
         .route("/{id}", put(handlers_post::update_post))
         .route("/{id}", delete(handlers_post::delete_post))
+        .route("/{id}/pin", post(handlers_post::pin_post))
+        .route("/{id}/pin", delete(handlers_post::unpin_post))
         // comment
         .route("/{id}/comments", get(handlers_comment::get_post_comments))
         .route("/{id}/comments", post(handlers_comment::create_comment))
@@ -28,6 +30,9 @@ pub async fn routes(state: Arc<AppState>) -> Router {
             "/{post_id}/comments/{comment_id}",
             delete(handlers_comment::delete_comment),
         )
-        .with_state(state)
-        .layer(middleware::from_fn(middlewares::require_auth))
+        .with_state(state.clone())
+        .layer(middleware::from_fn_with_state(
+            state.mm.clone(),
+            middlewares::require_auth,
+        ))
 }
@@ -13,6 +13,9 @@ pub async fn routes(mm: Arc<ModelManager>) -> Router {
         .route("/saves", get(handlers_profile::get_saves))
         .route("/saves", post(handlers_profile::create_save))
         .route("/saves", delete(handlers_profile::delete_save))
-        .with_state(mm)
-        .layer(middleware::from_fn(middlewares::require_auth))
+        .with_state(mm.clone())
+        .layer(middleware::from_fn_with_state(
+            mm.clone(),
+            middlewares::require_auth,
+        ))
 }
@@ -17,6 +17,9 @@ pub async fn routes(state: Arc<AppState>) -> Router {
         .route("/{id}", get(handlers_report::get_report))
         .route("/{id}", put(handlers_report::update_report_status))
         .route("/{id}", delete(handlers_report::delete_report))
-        .with_state(state)
-        .layer(middleware::from_fn(middlewares::require_auth))
+        .with_state(state.clone())
+        .layer(middleware::from_fn_with_state(
+            state.mm.clone(),
+            middlewares::require_auth,
+        ))
 }
@@ -9,6 +9,9 @@ use lib_web::{
 pub async fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/", get(handlers_search::search))
-        .with_state(state)
-        .layer(middleware::from_fn(middlewares::require_auth))
+        .with_state(state.clone())
+        .layer(middleware::from_fn_with_state(
+            state.mm.clone(),
+            middlewares::require_auth,
+        ))
 }
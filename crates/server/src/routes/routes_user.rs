@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use axum::{
     middleware,
-    routing::{delete, get, put},
+    routing::{delete, get, post, put},
     Router,
 };
 use lib_core::model::ModelManager;
@@ -14,6 +14,24 @@ pub async fn routes(mm: Arc<ModelManager>) -> Router {
         .route("/{nickname}", get(handlers_user::get_user_profile))
         .route("/{nickname}", put(handlers_user::update_user_profile))
         .route("/{nickname}", delete(handlers_user::delete_user_profile))
-        .with_state(mm)
-        .layer(middleware::from_fn(middlewares::require_auth))
+        .route("/{nickname}/ban", post(handlers_user::ban_user))
+        .route("/{nickname}/ban", delete(handlers_user::unban_user))
+        .route("/{nickname}/bans", get(handlers_user::get_user_bans))
+        .route(
+            "/{nickname}/impersonate",
+            post(handlers_user::impersonate_user),
+        )
+        .route(
+            "/{nickname}/impersonations",
+            get(handlers_user::get_user_impersonations),
+        )
+        .route(
+            "/impersonations/{id}",
+            delete(handlers_user::end_impersonation),
+        )
+        .with_state(mm.clone())
+        .layer(middleware::from_fn_with_state(
+            mm.clone(),
+            middlewares::require_auth,
+        ))
 }
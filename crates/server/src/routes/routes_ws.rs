@@ -1,14 +1,26 @@
 use std::sync::Arc;
 
-use axum::{routing::any, Router};
-use lib_web::handlers::{handlers_notification, ws_handlers_chat, AppState};
+use axum::{
+    middleware,
+    routing::{any, post},
+    Router,
+};
+use lib_web::{
+    handlers::{handlers_notification, handlers_ws, ws_handlers_chat, AppState},
+    middlewares,
+};
 
 pub async fn routes(state: Arc<AppState>) -> Router {
     Router::new()
+        .route("/ticket", post(handlers_ws::create_ticket))
         .route("/chat/{id}", any(ws_handlers_chat::ws_handler))
         .route(
             "/notifications",
             any(handlers_notification::notification_ws_handler),
         )
-        .with_state(state)
+        .with_state(state.clone())
+        .layer(middleware::from_fn_with_state(
+            state.mm.clone(),
+            middlewares::require_auth,
+        ))
 }
@@ -0,0 +1,7 @@
+-- Add migration script here
+ALTER TABLE users ADD COLUMN IF NOT EXISTS email_verified BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Accounts created before verification existed are treated as verified
+UPDATE users SET email_verified = TRUE;
+
+ALTER TABLE user_tokens ADD COLUMN IF NOT EXISTS created_at TIMESTAMP NOT NULL DEFAULT NOW();
@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE user_tokens ADD COLUMN IF NOT EXISTS payload TEXT;
@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE user_sessions ADD COLUMN IF NOT EXISTS generation INT NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TYPE token_type_enum ADD VALUE IF NOT EXISTS 'magic_link';
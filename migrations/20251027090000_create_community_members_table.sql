@@ -0,0 +1,23 @@
+-- Add migration script here
+CREATE TYPE community_role_enum AS ENUM ('owner', 'moderator', 'member');
+
+CREATE TABLE IF NOT EXISTS community_members (
+    community_id UUID NOT NULL REFERENCES communities(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role community_role_enum NOT NULL DEFAULT 'member',
+    joined_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY(community_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_community_members_user_id ON community_members(user_id);
+
+-- Owners come from communities, followers become members
+INSERT INTO community_members (community_id, user_id, role)
+SELECT id, user_id, 'owner' FROM communities;
+
+INSERT INTO community_members (community_id, user_id, role)
+SELECT DISTINCT community_id, user_id, 'member'::community_role_enum FROM follows
+WHERE community_id IS NOT NULL AND user_id IS NOT NULL
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS is_pinned BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,19 @@
+-- Add migration script here
+ALTER TABLE communities ADD COLUMN IF NOT EXISTS is_invite_only BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TYPE join_request_status_enum AS ENUM ('pending', 'approved', 'denied');
+
+CREATE TABLE IF NOT EXISTS community_join_requests (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    community_id UUID NOT NULL REFERENCES communities(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    message TEXT,
+    status join_request_status_enum NOT NULL DEFAULT 'pending',
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    decided_at TIMESTAMP,
+    decided_by UUID REFERENCES users(id) ON DELETE SET NULL
+);
+
+-- One open request per user and community
+CREATE UNIQUE INDEX IF NOT EXISTS idx_community_join_requests_pending
+    ON community_join_requests(community_id, user_id) WHERE status = 'pending';
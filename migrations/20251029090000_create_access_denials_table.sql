@@ -0,0 +1,13 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS access_denials (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role TEXT NOT NULL,
+    resource TEXT NOT NULL,
+    resource_id UUID NOT NULL,
+    action TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_access_denials_user_id ON access_denials(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_access_denials_created_at ON access_denials(created_at DESC);
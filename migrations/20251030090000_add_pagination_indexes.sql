@@ -0,0 +1,18 @@
+-- Add migration script here
+CREATE INDEX IF NOT EXISTS idx_posts_page ON posts(created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS idx_posts_user_page ON posts(user_id, created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS idx_posts_community_page ON posts(community_id, created_at DESC, id DESC);
+
+CREATE INDEX IF NOT EXISTS idx_comments_user_page ON comments(user_id, created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS idx_comments_post_page ON comments(post_id, created_at DESC, id DESC);
+
+CREATE INDEX IF NOT EXISTS idx_messages_chat_page ON messages(chat_id, created_at DESC, id DESC);
+
+CREATE INDEX IF NOT EXISTS idx_communities_page ON communities(created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS idx_communities_user_page ON communities(user_id, created_at DESC, id DESC);
+
+CREATE INDEX IF NOT EXISTS idx_users_page ON users(created_at DESC, id DESC);
+
+CREATE INDEX IF NOT EXISTS idx_user_saves_user_page ON user_saves(user_id, created_at DESC, id DESC);
+
+CREATE INDEX IF NOT EXISTS idx_reports_page ON reports(created_at DESC, id DESC);